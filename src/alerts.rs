@@ -0,0 +1,305 @@
+use crate::derived::lookup_reading;
+use crate::expression::{Expression, Value};
+use crate::views::AttributeReadings;
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+// An alert rules file lists one rule per line as `name = condition [for duration]`.
+// The condition is an expression over watched attributes, see `expression.rs`, and
+// the alert fires once it has held for the duration:
+//
+// # Comments start with a hash
+// too_hot = sys/tg_test/1/double_scalar > 100 for 5s
+// fault = sys/tg_test/1/state == "FAULT"
+// unreadable = error(sys/tg_test/1/long_scalar) for 30s
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    pub condition: Expression,
+    pub hold: Duration,
+}
+
+// `500ms`, `5s`, `2m` or `1h`
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let split_ix = duration.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = duration.split_at(split_ix);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    Some(Duration::from_secs_f64(seconds))
+}
+
+pub fn parse_rules(content: &str) -> Result<Vec<AlertRule>, Box<dyn Error>> {
+    let mut rules: Vec<AlertRule> = Vec::new();
+    for (line_ix, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_no = line_ix + 1;
+        let (name, definition) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Line {}: expected name = condition", line_no))?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(anyhow!("Line {}: invalid rule name [{}]", line_no, name).into());
+        }
+        if rules.iter().any(|rule| rule.name == name) {
+            return Err(anyhow!("Line {}: duplicate rule name [{}]", line_no, name).into());
+        }
+        let (source, hold) = match definition.rsplit_once(" for ") {
+            Some((source, hold)) => (
+                source,
+                parse_duration(hold.trim()).ok_or_else(|| {
+                    anyhow!("Line {}: invalid duration [{}]", line_no, hold.trim())
+                })?,
+            ),
+            None => (definition, Duration::ZERO),
+        };
+        let condition =
+            Expression::parse(source.trim()).map_err(|err| anyhow!("Line {}: {}", line_no, err))?;
+        rules.push(AlertRule {
+            name: name.to_string(),
+            condition,
+            hold,
+        });
+    }
+    Ok(rules)
+}
+
+// How fired alerts are announced besides the banner and the log
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AlertNotify {
+    pub bell: bool,
+    pub osc9: bool,
+}
+
+impl AlertNotify {
+    // A comma separated list of `bell`, `osc9` or `none`
+    pub fn parse(methods: &str) -> Result<AlertNotify, Box<dyn Error>> {
+        let mut notify = AlertNotify::default();
+        for method in methods.split(',').map(str::trim) {
+            match method {
+                "bell" => notify.bell = true,
+                "osc9" => notify.osc9 = true,
+                "none" | "" => {}
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown notification [{}], expected bell, osc9 or none",
+                        method
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(notify)
+    }
+
+    // Written straight to the terminal, OSC 9 shows a desktop notification
+    // in terminals that support it and is ignored by the others.
+    pub fn send(&self, message: &str) {
+        let mut stdout = io::stdout();
+        if self.osc9 {
+            let message: String = message.chars().filter(|c| !c.is_control()).collect();
+            let _ = write!(stdout, "\x1b]9;{}\x07", message);
+        }
+        if self.bell {
+            let _ = write!(stdout, "\x07");
+        }
+        let _ = stdout.flush();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveAlert {
+    pub name: String,
+    pub condition: String,
+    pub fired_at: SystemTime,
+    pub acknowledged: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertTransition {
+    Fired(ActiveAlert),
+    Cleared(ActiveAlert),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Alerts {
+    pub rules: Vec<AlertRule>,
+    pub notify: AlertNotify,
+    // When the condition of a rule that has not fired yet started to hold
+    pending: BTreeMap<String, SystemTime>,
+    pub active: Vec<ActiveAlert>,
+}
+
+impl Alerts {
+    pub fn load(path: &str, notify: AlertNotify) -> Result<Alerts, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Could not read alert rules file {}: {}", path, err))?;
+        Ok(Alerts {
+            rules: parse_rules(&content)?,
+            notify,
+            ..Alerts::default()
+        })
+    }
+
+    // Check the rules against the latest readings, a condition that is not a
+    // boolean or cannot be evaluated does not hold
+    pub fn check(&mut self, attr_map: &AttributeReadings, now: SystemTime) -> Vec<AlertTransition> {
+        let mut transitions = Vec::new();
+        for rule in &self.rules {
+            let holds = matches!(
+                rule.condition
+                    .evaluate(&|full_name| lookup_reading(attr_map, full_name)),
+                Ok(Value::Bool(true))
+            );
+            let active_ix = self.active.iter().position(|alert| alert.name == rule.name);
+            match (holds, active_ix) {
+                (true, None) => {
+                    let since = *self.pending.entry(rule.name.clone()).or_insert(now);
+                    if now.duration_since(since).unwrap_or_default() >= rule.hold {
+                        self.pending.remove(&rule.name);
+                        let alert = ActiveAlert {
+                            name: rule.name.clone(),
+                            condition: rule.condition.source.clone(),
+                            fired_at: now,
+                            acknowledged: false,
+                        };
+                        self.active.push(alert.clone());
+                        transitions.push(AlertTransition::Fired(alert));
+                    }
+                }
+                (false, Some(active_ix)) => {
+                    transitions.push(AlertTransition::Cleared(self.active.remove(active_ix)));
+                }
+                (false, None) => {
+                    self.pending.remove(&rule.name);
+                }
+                (true, Some(_)) => {}
+            }
+        }
+        transitions
+    }
+
+    pub fn unacknowledged(&self) -> Vec<&ActiveAlert> {
+        self.active
+            .iter()
+            .filter(|alert| !alert.acknowledged)
+            .collect()
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.active
+            .iter_mut()
+            .for_each(|alert| alert.acknowledged = true);
+    }
+}
+
+#[cfg(test)]
+mod alerts_tests {
+    use super::*;
+    use crate::views::watchlist::{AttributeReading, Reading};
+    use tango_controls_client_sys::types::AttrValue;
+
+    fn readings(value: f64) -> AttributeReadings {
+        let mut attr_map = AttributeReadings::default();
+        attr_map
+            .entry("sys/tg_test/1".to_string())
+            .or_default()
+            .insert(
+                "double_scalar".to_string(),
+                AttributeReading::Value(Reading {
+                    data: Some(AttrValue::Double(value)),
+                    ..Reading::default()
+                }),
+            );
+        attr_map
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules(
+            "
+            # A comment
+            too_hot = sys/tg_test/1/double_scalar > 100 for 1.5s
+            fault = sys/tg_test/1/state == \"FAULT\"
+            ",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].name, "too_hot");
+        assert_eq!(
+            rules[0].condition.source,
+            "sys/tg_test/1/double_scalar > 100"
+        );
+        assert_eq!(rules[0].hold, Duration::from_millis(1500));
+        assert_eq!(rules[1].hold, Duration::ZERO);
+
+        let errors = vec![
+            ("no condition", "Line 1: expected name = condition"),
+            ("a b = true", "Line 1: invalid rule name [a b]"),
+            ("a = true for 5 days", "Line 1: invalid duration [5 days]"),
+            ("a = true\na = false", "Line 2: duplicate rule name [a]"),
+            ("a = 1 +", "Line 1: Unexpected end"),
+        ];
+        for (content, expected) in errors {
+            let err = parse_rules(content).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{}: {}", content, err);
+        }
+    }
+
+    #[test]
+    fn test_check_rules() {
+        let mut alerts = Alerts {
+            rules: parse_rules("too_hot = sys/tg_test/1/double_scalar > 100 for 5s").unwrap(),
+            ..Alerts::default()
+        };
+        let start = SystemTime::UNIX_EPOCH;
+
+        assert!(alerts.check(&readings(101.0), start).is_empty());
+        assert!(alerts
+            .check(&readings(101.0), start + Duration::from_secs(4))
+            .is_empty());
+        // Dropping below restarts the hold time
+        assert!(alerts
+            .check(&readings(99.0), start + Duration::from_secs(5))
+            .is_empty());
+        assert!(alerts
+            .check(&readings(101.0), start + Duration::from_secs(6))
+            .is_empty());
+        let transitions = alerts.check(&readings(101.0), start + Duration::from_secs(11));
+        assert!(
+            matches!(&transitions[..], [AlertTransition::Fired(alert)] if alert.name == "too_hot")
+        );
+        assert_eq!(alerts.unacknowledged().len(), 1);
+
+        // Fires only once while the condition holds
+        assert!(alerts
+            .check(&readings(102.0), start + Duration::from_secs(12))
+            .is_empty());
+        alerts.acknowledge();
+        assert!(alerts.unacknowledged().is_empty());
+
+        let transitions = alerts.check(&readings(50.0), start + Duration::from_secs(13));
+        assert!(matches!(&transitions[..], [AlertTransition::Cleared(_)]));
+        assert!(alerts.active.is_empty());
+
+        // Unreadable attributes do not hold
+        assert!(alerts
+            .check(
+                &AttributeReadings::default(),
+                start + Duration::from_secs(20)
+            )
+            .is_empty());
+    }
+}
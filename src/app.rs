@@ -1,16 +1,29 @@
-use crate::views::command::ViewCommand;
+use crate::alerts::{AlertTransition, Alerts};
+use crate::audit_log::AuditLog;
+use crate::derived::{evaluate_derived, DERIVED_DEVICE};
+use crate::statistics::AttributeStatistics;
+use crate::tango_utils::attr_value_as_f64;
+use crate::trend::Trend;
+use crate::views::chart::ViewChart;
+use crate::views::command::{CommandOutcome, ViewCommand};
 use crate::views::confirm_command::ViewConfirmCommand;
 use crate::views::explorer::ViewExplorerHome;
-use crate::views::watchlist::ViewWatchList;
-use crate::views::{Draw, SharedViewState, View, ViewType};
+use crate::views::history::ViewHistory;
+use crate::views::image::ViewImage;
+use crate::views::spectrum::ViewSpectrum;
+use crate::views::watchlist::{AttributeReading, ViewWatchList};
+use crate::views::{Draw, SharedViewState, View, ViewType, WildcardWatch};
+use crate::watchlist_file::{split_attribute_name, WatchlistFile};
 use crate::Event;
 use crate::{tango_utils::TangoDevicesLookup, views::AttributeReadings};
 
-use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyModifiers};
+use log::{error, info, warn};
 use ratatui::{backend::Backend, Frame};
 use std::error::Error;
 use std::sync::mpsc;
+use std::time::SystemTime;
 use std::{collections::HashMap, env};
 
 pub struct App<'a> {
@@ -22,6 +35,47 @@ pub struct App<'a> {
     pub shared_view_state: SharedViewState<'a>,
 }
 
+// The number of readings in `latest` with a new timestamp
+fn count_updates(previous: &AttributeReadings, latest: &AttributeReadings) -> usize {
+    latest
+        .iter()
+        .flat_map(|(device_name, attr_readings)| {
+            attr_readings
+                .iter()
+                .map(move |(attr_name, attr_reading)| (device_name, attr_name, attr_reading))
+        })
+        .filter(|(device_name, attr_name, attr_reading)| {
+            let previous_time_stamp = match previous
+                .get(*device_name)
+                .and_then(|attr_readings| attr_readings.get(*attr_name))
+            {
+                Some(AttributeReading::Value(reading)) => reading.time_stamp,
+                _ => None,
+            };
+            match attr_reading {
+                AttributeReading::Value(reading) => {
+                    reading.time_stamp.is_some() && reading.time_stamp != previous_time_stamp
+                }
+                AttributeReading::Error(_) => false,
+            }
+        })
+        .count()
+}
+
+// Update the watched attributes with the polled readings. Attributes removed
+// while the poll was running are not brought back.
+fn merge_readings(watch_list: &mut AttributeReadings, mut latest: AttributeReadings) {
+    for (device_name, attr_readings) in watch_list.iter_mut() {
+        if let Some(mut latest_readings) = latest.remove(device_name) {
+            for (attr_name, attr_reading) in attr_readings.iter_mut() {
+                if let Some(latest_reading) = latest_readings.remove(attr_name) {
+                    *attr_reading = latest_reading;
+                }
+            }
+        }
+    }
+}
+
 impl<'a> App<'a> {
     pub fn new(
         title: &'a str,
@@ -62,12 +116,145 @@ impl<'a> App<'a> {
         let confirm_view = ViewType::ConfirmCommand(ViewConfirmCommand::new());
         app.views.insert(View::ConfirmCommand, confirm_view);
 
+        let chart_view = ViewType::Chart(ViewChart::new());
+        app.views.insert(View::Chart, chart_view);
+
+        let spectrum_view = ViewType::Spectrum(ViewSpectrum::new());
+        app.views.insert(View::Spectrum, spectrum_view);
+
+        let image_view = ViewType::Image(ViewImage::new());
+        app.views.insert(View::Image, image_view);
+
+        let history_view = ViewType::History(ViewHistory::new());
+        app.views.insert(View::History, history_view);
+
         Ok(app)
     }
 
+    pub fn load_watchlist_file(&mut self, watchlist_file: WatchlistFile) {
+        let shared_view_state = &mut self.shared_view_state;
+        let mut has_wildcards = false;
+        for entry in watchlist_file.entries {
+            let page_ix = match &entry.page {
+                Some(page) => shared_view_state.page_ix(page),
+                None => shared_view_state.current_page,
+            };
+            if entry.is_wildcard() {
+                shared_view_state.pages[page_ix]
+                    .wildcards
+                    .push(WildcardWatch {
+                        pattern: entry.full_name(),
+                        limits: entry.limits,
+                        ..WildcardWatch::default()
+                    });
+                has_wildcards = true;
+                continue;
+            }
+            if !entry.limits.is_empty() {
+                shared_view_state
+                    .user_limits
+                    .entry(entry.device_name.clone())
+                    .or_default()
+                    .insert(entry.attr_name.clone(), entry.limits);
+            }
+            shared_view_state.add_page_entry(page_ix, entry.device_name, entry.attr_name);
+        }
+        // The inputs of derived attributes are watched as well
+        for entry in watchlist_file.derived {
+            let page_ix = match &entry.page {
+                Some(page) => shared_view_state.page_ix(page),
+                None => shared_view_state.current_page,
+            };
+            for input in entry.expression.inputs() {
+                if let Some((device_name, attr_name)) = split_attribute_name(&input) {
+                    shared_view_state.add_page_entry(page_ix, device_name, attr_name);
+                }
+            }
+            if !entry.limits.is_empty() {
+                shared_view_state
+                    .user_limits
+                    .entry(DERIVED_DEVICE.to_string())
+                    .or_default()
+                    .insert(entry.name.clone(), entry.limits);
+            }
+            shared_view_state
+                .derived
+                .insert(entry.name.clone(), entry.expression);
+            shared_view_state.add_page_entry(page_ix, DERIVED_DEVICE.to_string(), entry.name);
+        }
+        if has_wildcards {
+            shared_view_state.expand_wildcards();
+        }
+        // Start on the page of the first entry when the file has no unnamed entries
+        if shared_view_state.page().entries.is_empty()
+            && shared_view_state.page().wildcards.is_empty()
+            && shared_view_state.pages.len() > 1
+        {
+            shared_view_state
+                .pages
+                .remove(shared_view_state.current_page);
+            shared_view_state.current_page = 0;
+        }
+    }
+
+    // Rebuild the device tree from the database and re-match the wildcard entries
+    fn refresh_devices(&mut self) {
+        self.shared_view_state.refresh_devices = false;
+        match TangoDevicesLookup::build() {
+            Ok(tdl) => {
+                self.views.insert(
+                    View::Explorer,
+                    ViewType::Explorer(ViewExplorerHome::new(&tdl)),
+                );
+                self.shared_view_state.selected_device = None;
+                self.shared_view_state.tango_devices_lookup = tdl;
+                self.shared_view_state.expand_wildcards();
+                info!(
+                    "Refreshed the device tree, {} devices",
+                    self.shared_view_state.tango_devices_lookup.devices.len()
+                );
+            }
+            Err(err) => error!("Could not refresh the device tree: {}", err),
+        }
+    }
+
+    // Watch the attributes the alert rules depend on
+    pub fn load_alerts(&mut self, alerts: Alerts) {
+        for rule in &alerts.rules {
+            for input in rule.condition.inputs() {
+                if let Some((device_name, attr_name)) = split_attribute_name(&input) {
+                    self.shared_view_state
+                        .add_watch_entry(device_name, attr_name);
+                }
+            }
+        }
+        self.shared_view_state.alerts = alerts;
+    }
+
+    // Show the logged history and recall the parameters entered in earlier sessions
+    pub fn load_audit_log(&mut self, audit_log: AuditLog) {
+        self.shared_view_state.audit_log = Some(audit_log);
+        self.shared_view_state.load_audit_entries();
+        if let Some(ViewType::Command(co)) = self.views.get_mut(&View::Command) {
+            co.load_histories(&self.shared_view_state.audit_entries);
+        }
+    }
+
+    pub fn finish_command(&mut self, id: u64, outcome: CommandOutcome) {
+        self.shared_view_state
+            .executed_commands
+            .finish_command(id, outcome);
+        self.shared_view_state.audit_command(id);
+    }
+
     pub fn handle_event(&mut self, key_event: &KeyEvent) {
-        if let KeyCode::Tab = key_event.code {
-            self.shared_view_state.toggle_current_view();
+        match key_event.code {
+            KeyCode::Tab => self.shared_view_state.toggle_current_view(),
+            KeyCode::Char('a') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.shared_view_state.alerts.acknowledge();
+                return;
+            }
+            _ => {}
         }
 
         let current_view = self
@@ -79,7 +266,23 @@ impl<'a> App<'a> {
             ViewType::WatchList(wl) => wl.handle_event(key_event, &mut self.shared_view_state),
             ViewType::Command(co) => co.handle_event(key_event, &mut self.shared_view_state),
             ViewType::ConfirmCommand(po) => po.handle_event(key_event, &mut self.shared_view_state),
+            ViewType::Chart(ch) => ch.handle_event(key_event, &mut self.shared_view_state),
+            ViewType::Spectrum(sp) => sp.handle_event(key_event, &mut self.shared_view_state),
+            ViewType::Image(im) => im.handle_event(key_event, &mut self.shared_view_state),
+            ViewType::History(hi) => hi.handle_event(key_event, &mut self.shared_view_state),
         };
+        if self.shared_view_state.refresh_devices {
+            self.refresh_devices();
+        }
+    }
+
+    // Bracketed paste, only the command parameter and the history search take text
+    pub fn handle_paste(&mut self, text: &str) {
+        match self.views.get_mut(&self.shared_view_state.current_view) {
+            Some(ViewType::Command(co)) => co.handle_paste(text, &self.shared_view_state),
+            Some(ViewType::History(hi)) => hi.handle_paste(text),
+            _ => {}
+        }
     }
 
     pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>) {
@@ -100,14 +303,113 @@ impl<'a> App<'a> {
                 co.draw(f, &mut self.shared_view_state, view.into());
             }
             ViewType::ConfirmCommand(po) => {
-                po.draw(f, &mut self.shared_view_state, view.into());
+                // Deleting a page pops up over the watchlist tab
+                let tab_index = if self.shared_view_state.confirm_delete_page {
+                    1
+                } else {
+                    view.into()
+                };
+                po.draw(f, &mut self.shared_view_state, tab_index);
+            }
+            ViewType::Chart(ch) => {
+                ch.draw(f, &mut self.shared_view_state, view.into());
+            }
+            ViewType::Spectrum(sp) => {
+                sp.draw(f, &mut self.shared_view_state, view.into());
+            }
+            ViewType::Image(im) => {
+                im.draw(f, &mut self.shared_view_state, view.into());
+            }
+            ViewType::History(hi) => {
+                hi.draw(f, &mut self.shared_view_state, view.into());
             }
         }
     }
 
-    pub fn update_device_attr_map(&mut self, attr_map: AttributeReadings) {
+    pub fn update_device_attr_map(&mut self, mut attr_map: AttributeReadings) {
+        evaluate_derived(&mut attr_map, &self.shared_view_state.derived);
+        self.check_alerts(&attr_map);
+        self.update_trends(&attr_map);
+        self.update_statistics(&attr_map);
+        self.record(&attr_map);
         if let Ok(mut wl) = self.shared_view_state.watch_list.try_lock() {
-            *wl = attr_map;
+            if let Some(frozen) = self.shared_view_state.frozen.as_mut() {
+                frozen.buffered_updates += count_updates(&wl, &attr_map);
+            }
+            merge_readings(&mut wl, attr_map);
+        }
+    }
+
+    fn check_alerts(&mut self, attr_map: &AttributeReadings) {
+        let alerts = &mut self.shared_view_state.alerts;
+        for transition in alerts.check(attr_map, SystemTime::now()) {
+            match transition {
+                AlertTransition::Fired(alert) => {
+                    warn!("Alert {} fired: {}", alert.name, alert.condition);
+                    alerts
+                        .notify
+                        .send(&format!("Tango alert {}: {}", alert.name, alert.condition));
+                }
+                AlertTransition::Cleared(alert) => {
+                    info!("Alert {} cleared: {}", alert.name, alert.condition);
+                }
+            }
+        }
+    }
+
+    fn update_trends(&mut self, attr_map: &AttributeReadings) {
+        let trend_capacity = self.shared_view_state.trend_capacity;
+        for (device_name, attr_readings) in attr_map {
+            for (attr_name, attr_reading) in attr_readings {
+                if let AttributeReading::Value(reading) = attr_reading {
+                    let value = reading.data.as_ref().and_then(attr_value_as_f64);
+                    if let (Some(value), Some(time_stamp)) = (value, reading.time_stamp) {
+                        self.shared_view_state
+                            .trends
+                            .entry(device_name.clone())
+                            .or_default()
+                            .entry(attr_name.clone())
+                            .or_insert_with(|| Trend::new(trend_capacity))
+                            .push(time_stamp, value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, attr_map: &AttributeReadings) {
+        if let Some(recorder) = self.shared_view_state.recorder.as_mut() {
+            if let Err(err) = recorder.record(attr_map) {
+                error!("Recording stopped: {}", err);
+                self.shared_view_state.recorder_error = Some(err.to_string());
+                self.shared_view_state.recorder = None;
+            }
+        }
+    }
+
+    fn update_statistics(&mut self, attr_map: &AttributeReadings) {
+        let now = SystemTime::now();
+        for (device_name, attr_readings) in attr_map {
+            for (attr_name, attr_reading) in attr_readings {
+                let statistics = self
+                    .shared_view_state
+                    .statistics
+                    .entry(device_name.clone())
+                    .or_default()
+                    .entry(attr_name.clone())
+                    .or_insert_with(|| AttributeStatistics::new(now));
+                match attr_reading {
+                    // Not read yet
+                    AttributeReading::Value(reading)
+                        if reading.data.is_none() && reading.time_stamp.is_none() => {}
+                    AttributeReading::Value(reading) => statistics.record_value(
+                        &reading.value,
+                        reading.data.as_ref().and_then(attr_value_as_f64),
+                        reading.time_stamp,
+                    ),
+                    AttributeReading::Error(_) => statistics.record_error(),
+                }
+            }
         }
     }
 
@@ -115,3 +417,46 @@ impl<'a> App<'a> {
         // Unimplemented for now
     }
 }
+
+#[cfg(test)]
+mod app_tests {
+    use super::*;
+    use crate::views::watchlist::Reading;
+
+    fn readings(entries: &[(&str, &str, &str)]) -> AttributeReadings {
+        let mut attr_map = AttributeReadings::default();
+        for (device_name, attr_name, value) in entries {
+            attr_map.entry(device_name.to_string()).or_default().insert(
+                attr_name.to_string(),
+                AttributeReading::Value(Reading {
+                    value: value.to_string(),
+                    ..Reading::default()
+                }),
+            );
+        }
+        attr_map
+    }
+
+    fn value(attr_map: &AttributeReadings, device_name: &str, attr_name: &str) -> String {
+        match &attr_map[device_name][attr_name] {
+            AttributeReading::Value(reading) => reading.value.clone(),
+            AttributeReading::Error(err) => err.message.clone(),
+        }
+    }
+
+    #[test]
+    fn test_merge_readings() {
+        // a/b/c/pruned was removed and a/b/c/added watched while polling
+        let mut watch_list = readings(&[("a/b/c", "kept", "0"), ("a/b/c", "added", "")]);
+        let latest = readings(&[
+            ("a/b/c", "kept", "1"),
+            ("a/b/c", "pruned", "2"),
+            ("d/e/f", "gone", "3"),
+        ]);
+        merge_readings(&mut watch_list, latest);
+        assert_eq!(value(&watch_list, "a/b/c", "kept"), "1");
+        assert_eq!(value(&watch_list, "a/b/c", "added"), "");
+        assert!(!watch_list["a/b/c"].contains_key("pruned"));
+        assert!(!watch_list.contains_key("d/e/f"));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::views::command::{CommandError, CommandStatus, ExecutedCommand};
+use anyhow::anyhow;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use log::warn;
+use serde_json::{json, Value};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+// The latest entries kept when the log is loaded
+const AUDIT_HISTORY_SIZE: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Command,
+    Write,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuditAction::Command => write!(f, "exec"),
+            AuditAction::Write => write!(f, "write"),
+        }
+    }
+}
+
+// One command execution or attribute write, appended to the log as a line of JSON:
+//
+// {"user": "operator", "hostname": "ctrl-1", "tango_host": "db:10000", "action": "exec",
+//  "device": "sys/tg_test/1", "command": "DevDouble", "parameter": "1.5",
+//  "status": "succeeded", "result": "1.5", "started": "...", "finished": "..."}
+//
+// Writes name the `attribute` instead of the `command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub user: String,
+    pub hostname: String,
+    pub tango_host: String,
+    pub action: AuditAction,
+    pub device_name: String,
+    // The command executed or the attribute written
+    pub name: String,
+    pub parameter: String,
+    pub status: CommandStatus,
+    // The output or the value written, or the error
+    pub result: String,
+    pub started: SystemTime,
+    pub finished: Option<SystemTime>,
+}
+
+fn display_time_stamp(time_stamp: SystemTime) -> String {
+    let date_time: DateTime<Local> = time_stamp.into();
+    date_time.to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+fn parse_time_stamp(time_stamp: &str) -> Result<SystemTime, Box<dyn Error>> {
+    let date_time = DateTime::parse_from_rfc3339(time_stamp)
+        .map_err(|err| anyhow!("Invalid timestamp [{}]: {}", time_stamp, err))?;
+    Ok(date_time.with_timezone(&Utc).into())
+}
+
+fn string_field(value: &Value, key: &str) -> String {
+    value[key].as_str().unwrap_or_default().to_string()
+}
+
+impl AuditEntry {
+    pub fn finish(&mut self, outcome: Result<String, CommandError>, finished: SystemTime) {
+        self.finished = Some(finished);
+        match outcome {
+            Ok(result) => {
+                self.status = CommandStatus::Succeeded;
+                self.result = result;
+            }
+            Err(err) => {
+                self.status = CommandStatus::from_error(&err);
+                self.result = err.message;
+            }
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        let name_key = match self.action {
+            AuditAction::Command => "command",
+            AuditAction::Write => "attribute",
+        };
+        let mut line = json!({
+            "user": self.user,
+            "hostname": self.hostname,
+            "tango_host": self.tango_host,
+            "action": self.action.to_string(),
+            "device": self.device_name,
+            "parameter": self.parameter,
+            "status": self.status.name(),
+            "result": self.result,
+            "started": display_time_stamp(self.started),
+            "finished": self.finished.map(display_time_stamp),
+        });
+        line[name_key] = json!(self.name);
+        line.to_string()
+    }
+
+    pub fn from_line(line: &str) -> Result<AuditEntry, Box<dyn Error>> {
+        let value: Value = serde_json::from_str(line)?;
+        let (action, name_key) = match value["action"].as_str() {
+            Some("exec") => (AuditAction::Command, "command"),
+            Some("write") => (AuditAction::Write, "attribute"),
+            _ => return Err(anyhow!("Unknown action {}", value["action"]).into()),
+        };
+        let status = value["status"]
+            .as_str()
+            .and_then(CommandStatus::parse)
+            .ok_or_else(|| anyhow!("Unknown status {}", value["status"]))?;
+        let started = parse_time_stamp(value["started"].as_str().unwrap_or_default())?;
+        let finished = match value["finished"].as_str() {
+            Some(finished) => Some(parse_time_stamp(finished)?),
+            None => None,
+        };
+        Ok(AuditEntry {
+            user: string_field(&value, "user"),
+            hostname: string_field(&value, "hostname"),
+            tango_host: string_field(&value, "tango_host"),
+            action,
+            device_name: string_field(&value, "device"),
+            name: string_field(&value, name_key),
+            parameter: string_field(&value, "parameter"),
+            status,
+            result: string_field(&value, "result"),
+            started,
+            finished,
+        })
+    }
+
+    // Every word of the query appears in one of the fields, ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        let fields = [
+            self.user.as_str(),
+            self.hostname.as_str(),
+            self.tango_host.as_str(),
+            self.device_name.as_str(),
+            self.name.as_str(),
+            self.parameter.as_str(),
+            self.status.name(),
+            self.result.as_str(),
+        ]
+        .join("\n")
+        .to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| fields.contains(word))
+    }
+}
+
+// Where the executions and writes are appended, with who made them from where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLog {
+    pub path: PathBuf,
+    user: String,
+    hostname: String,
+    tango_host: String,
+}
+
+fn hostname() -> String {
+    match env::var("HOSTNAME") {
+        Ok(hostname) if !hostname.is_empty() => hostname,
+        _ => fs::read_to_string("/etc/hostname")
+            .map(|hostname| hostname.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string()),
+    }
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> AuditLog {
+        AuditLog {
+            path,
+            user: env::var("USER")
+                .or_else(|_| env::var("USERNAME"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+            hostname: hostname(),
+            tango_host: env::var("TANGO_HOST").unwrap_or_default(),
+        }
+    }
+
+    // ~/.tango-controls-tui/audit.jsonl
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join(".tango-controls-tui")
+                .join("audit.jsonl")
+        })
+    }
+
+    // An entry for an action starting now, to finish once it is done
+    pub fn entry(
+        &self,
+        action: AuditAction,
+        device_name: &str,
+        name: &str,
+        parameter: &str,
+    ) -> AuditEntry {
+        AuditEntry {
+            user: self.user.clone(),
+            hostname: self.hostname.clone(),
+            tango_host: self.tango_host.clone(),
+            action,
+            device_name: device_name.to_string(),
+            name: name.to_string(),
+            parameter: parameter.to_string(),
+            status: CommandStatus::Pending,
+            result: String::new(),
+            started: SystemTime::now(),
+            finished: None,
+        }
+    }
+
+    pub fn command_entry(&self, executed_command: &ExecutedCommand) -> AuditEntry {
+        AuditEntry {
+            status: executed_command.status,
+            result: executed_command.result_text(),
+            started: executed_command.started,
+            finished: executed_command.finished,
+            ..self.entry(
+                AuditAction::Command,
+                &executed_command.device_name,
+                &executed_command.command,
+                &executed_command.parameter,
+            )
+        }
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| anyhow!("Could not open {}: {}", self.path.display(), err))?;
+        writeln!(file, "{}", entry.to_line())?;
+        Ok(())
+    }
+
+    // The latest entries, oldest first. Lines that cannot be read are skipped
+    pub fn load(&self) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|err| anyhow!("Could not read {}: {}", self.path.display(), err))?;
+        let mut entries: Vec<AuditEntry> = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(ix, line)| match AuditEntry::from_line(line) {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    warn!("{} line {}: {}", self.path.display(), ix + 1, err);
+                    None
+                }
+            })
+            .collect();
+        if entries.len() > AUDIT_HISTORY_SIZE {
+            entries.drain(..entries.len() - AUDIT_HISTORY_SIZE);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod audit_log_tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_audit_entry_lines() {
+        let audit_log = AuditLog::new(PathBuf::from("audit.jsonl"));
+        let mut entry = audit_log.entry(
+            AuditAction::Write,
+            "sys/tg_test/1",
+            "double_spectrum",
+            "[1.5, 2]",
+        );
+        entry.started = SystemTime::UNIX_EPOCH + Duration::from_micros(1_500_250);
+        entry.finish(
+            Ok("[1.5, 2.0]".to_string()),
+            entry.started + Duration::from_millis(20),
+        );
+
+        let line = entry.to_line();
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["attribute"], "double_spectrum");
+        assert_eq!(value["status"], "succeeded");
+        assert_eq!(AuditEntry::from_line(&line).unwrap(), entry);
+
+        assert!(entry.matches("TG_TEST spectrum"));
+        assert!(entry.matches("succeeded 1.5"));
+        assert!(!entry.matches("tg_test failed"));
+        assert!(AuditEntry::from_line("{\"action\": \"read\"}").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::audit_log::{AuditAction, AuditLog};
+use crate::tango_utils::{
+    self, attr_value_to_json, attribute_time_stamp, command_data_to_json, display_attr_value_type,
+    display_attribute_format, display_attribute_quality, display_attribute_type,
+    TangoDevicesLookup,
+};
+use crate::value_parser::{format_attr_value, format_command_data};
+use crate::views::command::CommandError;
+use crate::watchlist_file::split_attribute_name;
+use anyhow::anyhow;
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::error;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::SystemTime;
+
+// The non-interactive subcommands, printing tables or JSON with `--json`
+pub const SUBCOMMANDS: [&str; 7] = ["ls", "attrs", "cmds", "read", "write", "exec", "info"];
+
+// Left aligned columns separated by two spaces
+pub fn format_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|title| title.chars().count()).collect();
+    for row in rows {
+        for (ix, cell) in row.iter().enumerate() {
+            if let Some(width) = widths.get_mut(ix) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+    }
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(ix, cell)| format!("{:width$}", cell, width = widths[ix]))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![format_row(
+        header.iter().map(|title| title.to_string()).collect(),
+    )];
+    lines.extend(rows.iter().map(|row| format_row(row.clone())));
+    lines.join("\n")
+}
+
+fn split_attribute(full_name: &str) -> Result<(String, String), Box<dyn Error>> {
+    split_attribute_name(full_name).ok_or_else(|| {
+        anyhow!(
+            "[{}] is not of the form domain/family/member/attribute",
+            full_name
+        )
+        .into()
+    })
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+fn list_devices(pattern: &str, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let devices = TangoDevicesLookup::get_tango_devices(pattern)?;
+    if as_json {
+        print_json(&json!(devices));
+    } else {
+        for device in devices {
+            println!("{}", device);
+        }
+    }
+    Ok(())
+}
+
+fn list_attributes(device_name: &str, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let attributes = tango_utils::get_attribute_list(device_name)?;
+    if as_json {
+        let values: Vec<Value> = attributes
+            .iter()
+            .map(|attribute| {
+                json!({
+                    "name": attribute.attribute_info.name,
+                    "type": display_attribute_type(attribute.attribute_data.clone()),
+                    "format": display_attribute_format(attribute.attribute_info.data_format),
+                    "writable": format!("{:?}", attribute.attribute_info.writable),
+                    "unit": attribute.attribute_info.unit,
+                    "value": attribute
+                        .attribute_data
+                        .as_ref()
+                        .map(|attr_data| attr_value_to_json(&attr_data.data)),
+                })
+            })
+            .collect();
+        print_json(&json!(values));
+    } else {
+        let rows: Vec<Vec<String>> = attributes
+            .iter()
+            .map(|attribute| {
+                vec![
+                    attribute.attribute_info.name.clone(),
+                    display_attribute_type(attribute.attribute_data.clone()),
+                    display_attribute_format(attribute.attribute_info.data_format),
+                    format!("{:?}", attribute.attribute_info.writable),
+                    attribute
+                        .attribute_data
+                        .as_ref()
+                        .map(|attr_data| format!("{}", attr_data.data))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            format_table(&["Name", "Type", "Format", "Writable", "Value"], &rows)
+        );
+    }
+    Ok(())
+}
+
+fn list_commands(device_name: &str, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let commands = tango_utils::get_command_list(device_name)?;
+    if as_json {
+        let values: Vec<Value> = commands
+            .iter()
+            .map(|command| {
+                json!({
+                    "name": command.cmd_name,
+                    "in_type": format!("{:?}", command.in_type),
+                    "out_type": format!("{:?}", command.out_type),
+                    "in_type_desc": command.in_type_desc,
+                    "out_type_desc": command.out_type_desc,
+                })
+            })
+            .collect();
+        print_json(&json!(values));
+    } else {
+        let rows: Vec<Vec<String>> = commands
+            .iter()
+            .map(|command| {
+                vec![
+                    command.cmd_name.clone(),
+                    format!("{:?}", command.in_type),
+                    format!("{:?}", command.out_type),
+                ]
+            })
+            .collect();
+        println!("{}", format_table(&["Name", "In", "Out"], &rows));
+    }
+    Ok(())
+}
+
+fn read_attributes(full_names: &[&str], as_json: bool) -> Result<(), Box<dyn Error>> {
+    let mut failures = 0;
+    let mut values = Vec::new();
+    let mut rows = Vec::new();
+    for full_name in full_names {
+        let (device_name, attr_name) = split_attribute(full_name)?;
+        match tango_utils::try_read_attribute(&device_name, &attr_name) {
+            Ok(attr_data) => {
+                let time_stamp: DateTime<Utc> = attribute_time_stamp(&attr_data).into();
+                let time_stamp = time_stamp.to_rfc3339_opts(SecondsFormat::Micros, true);
+                let quality = display_attribute_quality(attr_data.quality);
+                values.push(json!({
+                    "attribute": full_name,
+                    "type": display_attr_value_type(&attr_data.data),
+                    "value": attr_value_to_json(&attr_data.data),
+                    "quality": quality,
+                    "timestamp": time_stamp,
+                }));
+                rows.push(vec![
+                    full_name.to_string(),
+                    format_attr_value(&attr_data.data),
+                    quality,
+                    time_stamp,
+                ]);
+            }
+            Err(err) => {
+                failures += 1;
+                values.push(json!({"attribute": full_name, "error": err.to_string()}));
+                rows.push(vec![
+                    full_name.to_string(),
+                    format!("Error: {}", err),
+                    String::new(),
+                    String::new(),
+                ]);
+            }
+        }
+    }
+    if as_json {
+        print_json(&json!(values));
+    } else {
+        println!(
+            "{}",
+            format_table(&["Attribute", "Value", "Quality", "Timestamp"], &rows)
+        );
+    }
+    if failures > 0 {
+        return Err(anyhow!(
+            "Could not read {} of {} attributes",
+            failures,
+            full_names.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// Log a write or an execution, failing to do so does not fail the subcommand
+fn audit(
+    audit_log: Option<&AuditLog>,
+    action: AuditAction,
+    (device_name, name, parameter): (&str, &str, &str),
+    started: SystemTime,
+    outcome: Result<String, CommandError>,
+) {
+    if let Some(audit_log) = audit_log {
+        let mut entry = audit_log.entry(action, device_name, name, parameter);
+        entry.started = started;
+        entry.finish(outcome, SystemTime::now());
+        if let Err(err) = audit_log.append(&entry) {
+            error!("Could not write to the audit log: {}", err);
+            eprintln!("Could not write to the audit log: {}", err);
+        }
+    }
+}
+
+fn write_attribute(
+    full_name: &str,
+    value: &str,
+    as_json: bool,
+    audit_log: Option<&AuditLog>,
+) -> Result<(), Box<dyn Error>> {
+    let (device_name, attr_name) = split_attribute(full_name)?;
+    let started = SystemTime::now();
+    let written = tango_utils::write_attribute(&device_name, &attr_name, value);
+    audit(
+        audit_log,
+        AuditAction::Write,
+        (&device_name, &attr_name, value),
+        started,
+        match &written {
+            Ok(written) => Ok(format_attr_value(written)),
+            Err(err) => Err(CommandError::from_error(err.as_ref())),
+        },
+    );
+    let written = written?;
+    if as_json {
+        print_json(&json!({"attribute": full_name, "value": attr_value_to_json(&written)}));
+    } else {
+        println!("{} = {}", full_name, format_attr_value(&written));
+    }
+    Ok(())
+}
+
+fn execute_command(
+    device_name: &str,
+    command_name: &str,
+    parameter: &str,
+    as_json: bool,
+    audit_log: Option<&AuditLog>,
+) -> Result<(), Box<dyn Error>> {
+    let started = SystemTime::now();
+    let result = tango_utils::execute_tango_command(device_name, command_name, parameter);
+    audit(
+        audit_log,
+        AuditAction::Command,
+        (device_name, command_name, parameter),
+        started,
+        match &result {
+            Ok(result) => Ok(format_command_data(result)),
+            Err(err) => Err(CommandError::from_error(err.as_ref())),
+        },
+    );
+    let result = result?;
+    if as_json {
+        print_json(&json!({
+            "device": device_name,
+            "command": command_name,
+            "result": command_data_to_json(&result),
+        }));
+    } else {
+        println!("{}", format_command_data(&result));
+    }
+    Ok(())
+}
+
+fn device_info(device_name: &str, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let info = tango_utils::get_device_info(device_name)?;
+    if as_json {
+        print_json(&json!({
+            "device": info.name,
+            "state": info.state,
+            "status": info.status,
+            "timeout_millis": info.timeout_millis,
+            "locked": info.locked,
+            "attributes": info.attribute_count,
+            "commands": info.command_count,
+        }));
+    } else {
+        let rows = vec![
+            vec!["Device".to_string(), info.name],
+            vec!["State".to_string(), info.state],
+            vec!["Status".to_string(), info.status],
+            vec!["Timeout".to_string(), format!("{} ms", info.timeout_millis)],
+            vec!["Locked".to_string(), info.locked.to_string()],
+            vec!["Attributes".to_string(), info.attribute_count.to_string()],
+            vec!["Commands".to_string(), info.command_count.to_string()],
+        ];
+        println!("{}", format_table(&["Property", "Value"], &rows));
+    }
+    Ok(())
+}
+
+pub fn run(
+    subcommand: &str,
+    matches: &clap::ArgMatches,
+    audit_log: Option<&AuditLog>,
+) -> Result<(), Box<dyn Error>> {
+    let as_json = matches.is_present("json");
+    match subcommand {
+        "ls" => list_devices(matches.value_of("pattern").unwrap_or("*"), as_json),
+        "attrs" => list_attributes(matches.value_of("device").unwrap(), as_json),
+        "cmds" => list_commands(matches.value_of("device").unwrap(), as_json),
+        "read" => {
+            let full_names: Vec<&str> = matches.values_of("attributes").unwrap().collect();
+            read_attributes(&full_names, as_json)
+        }
+        "write" => write_attribute(
+            matches.value_of("attribute").unwrap(),
+            matches.value_of("value").unwrap(),
+            as_json,
+            audit_log,
+        ),
+        "exec" => execute_command(
+            matches.value_of("device").unwrap(),
+            matches.value_of("command").unwrap(),
+            matches.value_of("argument").unwrap_or(""),
+            as_json,
+            audit_log,
+        ),
+        "info" => device_info(matches.value_of("device").unwrap(), as_json),
+        _ => Err(anyhow!("Unknown subcommand [{}]", subcommand).into()),
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec!["sys/tg_test/1/ampli".to_string(), "1.5".to_string()],
+            vec!["a/b/c/d".to_string(), "[1, 2]".to_string()],
+        ];
+        assert_eq!(
+            format_table(&["Attribute", "Value"], &rows),
+            "Attribute            Value\n\
+             sys/tg_test/1/ampli  1.5\n\
+             a/b/c/d              [1, 2]"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::expression::{Expression, Value};
+use crate::tango_utils::{attr_value_as_f64, attr_value_as_f64_array};
+use crate::views::watchlist::{AttributeReading, ReadError, ReadErrorKind, Reading};
+use crate::views::{AttributeName, AttributeReadings};
+use crate::watchlist_file::split_attribute_name;
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::time::SystemTime;
+use tango_controls_client_sys::types::{AttrDataFormat, AttrQuality, AttrValue};
+
+// Derived attributes are computed from other watched attributes and are listed
+// in the watchlist under this pseudo device, which is never polled.
+pub const DERIVED_DEVICE: &str = "derived";
+
+pub type DerivedAttributes = BTreeMap<AttributeName, Expression>;
+
+pub fn attr_value_to_value(attr_value: &AttrValue) -> Result<Value, Box<dyn Error>> {
+    match attr_value {
+        AttrValue::Boolean(val) => Ok(Value::Bool(*val)),
+        AttrValue::String(val) => Ok(Value::Str(val.clone())),
+        AttrValue::DevState(val) => Ok(Value::Str(val.to_string())),
+        _ => attr_value_as_f64(attr_value)
+            .map(Value::Number)
+            .or_else(|| attr_value_as_f64_array(attr_value).map(Value::Array))
+            .ok_or_else(|| anyhow!("unsupported type").into()),
+    }
+}
+
+fn value_to_attr_value(value: &Value) -> AttrValue {
+    match value {
+        Value::Number(val) => AttrValue::Double(*val),
+        Value::Bool(val) => AttrValue::Boolean(*val),
+        Value::Str(val) => AttrValue::String(val.clone()),
+        Value::Array(val) => AttrValue::DoubleArray(val.clone()),
+    }
+}
+
+// The current value of a watched attribute
+pub fn lookup_reading(
+    attr_map: &AttributeReadings,
+    full_name: &str,
+) -> Result<Value, Box<dyn Error>> {
+    let reading = split_attribute_name(full_name)
+        .and_then(|(device_name, attr_name)| attr_map.get(&device_name)?.get(&attr_name).cloned());
+    match reading {
+        Some(AttributeReading::Value(Reading {
+            data: Some(data), ..
+        })) => attr_value_to_value(&data),
+        Some(AttributeReading::Value(reading)) if reading.time_stamp.is_some() => {
+            Err(anyhow!("{}", reading.value).into())
+        }
+        Some(AttributeReading::Value(_)) => Err(anyhow!("not read yet").into()),
+        Some(AttributeReading::Error(err)) => Err(anyhow!("{}", err).into()),
+        None => Err(anyhow!("not watched").into()),
+    }
+}
+
+// Higher is worse
+pub fn quality_rank(quality: AttrQuality) -> u8 {
+    match quality {
+        AttrQuality::ATTR_VALID => 0,
+        AttrQuality::ATTR_CHANGING => 1,
+        AttrQuality::ATTR_WARNING => 2,
+        AttrQuality::ATTR_ALARM => 3,
+        AttrQuality::ATTR_INVALID => 4,
+    }
+}
+
+// Evaluate the watched derived attributes from the other readings in the map. A
+// derived reading takes the latest timestamp and the worst quality of its inputs,
+// so it only counts as a new value when one of the inputs changed.
+pub fn evaluate_derived(attr_map: &mut AttributeReadings, derived: &DerivedAttributes) {
+    let watched: Vec<AttributeName> = match attr_map.get(DERIVED_DEVICE) {
+        Some(derived_map) => derived_map.keys().cloned().collect(),
+        None => return,
+    };
+    let mut derived_readings = BTreeMap::new();
+    for name in watched {
+        let expression = match derived.get(&name) {
+            Some(expression) => expression,
+            None => continue,
+        };
+        let mut time_stamp: Option<SystemTime> = None;
+        let mut quality: Option<AttrQuality> = None;
+        for input in expression.inputs() {
+            let reading = split_attribute_name(&input)
+                .and_then(|(device_name, attr_name)| attr_map.get(&device_name)?.get(&attr_name));
+            if let Some(AttributeReading::Value(reading)) = reading {
+                time_stamp = time_stamp.max(reading.time_stamp);
+                quality = match (quality, reading.quality) {
+                    (Some(current), Some(input))
+                        if quality_rank(current) >= quality_rank(input) =>
+                    {
+                        Some(current)
+                    }
+                    (current, input) => input.or(current),
+                };
+            }
+        }
+        let derived_reading =
+            match expression.evaluate(&|full_name| lookup_reading(attr_map, full_name)) {
+                Ok(value) => Ok(AttributeReading::Value(Reading {
+                    value: value.to_string(),
+                    data: Some(value_to_attr_value(&value)),
+                    format: Some(match value {
+                        Value::Array(_) => AttrDataFormat::SPECTRUM,
+                        _ => AttrDataFormat::SCALAR,
+                    }),
+                    dim_x: match &value {
+                        Value::Array(val) => val.len(),
+                        _ => 1,
+                    },
+                    dim_y: 0,
+                    quality,
+                    time_stamp,
+                    received_time_stamp: Some(SystemTime::now()),
+                })),
+                Err(err) => Err(ReadError::new(ReadErrorKind::Failed, &err.to_string())),
+            };
+        derived_readings.insert(name, derived_reading);
+    }
+    let now = SystemTime::now();
+    if let Some(derived_map) = attr_map.get_mut(DERIVED_DEVICE) {
+        for (name, derived_reading) in derived_readings {
+            if let Some(attr_reading) = derived_map.get_mut(&name) {
+                // Failures count their attempts like the polled attributes
+                match derived_reading {
+                    Ok(reading) => *attr_reading = reading,
+                    Err(err) => attr_reading.set_error(err, now),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod derived_tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn reading(data: AttrValue, quality: AttrQuality, time_stamp: SystemTime) -> AttributeReading {
+        AttributeReading::Value(Reading {
+            data: Some(data),
+            quality: Some(quality),
+            time_stamp: Some(time_stamp),
+            ..Reading::default()
+        })
+    }
+
+    #[test]
+    fn test_evaluate_derived() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = start + Duration::from_secs(5);
+        let mut attr_map = AttributeReadings::new();
+        attr_map.insert(
+            "a/b/c".to_string(),
+            BTreeMap::from([
+                (
+                    "x".to_string(),
+                    reading(AttrValue::Double(2.0), AttrQuality::ATTR_VALID, start),
+                ),
+                (
+                    "y".to_string(),
+                    reading(AttrValue::Long(3), AttrQuality::ATTR_WARNING, later),
+                ),
+                (
+                    "s".to_string(),
+                    reading(
+                        AttrValue::DoubleArray(vec![1.0, 2.0]),
+                        AttrQuality::ATTR_CHANGING,
+                        start,
+                    ),
+                ),
+            ]),
+        );
+        attr_map.insert(
+            DERIVED_DEVICE.to_string(),
+            ["sum", "scaled", "broken"]
+                .iter()
+                .map(|name| (name.to_string(), AttributeReading::default()))
+                .collect(),
+        );
+        let derived: DerivedAttributes = [
+            ("sum", "a/b/c/x + a/b/c/y"),
+            ("scaled", "a/b/c/s * a/b/c/x"),
+            ("broken", "a/b/c/x + a/b/c/missing"),
+        ]
+        .iter()
+        .map(|(name, source)| (name.to_string(), Expression::parse(source).unwrap()))
+        .collect();
+
+        evaluate_derived(&mut attr_map, &derived);
+        let derived_map = &attr_map[DERIVED_DEVICE];
+        match &derived_map["sum"] {
+            AttributeReading::Value(reading) => {
+                assert_eq!(reading.data, Some(AttrValue::Double(5.0)));
+                assert_eq!(reading.format, Some(AttrDataFormat::SCALAR));
+                // The latest timestamp and the worst quality of the inputs
+                assert_eq!(reading.time_stamp, Some(later));
+                assert_eq!(reading.quality, Some(AttrQuality::ATTR_WARNING));
+            }
+            other => panic!("Expected a value, not {:?}", other),
+        }
+        match &derived_map["scaled"] {
+            AttributeReading::Value(reading) => {
+                assert_eq!(reading.data, Some(AttrValue::DoubleArray(vec![2.0, 4.0])));
+                assert_eq!(reading.format, Some(AttrDataFormat::SPECTRUM));
+                assert_eq!(reading.dim_x, 2);
+                assert_eq!(reading.time_stamp, Some(start));
+                assert_eq!(reading.quality, Some(AttrQuality::ATTR_CHANGING));
+            }
+            other => panic!("Expected a value, not {:?}", other),
+        }
+        match &derived_map["broken"] {
+            AttributeReading::Error(err) => {
+                assert_eq!(err.kind, ReadErrorKind::Failed);
+                assert_eq!(err.attempts, 1);
+                assert!(err.retry_at.is_some());
+            }
+            other => panic!("Expected an error, not {:?}", other),
+        }
+
+        // Failing again counts the attempts
+        evaluate_derived(&mut attr_map, &derived);
+        match &attr_map[DERIVED_DEVICE]["broken"] {
+            AttributeReading::Error(err) => assert_eq!(err.attempts, 2),
+            other => panic!("Expected an error, not {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,799 @@
+use anyhow::anyhow;
+use std::error::Error;
+use std::fmt;
+
+// Expressions over attribute values, e.g. `abs(sys/motor/1/position - sys/motor/2/position) < 0.1`.
+//
+// Attributes are referenced by their full `domain/family/member/attribute` name. Put spaces
+// around operators next to attribute names since `/` and `-` are allowed in the name.
+// Operators from lowest to highest precedence:
+//   ||   &&   == !=   < <= > >=   + -   * / %   unary - !   ^   [index]
+// Numeric operators and functions apply element-wise to spectrums.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+    Str(String),
+    Array(Vec<f64>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::Str(_) => "string",
+            Value::Array(_) => "array",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(val) => write!(f, "{}", val),
+            Value::Bool(val) => write!(f, "{}", val),
+            Value::Str(val) => write!(f, "{}", val),
+            Value::Array(val) => write!(
+                f,
+                "[{}]",
+                val.iter()
+                    .map(|val| val.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl BinaryOp {
+    fn from_symbol(symbol: &str) -> Option<BinaryOp> {
+        let op = match symbol {
+            "||" => BinaryOp::Or,
+            "&&" => BinaryOp::And,
+            "==" => BinaryOp::Eq,
+            "!=" => BinaryOp::Ne,
+            "<" => BinaryOp::Lt,
+            "<=" => BinaryOp::Le,
+            ">" => BinaryOp::Gt,
+            ">=" => BinaryOp::Ge,
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Sub,
+            "*" => BinaryOp::Mul,
+            "/" => BinaryOp::Div,
+            "%" => BinaryOp::Rem,
+            "^" => BinaryOp::Pow,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    // Left and right binding power, `^` is right associative
+    fn binding_power(&self) -> (u8, u8) {
+        match self {
+            BinaryOp::Or => (1, 2),
+            BinaryOp::And => (3, 4),
+            BinaryOp::Eq | BinaryOp::Ne => (5, 6),
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => (7, 8),
+            BinaryOp::Add | BinaryOp::Sub => (9, 10),
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => (11, 12),
+            BinaryOp::Pow => (16, 15),
+        }
+    }
+}
+
+const PREFIX_BINDING_POWER: u8 = 13;
+const INDEX_BINDING_POWER: u8 = 17;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Bool(bool),
+    Str(String),
+    Array(Vec<Expr>),
+    Attribute(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Attribute(String),
+    Op(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+// Characters that end an attribute name
+fn ends_attribute(c: char) -> bool {
+    c.is_whitespace() || "()[],*+<>=!&|%^\"".contains(c)
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, Box<dyn Error>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut ix = 0;
+    while ix < chars.len() {
+        let c = chars[ix];
+        let start = ix;
+        if c.is_whitespace() {
+            ix += 1;
+            continue;
+        }
+        let token = if c.is_ascii_digit()
+            || (c == '.' && matches!(chars.get(ix + 1), Some(c) if c.is_ascii_digit()))
+        {
+            while ix < chars.len() && (chars[ix].is_ascii_digit() || chars[ix] == '.') {
+                ix += 1;
+            }
+            // Exponent
+            if ix < chars.len() && (chars[ix] == 'e' || chars[ix] == 'E') {
+                let mut end = ix + 1;
+                if end < chars.len() && (chars[end] == '+' || chars[end] == '-') {
+                    end += 1;
+                }
+                if end < chars.len() && chars[end].is_ascii_digit() {
+                    ix = end;
+                    while ix < chars.len() && chars[ix].is_ascii_digit() {
+                        ix += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..ix].iter().collect();
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| anyhow!("Invalid number [{}] at position {}", text, start + 1))?;
+            Token::Number(number)
+        } else if c.is_alphabetic() || c == '_' {
+            while ix < chars.len() && (chars[ix].is_alphanumeric() || chars[ix] == '_') {
+                ix += 1;
+            }
+            if ix < chars.len()
+                && chars[ix] == '/'
+                && matches!(chars.get(ix + 1), Some(c) if !ends_attribute(*c))
+            {
+                while ix < chars.len() && !ends_attribute(chars[ix]) {
+                    ix += 1;
+                }
+                let name: String = chars[start..ix].iter().collect();
+                if name.split('/').count() != 4 || name.split('/').any(|part| part.is_empty()) {
+                    return Err(anyhow!(
+                        "[{}] at position {} is not of the form domain/family/member/attribute, \
+                         put spaces around / for division",
+                        name,
+                        start + 1
+                    )
+                    .into());
+                }
+                Token::Attribute(name)
+            } else {
+                Token::Ident(chars[start..ix].iter().collect())
+            }
+        } else if c == '"' {
+            ix += 1;
+            let mut text = String::new();
+            loop {
+                match chars.get(ix) {
+                    None => {
+                        return Err(anyhow!("Unterminated string at position {}", start + 1).into())
+                    }
+                    Some('"') => break,
+                    Some('\\') if ix + 1 < chars.len() => {
+                        text.push(chars[ix + 1]);
+                        ix += 2;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        ix += 1;
+                    }
+                }
+            }
+            ix += 1;
+            Token::Str(text)
+        } else {
+            let two: String = chars[ix..(ix + 2).min(chars.len())].iter().collect();
+            if ["||", "&&", "==", "!=", "<=", ">="].contains(&two.as_str()) {
+                ix += 2;
+                Token::Op(two)
+            } else {
+                ix += 1;
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    ',' => Token::Comma,
+                    '+' | '-' | '*' | '/' | '%' | '^' | '<' | '>' | '!' => Token::Op(c.to_string()),
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected character [{}] at position {}",
+                            c,
+                            start + 1
+                        )
+                        .into())
+                    }
+                }
+            }
+        };
+        tokens.push((start + 1, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end_position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(position, _)| *position)
+            .unwrap_or(self.end_position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, token)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), Box<dyn Error>> {
+        let position = self.position();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(anyhow!("Expected {} at position {}", description, position).into()),
+        }
+    }
+
+    // Comma separated expressions up to the closing token
+    fn parse_list(&mut self, close: Token, description: &str) -> Result<Vec<Expr>, Box<dyn Error>> {
+        let mut items = Vec::new();
+        if self.peek() == Some(&close) {
+            self.next();
+            return Ok(items);
+        }
+        loop {
+            items.push(self.parse_expr(0)?);
+            if self.peek() == Some(&Token::Comma) {
+                self.next();
+            } else {
+                self.expect(close, description)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn parse_expr(&mut self, min_bp: u8) -> Result<Expr, Box<dyn Error>> {
+        let position = self.position();
+        let mut lhs = match self.next() {
+            Some(Token::Number(val)) => Expr::Number(val),
+            Some(Token::Str(val)) => Expr::Str(val),
+            Some(Token::Attribute(name)) => Expr::Attribute(name),
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.next();
+                    let args = self.parse_list(Token::RParen, ", or )")?;
+                    check_function(&name, args.len(), position)?;
+                    Expr::Call(name, args)
+                } else {
+                    match name.as_str() {
+                        "true" => Expr::Bool(true),
+                        "false" => Expr::Bool(false),
+                        "pi" => Expr::Number(std::f64::consts::PI),
+                        "e" => Expr::Number(std::f64::consts::E),
+                        _ => {
+                            return Err(
+                                anyhow!("Unknown name [{}] at position {}", name, position).into()
+                            )
+                        }
+                    }
+                }
+            }
+            Some(Token::LParen) => {
+                let expr = self.parse_expr(0)?;
+                self.expect(Token::RParen, ")")?;
+                expr
+            }
+            Some(Token::LBracket) => Expr::Array(self.parse_list(Token::RBracket, ", or ]")?),
+            Some(Token::Op(op)) if op == "-" => Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(self.parse_expr(PREFIX_BINDING_POWER)?),
+            ),
+            Some(Token::Op(op)) if op == "!" => Expr::Unary(
+                UnaryOp::Not,
+                Box::new(self.parse_expr(PREFIX_BINDING_POWER)?),
+            ),
+            Some(_) => return Err(anyhow!("Unexpected token at position {}", position).into()),
+            None => return Err(anyhow!("Unexpected end of expression").into()),
+        };
+
+        loop {
+            match self.peek() {
+                Some(Token::LBracket) if INDEX_BINDING_POWER >= min_bp => {
+                    self.next();
+                    let index = self.parse_expr(0)?;
+                    self.expect(Token::RBracket, "]")?;
+                    lhs = Expr::Index(Box::new(lhs), Box::new(index));
+                }
+                Some(Token::Op(symbol)) => {
+                    let position = self.position();
+                    let op = BinaryOp::from_symbol(symbol).ok_or_else(|| {
+                        anyhow!("Unexpected operator [{}] at position {}", symbol, position)
+                    })?;
+                    let (left_bp, right_bp) = op.binding_power();
+                    if left_bp < min_bp {
+                        break;
+                    }
+                    self.next();
+                    let rhs = self.parse_expr(right_bp)?;
+                    lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+                }
+                _ => break,
+            }
+        }
+        Ok(lhs)
+    }
+}
+
+const FUNCTIONS: [(&str, usize, usize); 22] = [
+    ("abs", 1, 1),
+    ("sqrt", 1, 1),
+    ("exp", 1, 1),
+    ("ln", 1, 1),
+    ("log10", 1, 1),
+    ("sin", 1, 1),
+    ("cos", 1, 1),
+    ("tan", 1, 1),
+    ("floor", 1, 1),
+    ("ceil", 1, 1),
+    ("round", 1, 1),
+    ("min", 1, usize::MAX),
+    ("max", 1, usize::MAX),
+    ("sum", 1, 1),
+    ("mean", 1, 1),
+    ("std", 1, 1),
+    ("rms", 1, 1),
+    ("len", 1, 1),
+    ("if", 3, 3),
+    ("error", 1, 1),
+    ("atan2", 2, 2),
+    ("pow", 2, 2),
+];
+
+fn check_function(name: &str, arg_count: usize, position: usize) -> Result<(), Box<dyn Error>> {
+    match FUNCTIONS.iter().find(|(function, _, _)| *function == name) {
+        Some((_, min_args, max_args)) if arg_count < *min_args || arg_count > *max_args => {
+            Err(anyhow!(
+                "Wrong number of arguments for [{}] at position {}",
+                name,
+                position
+            )
+            .into())
+        }
+        Some(_) => Ok(()),
+        None => Err(anyhow!("Unknown function [{}] at position {}", name, position).into()),
+    }
+}
+
+fn numbers(value: Value, function: &str) -> Result<Vec<f64>, Box<dyn Error>> {
+    match value {
+        Value::Number(val) => Ok(vec![val]),
+        Value::Array(val) => Ok(val),
+        other => Err(anyhow!("{} expects numbers, got a {}", function, other.type_name()).into()),
+    }
+}
+
+// Apply `f` to a number or to every element of an array
+fn map_numbers(value: Value, function: &str, f: fn(f64) -> f64) -> Result<Value, Box<dyn Error>> {
+    match value {
+        Value::Number(val) => Ok(Value::Number(f(val))),
+        Value::Array(val) => Ok(Value::Array(val.into_iter().map(f).collect())),
+        other => Err(anyhow!("{} expects numbers, got a {}", function, other.type_name()).into()),
+    }
+}
+
+fn arithmetic(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, Box<dyn Error>> {
+    let f = |a: f64, b: f64| match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        BinaryOp::Rem => a % b,
+        _ => a.powf(b),
+    };
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(f(a, b))),
+        (Value::Array(a), Value::Number(b)) => {
+            Ok(Value::Array(a.iter().map(|a| f(*a, b)).collect()))
+        }
+        (Value::Number(a), Value::Array(b)) => {
+            Ok(Value::Array(b.iter().map(|b| f(a, *b)).collect()))
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => Ok(Value::Array(
+            a.iter().zip(b.iter()).map(|(a, b)| f(*a, *b)).collect(),
+        )),
+        (Value::Array(a), Value::Array(b)) => {
+            Err(anyhow!("Arrays of length {} and {} differ", a.len(), b.len()).into())
+        }
+        (Value::Str(a), Value::Str(b)) if op == BinaryOp::Add => Ok(Value::Str(a + &b)),
+        (lhs, rhs) => Err(anyhow!(
+            "Cannot apply {:?} to a {} and a {}",
+            op,
+            lhs.type_name(),
+            rhs.type_name()
+        )
+        .into()),
+    }
+}
+
+fn compare(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, Box<dyn Error>> {
+    let ordering = match (&lhs, &rhs) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) if matches!(op, BinaryOp::Eq | BinaryOp::Ne) => {
+            Some(a.cmp(b))
+        }
+        _ => {
+            return Err(anyhow!(
+                "Cannot compare a {} and a {}",
+                lhs.type_name(),
+                rhs.type_name()
+            )
+            .into())
+        }
+    };
+    let result = match ordering {
+        // NaN is only unequal
+        None => op == BinaryOp::Ne,
+        Some(ordering) => match op {
+            BinaryOp::Eq => ordering.is_eq(),
+            BinaryOp::Ne => ordering.is_ne(),
+            BinaryOp::Lt => ordering.is_lt(),
+            BinaryOp::Le => ordering.is_le(),
+            BinaryOp::Gt => ordering.is_gt(),
+            _ => ordering.is_ge(),
+        },
+    };
+    Ok(Value::Bool(result))
+}
+
+fn as_bool(value: Value) -> Result<bool, Box<dyn Error>> {
+    match value {
+        Value::Bool(val) => Ok(val),
+        other => Err(anyhow!("Expected a boolean, got a {}", other.type_name()).into()),
+    }
+}
+
+// Resolves an attribute name to its current value
+pub type Lookup<'a> = dyn Fn(&str) -> Result<Value, Box<dyn Error>> + 'a;
+
+fn evaluate(expr: &Expr, lookup: &Lookup) -> Result<Value, Box<dyn Error>> {
+    match expr {
+        Expr::Number(val) => Ok(Value::Number(*val)),
+        Expr::Bool(val) => Ok(Value::Bool(*val)),
+        Expr::Str(val) => Ok(Value::Str(val.clone())),
+        Expr::Array(items) => {
+            let mut values = Vec::new();
+            for item in items {
+                match evaluate(item, lookup)? {
+                    Value::Number(val) => values.push(val),
+                    other => {
+                        return Err(anyhow!(
+                            "Arrays can only hold numbers, got a {}",
+                            other.type_name()
+                        )
+                        .into())
+                    }
+                }
+            }
+            Ok(Value::Array(values))
+        }
+        Expr::Attribute(name) => lookup(name).map_err(|err| anyhow!("{}: {}", name, err).into()),
+        Expr::Unary(UnaryOp::Neg, operand) => {
+            map_numbers(evaluate(operand, lookup)?, "-", |val| -val)
+        }
+        Expr::Unary(UnaryOp::Not, operand) => {
+            Ok(Value::Bool(!as_bool(evaluate(operand, lookup)?)?))
+        }
+        Expr::Binary(BinaryOp::And, lhs, rhs) => Ok(Value::Bool(
+            as_bool(evaluate(lhs, lookup)?)? && as_bool(evaluate(rhs, lookup)?)?,
+        )),
+        Expr::Binary(BinaryOp::Or, lhs, rhs) => Ok(Value::Bool(
+            as_bool(evaluate(lhs, lookup)?)? || as_bool(evaluate(rhs, lookup)?)?,
+        )),
+        Expr::Binary(op, lhs, rhs) => {
+            let (lhs, rhs) = (evaluate(lhs, lookup)?, evaluate(rhs, lookup)?);
+            match op {
+                BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::Lt
+                | BinaryOp::Le
+                | BinaryOp::Gt
+                | BinaryOp::Ge => compare(*op, lhs, rhs),
+                _ => arithmetic(*op, lhs, rhs),
+            }
+        }
+        Expr::Index(array, index) => {
+            let array = numbers(evaluate(array, lookup)?, "[]")?;
+            let index = match evaluate(index, lookup)? {
+                Value::Number(val) if val >= 0.0 && val.fract() == 0.0 => val as usize,
+                other => return Err(anyhow!("Invalid index {}", other).into()),
+            };
+            array
+                .get(index)
+                .map(|val| Value::Number(*val))
+                .ok_or_else(|| {
+                    anyhow!("Index {} out of range for length {}", index, array.len()).into()
+                })
+        }
+        Expr::Call(name, args) => call(name, args, lookup),
+    }
+}
+
+fn call(name: &str, args: &[Expr], lookup: &Lookup) -> Result<Value, Box<dyn Error>> {
+    // Lazily evaluated arguments
+    match name {
+        "if" => {
+            return if as_bool(evaluate(&args[0], lookup)?)? {
+                evaluate(&args[1], lookup)
+            } else {
+                evaluate(&args[2], lookup)
+            }
+        }
+        "error" => return Ok(Value::Bool(evaluate(&args[0], lookup).is_err())),
+        _ => {}
+    }
+
+    let mut values = Vec::new();
+    for arg in args {
+        values.push(evaluate(arg, lookup)?);
+    }
+    let first = values[0].clone();
+    let result = match name {
+        "abs" => map_numbers(first, name, f64::abs)?,
+        "sqrt" => map_numbers(first, name, f64::sqrt)?,
+        "exp" => map_numbers(first, name, f64::exp)?,
+        "ln" => map_numbers(first, name, f64::ln)?,
+        "log10" => map_numbers(first, name, f64::log10)?,
+        "sin" => map_numbers(first, name, f64::sin)?,
+        "cos" => map_numbers(first, name, f64::cos)?,
+        "tan" => map_numbers(first, name, f64::tan)?,
+        "floor" => map_numbers(first, name, f64::floor)?,
+        "ceil" => map_numbers(first, name, f64::ceil)?,
+        "round" => map_numbers(first, name, f64::round)?,
+        "atan2" => arithmetic_pair(values, name, f64::atan2)?,
+        "pow" => arithmetic_pair(values, name, f64::powf)?,
+        _ => {
+            // Reductions over one array or several numbers
+            let mut all = Vec::new();
+            for value in values {
+                all.extend(numbers(value, name)?);
+            }
+            reduce(name, &all)?
+        }
+    };
+    Ok(result)
+}
+
+fn arithmetic_pair(
+    values: Vec<Value>,
+    name: &str,
+    f: fn(f64, f64) -> f64,
+) -> Result<Value, Box<dyn Error>> {
+    match (&values[0], &values[1]) {
+        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(f(*a, *b))),
+        _ => Err(anyhow!("{} expects two numbers", name).into()),
+    }
+}
+
+fn reduce(name: &str, values: &[f64]) -> Result<Value, Box<dyn Error>> {
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let result = match name {
+        "len" => count,
+        "sum" => values.iter().sum(),
+        _ if values.is_empty() => return Err(anyhow!("{} of an empty array", name).into()),
+        "min" => values.iter().cloned().fold(f64::INFINITY, f64::min),
+        "max" => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        "mean" => mean,
+        "rms" => (values.iter().map(|val| val * val).sum::<f64>() / count).sqrt(),
+        _ if values.len() < 2 => return Err(anyhow!("{} needs at least two values", name).into()),
+        _ => (values.iter().map(|val| (val - mean).powi(2)).sum::<f64>() / (count - 1.0)).sqrt(),
+    };
+    Ok(Value::Number(result))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub source: String,
+    pub expr: Expr,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Expression, Box<dyn Error>> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end_position: source.chars().count() + 1,
+        };
+        let expr = parser.parse_expr(0)?;
+        if parser.peek().is_some() {
+            return Err(anyhow!("Unexpected token at position {}", parser.position()).into());
+        }
+        Ok(Expression {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    // The attributes referenced in the expression, without duplicates
+    pub fn inputs(&self) -> Vec<String> {
+        fn collect(expr: &Expr, inputs: &mut Vec<String>) {
+            match expr {
+                Expr::Attribute(name) => {
+                    if !inputs.contains(name) {
+                        inputs.push(name.clone());
+                    }
+                }
+                Expr::Array(items) | Expr::Call(_, items) => {
+                    items.iter().for_each(|item| collect(item, inputs))
+                }
+                Expr::Unary(_, operand) => collect(operand, inputs),
+                Expr::Binary(_, lhs, rhs) | Expr::Index(lhs, rhs) => {
+                    collect(lhs, inputs);
+                    collect(rhs, inputs);
+                }
+                Expr::Number(_) | Expr::Bool(_) | Expr::Str(_) => {}
+            }
+        }
+        let mut inputs = Vec::new();
+        collect(&self.expr, &mut inputs);
+        inputs
+    }
+
+    pub fn evaluate(&self, lookup: &Lookup) -> Result<Value, Box<dyn Error>> {
+        evaluate(&self.expr, lookup)
+    }
+}
+
+#[cfg(test)]
+mod expression_tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Result<Value, Box<dyn Error>> {
+        match name {
+            "sys/tg_test/1/double_scalar" => Ok(Value::Number(2.5)),
+            "sys/motor-1/x/position" => Ok(Value::Number(10.0)),
+            "sys/tg_test/1/double_spectrum" => Ok(Value::Array(vec![1.0, 2.0, 3.0, 6.0])),
+            "sys/tg_test/1/state" => Ok(Value::Str("ON".to_string())),
+            _ => Err(anyhow!("not watched").into()),
+        }
+    }
+
+    fn evaluate_source(source: &str) -> Value {
+        Expression::parse(source)
+            .unwrap()
+            .evaluate(&lookup)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let tests = vec![
+            ("sys/tg_test/1/double_scalar * 2", Value::Number(5.0)),
+            ("1 + 2 * 3 - 4 / 2", Value::Number(5.0)),
+            ("-2 ^ 2", Value::Number(-4.0)),
+            ("2 ^ 3 ^ 2", Value::Number(512.0)),
+            ("(1 + 2) * 3 % 4", Value::Number(1.0)),
+            (
+                "sys/motor-1/x/position - sys/tg_test/1/double_scalar",
+                Value::Number(7.5),
+            ),
+            (
+                "abs(sys/tg_test/1/double_scalar - 3) < 0.6",
+                Value::Bool(true),
+            ),
+            ("1 < 2 && !(2 <= 1) || false", Value::Bool(true)),
+            ("mean(sys/tg_test/1/double_spectrum)", Value::Number(3.0)),
+            ("max(sys/tg_test/1/double_spectrum)", Value::Number(6.0)),
+            ("min(4, 1e-1, 2)", Value::Number(0.1)),
+            ("len(sys/tg_test/1/double_spectrum)", Value::Number(4.0)),
+            ("sys/tg_test/1/double_spectrum[3]", Value::Number(6.0)),
+            (
+                "sys/tg_test/1/double_spectrum * 2",
+                Value::Array(vec![2.0, 4.0, 6.0, 12.0]),
+            ),
+            ("sys/tg_test/1/state == \"ON\"", Value::Bool(true)),
+            (
+                "if(sys/tg_test/1/double_scalar > 2, 1, 0)",
+                Value::Number(1.0),
+            ),
+            ("error(sys/tg_test/1/missing)", Value::Bool(true)),
+            ("error(sys/tg_test/1/double_scalar)", Value::Bool(false)),
+        ];
+        for (source, expected) in tests {
+            assert_eq!(evaluate_source(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_inputs() {
+        let expression = Expression::parse("a/b/c/d + max(a/b/c/e, a/b/c/d) * x/y/z/w[0]").unwrap();
+        assert_eq!(expression.inputs(), vec!["a/b/c/d", "a/b/c/e", "x/y/z/w"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let parse_errors = vec![
+            ("1 +", "Unexpected end of expression"),
+            ("a/b/c * 2", "[a/b/c] at position 1 is not of the form"),
+            ("foo(1)", "Unknown function [foo] at position 1"),
+            ("abs(1, 2)", "Wrong number of arguments for [abs]"),
+            ("(1 + 2", "Expected ) at position 7"),
+            ("1 2", "Unexpected token at position 3"),
+            ("1 $ 2", "Unexpected character [$] at position 3"),
+        ];
+        for (source, expected) in parse_errors {
+            let err = Expression::parse(source).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{}: {}", source, err);
+        }
+
+        let evaluation_errors = vec![
+            (
+                "sys/tg_test/1/missing + 1",
+                "sys/tg_test/1/missing: not watched",
+            ),
+            ("true + 1", "Cannot apply Add to a boolean and a number"),
+            ("sys/tg_test/1/double_spectrum[4]", "Index 4 out of range"),
+        ];
+        for (source, expected) in evaluation_errors {
+            let err = Expression::parse(source)
+                .unwrap()
+                .evaluate(&lookup)
+                .unwrap_err()
+                .to_string();
+            assert!(err.starts_with(expected), "{}: {}", source, err);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::derived::{evaluate_derived, DerivedAttributes, DERIVED_DEVICE};
+use crate::recorder::{Recorder, RecorderConfig};
+use crate::snapshot::{diff, DiffStatus, Snapshot, SnapshotDiff, SnapshotEntry};
+use crate::tango_utils::{expand_attribute_pattern, TangoDevicesLookup};
+use crate::views::watchlist::{update_device_readings, AttributeReading};
+use crate::views::AttributeReadings;
+use crate::watchlist_file::{split_attribute_name, WatchlistFile};
+use anyhow::anyhow;
+use log::info;
+use serde_json::json;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+// The `record` subcommand, polls the attributes and records the readings
+// without the terminal UI until the duration elapses or it is interrupted.
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    pub watchlist_path: Option<String>,
+    pub attributes: Vec<String>,
+    pub recorder_config: RecorderConfig,
+    pub interval: Duration,
+    pub duration: Option<Duration>,
+}
+
+// Match `*` wildcard patterns against the exported devices
+fn expand_patterns(patterns: &[String]) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let devices = TangoDevicesLookup::build()?.devices;
+    Ok(patterns
+        .iter()
+        .flat_map(|pattern| expand_attribute_pattern(pattern, &devices))
+        .collect())
+}
+
+fn watched_attributes(
+    watchlist_path: Option<&str>,
+    attributes: &[String],
+    expand: impl FnOnce(&[String]) -> Result<Vec<(String, String)>, Box<dyn Error>>,
+) -> Result<(AttributeReadings, DerivedAttributes), Box<dyn Error>> {
+    let mut attr_map = AttributeReadings::default();
+    let mut derived = DerivedAttributes::default();
+    let mut patterns = Vec::new();
+    if let Some(watchlist_path) = watchlist_path {
+        let watchlist_file = WatchlistFile::load(watchlist_path)?;
+        for entry in watchlist_file.entries {
+            if entry.is_wildcard() {
+                patterns.push(entry.full_name());
+                continue;
+            }
+            attr_map
+                .entry(entry.device_name)
+                .or_default()
+                .insert(entry.attr_name, AttributeReading::default());
+        }
+        for entry in watchlist_file.derived {
+            for input in entry.expression.inputs() {
+                if let Some((device_name, attr_name)) = split_attribute_name(&input) {
+                    attr_map
+                        .entry(device_name)
+                        .or_default()
+                        .insert(attr_name, AttributeReading::default());
+                }
+            }
+            attr_map
+                .entry(DERIVED_DEVICE.to_string())
+                .or_default()
+                .insert(entry.name.clone(), AttributeReading::default());
+            derived.insert(entry.name, entry.expression);
+        }
+    }
+    for full_name in attributes {
+        if full_name.contains('*') {
+            patterns.push(full_name.clone());
+            continue;
+        }
+        let (device_name, attr_name) = split_attribute_name(full_name).ok_or_else(|| {
+            anyhow!(
+                "[{}] is not of the form domain/family/member/attribute",
+                full_name
+            )
+        })?;
+        attr_map
+            .entry(device_name)
+            .or_default()
+            .insert(attr_name, AttributeReading::default());
+    }
+    if !patterns.is_empty() {
+        for (device_name, attr_name) in expand(&patterns)? {
+            attr_map
+                .entry(device_name)
+                .or_default()
+                .insert(attr_name, AttributeReading::default());
+        }
+    }
+    if attr_map.is_empty() {
+        return Err(
+            anyhow!("No attributes given, pass a watchlist file or attribute names").into(),
+        );
+    }
+    Ok((attr_map, derived))
+}
+
+fn read_all(attr_map: &mut AttributeReadings, derived: &DerivedAttributes) {
+    for (device_name, attr_readings) in attr_map
+        .iter_mut()
+        .filter(|(device_name, _)| *device_name != DERIVED_DEVICE)
+    {
+        update_device_readings(device_name, attr_readings);
+    }
+    evaluate_derived(attr_map, derived);
+}
+
+pub fn run_record(options: &RecordOptions) -> Result<(), Box<dyn Error>> {
+    let (mut attr_map, derived) = watched_attributes(
+        options.watchlist_path.as_deref(),
+        &options.attributes,
+        expand_patterns,
+    )?;
+    let mut recorder = Recorder::start(&options.recorder_config)?;
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let interrupted_handler = Arc::clone(&interrupted);
+    ctrlc::set_handler(move || interrupted_handler.store(true, Ordering::SeqCst))?;
+
+    info!("Recording to {}", recorder.path().display());
+    let started = Instant::now();
+    loop {
+        let poll_started = Instant::now();
+        read_all(&mut attr_map, &derived);
+        recorder.record(&attr_map)?;
+
+        let finished = match options.duration {
+            Some(duration) => started.elapsed() >= duration,
+            None => false,
+        };
+        if finished || interrupted.load(Ordering::SeqCst) {
+            break;
+        }
+        // Sleep in short steps to stop promptly when interrupted
+        while poll_started.elapsed() < options.interval && !interrupted.load(Ordering::SeqCst) {
+            thread::sleep(
+                options
+                    .interval
+                    .saturating_sub(poll_started.elapsed())
+                    .min(Duration::from_millis(100)),
+            );
+        }
+    }
+    info!(
+        "Stopped recording to {} after {} lines",
+        recorder.path().display(),
+        recorder.lines_written
+    );
+    Ok(())
+}
+
+// The `snapshot` subcommand, reads the attributes once and saves their values
+pub fn run_snapshot(
+    watchlist_path: Option<&str>,
+    attributes: &[String],
+    output: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let (mut attr_map, derived) = watched_attributes(watchlist_path, attributes, expand_patterns)?;
+    read_all(&mut attr_map, &derived);
+    let snapshot = Snapshot::capture(&attr_map, SystemTime::now());
+    let path = PathBuf::from(output.map_or_else(|| snapshot.file_name(), String::from));
+    snapshot.save(&path)?;
+    println!(
+        "Saved {} attributes to {}",
+        snapshot.entries.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn display_entry(entry: &Option<SnapshotEntry>) -> String {
+    match entry {
+        Some(entry) if entry.quality == "VALID" || entry.quality.is_empty() => entry.value.clone(),
+        Some(entry) => format!("{} ({})", entry.value, entry.quality),
+        None => "-".to_string(),
+    }
+}
+
+// The `diff` subcommand, compares a snapshot with another one or with the live
+// values. Live values are read for the attributes of the watchlist file when
+// given, otherwise for the attributes in the snapshot.
+pub fn run_diff(
+    before_path: &str,
+    after_path: Option<&str>,
+    watchlist_path: Option<&str>,
+    show_unchanged: bool,
+    as_json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let before = Snapshot::load(Path::new(before_path))?;
+    let after = match after_path {
+        Some(after_path) => Snapshot::load(Path::new(after_path))?,
+        None => {
+            let attributes: Vec<String> = match watchlist_path {
+                Some(_) => Vec::new(),
+                None => before
+                    .entries
+                    .keys()
+                    .filter(|name| split_attribute_name(name).is_some())
+                    .cloned()
+                    .collect(),
+            };
+            let (mut attr_map, derived) =
+                watched_attributes(watchlist_path, &attributes, expand_patterns)?;
+            read_all(&mut attr_map, &derived);
+            Snapshot::capture(&attr_map, SystemTime::now())
+        }
+    };
+    let all_diffs = diff(&before, &after);
+    let diffs: Vec<&SnapshotDiff> = all_diffs
+        .iter()
+        .filter(|diff| show_unchanged || diff.status != DiffStatus::Unchanged)
+        .collect();
+
+    if as_json {
+        let diffs: Vec<serde_json::Value> = diffs
+            .iter()
+            .map(|diff| {
+                json!({
+                    "attribute": diff.name,
+                    "status": diff.status.to_string(),
+                    "before": diff.before.as_ref().map(|entry| entry.value.clone()),
+                    "after": diff.after.as_ref().map(|entry| entry.value.clone()),
+                    "delta": diff.delta,
+                })
+            })
+            .collect();
+        println!("{:#}", json!(diffs));
+        return Ok(());
+    }
+    let name_width = diffs
+        .iter()
+        .map(|diff| diff.name.len())
+        .max()
+        .unwrap_or_default();
+    for diff in diffs {
+        let delta = match diff.delta {
+            Some(delta) if diff.status == DiffStatus::Changed => format!("  ({:+})", delta),
+            _ => String::new(),
+        };
+        println!(
+            "{:<9} {:<name_width$}  {} -> {}{}",
+            diff.status,
+            diff.name,
+            display_entry(&diff.before),
+            display_entry(&diff.after),
+            delta,
+        );
+    }
+    let count = |status: DiffStatus| {
+        all_diffs
+            .iter()
+            .filter(|diff| diff.status == status)
+            .count()
+    };
+    println!(
+        "{} changed, {} unchanged, {} missing, {} added",
+        count(DiffStatus::Changed),
+        count(DiffStatus::Unchanged),
+        count(DiffStatus::Missing),
+        count(DiffStatus::Added)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod headless_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_wildcard_watchlist() {
+        let watchlist_path = std::env::temp_dir().join("headless_wildcard_watchlist.txt");
+        fs::write(
+            &watchlist_path,
+            "sys/tg_test/1/double_scalar\nmid-dish/*/*/State\n",
+        )
+        .unwrap();
+        let (attr_map, _) = watched_attributes(
+            watchlist_path.to_str(),
+            &["*/subarray/*/obsState".to_string()],
+            |patterns| {
+                assert_eq!(patterns, ["mid-dish/*/*/State", "*/subarray/*/obsState"]);
+                Ok(vec![(
+                    "mid-dish/dish-manager/SKA001".to_string(),
+                    "State".to_string(),
+                )])
+            },
+        )
+        .unwrap();
+        fs::remove_file(&watchlist_path).unwrap();
+
+        assert!(attr_map
+            .keys()
+            .all(|device_name| !device_name.contains('*')));
+        assert_eq!(
+            attr_map.keys().collect::<Vec<_>>(),
+            ["mid-dish/dish-manager/SKA001", "sys/tg_test/1"]
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use crate::tango_utils::attr_value_as_f64_array;
+use anyhow::anyhow;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use std::error::Error;
+use std::fmt;
+use tango_controls_client_sys::types::{AttrDataFormat, AttrValue};
+
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (68, 1, 84),
+    (71, 44, 122),
+    (59, 81, 139),
+    (44, 113, 142),
+    (33, 144, 141),
+    (39, 173, 129),
+    (92, 200, 99),
+    (170, 220, 50),
+    (253, 231, 37),
+];
+
+const JET: [(u8, u8, u8); 5] = [
+    (0, 0, 143),
+    (0, 128, 255),
+    (128, 255, 128),
+    (255, 128, 0),
+    (128, 0, 0),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pixels {
+    Gray(Vec<f64>),
+    Rgb(Vec<(u8, u8, u8)>),
+}
+
+// An image attribute value, row major
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageData {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Pixels,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMap {
+    #[default]
+    Gray,
+    Viridis,
+    Jet,
+}
+
+impl ColorMap {
+    pub fn next(&self) -> ColorMap {
+        match self {
+            ColorMap::Gray => ColorMap::Viridis,
+            ColorMap::Viridis => ColorMap::Jet,
+            ColorMap::Jet => ColorMap::Gray,
+        }
+    }
+
+    // Map `t` in [0, 1] to a colour
+    pub fn color(&self, t: f64) -> (u8, u8, u8) {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        match self {
+            ColorMap::Gray => {
+                let level = (t * 255.0).round() as u8;
+                (level, level, level)
+            }
+            ColorMap::Viridis => interpolate(&VIRIDIS, t),
+            ColorMap::Jet => interpolate(&JET, t),
+        }
+    }
+}
+
+impl fmt::Display for ColorMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorMap::Gray => write!(f, "gray"),
+            ColorMap::Viridis => write!(f, "viridis"),
+            ColorMap::Jet => write!(f, "jet"),
+        }
+    }
+}
+
+fn interpolate(stops: &[(u8, u8, u8)], t: f64) -> (u8, u8, u8) {
+    let position = t * (stops.len() - 1) as f64;
+    let ix = (position.floor() as usize).min(stops.len() - 2);
+    let fraction = position - ix as f64;
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+    let (a, b) = (stops[ix], stops[ix + 1]);
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+impl ImageData {
+    pub fn from_attr_value(
+        attr_value: &AttrValue,
+        format: Option<AttrDataFormat>,
+        dim_x: usize,
+        dim_y: usize,
+    ) -> Result<ImageData, Box<dyn Error>> {
+        if let AttrValue::DevEncoded((encoded_format, data)) = attr_value {
+            return ImageData::decode(encoded_format, data);
+        }
+        if format != Some(AttrDataFormat::IMAGE) {
+            return Err(anyhow!("Not an image attribute").into());
+        }
+        let pixels = attr_value_as_f64_array(attr_value)
+            .ok_or_else(|| anyhow!("Image data type not supported"))?;
+        if pixels.len() < dim_x * dim_y {
+            return Err(anyhow!(
+                "Image of {}x{} has only {} pixels",
+                dim_x,
+                dim_y,
+                pixels.len()
+            )
+            .into());
+        }
+        Ok(ImageData {
+            width: dim_x,
+            height: dim_y,
+            pixels: Pixels::Gray(pixels),
+        })
+    }
+
+    // Decode the DevEncoded formats produced by Tango's EncodedAttribute.
+    // The raw formats start with the width and height as big endian u16.
+    pub fn decode(encoded_format: &str, data: &[u8]) -> Result<ImageData, Box<dyn Error>> {
+        match encoded_format.to_uppercase().as_str() {
+            "GRAY8" | "GRAY16" | "RGB24" => {
+                if data.len() < 4 {
+                    return Err(anyhow!("Encoded image has no header").into());
+                }
+                let width = u16::from_be_bytes([data[0], data[1]]) as usize;
+                let height = u16::from_be_bytes([data[2], data[3]]) as usize;
+                let body = &data[4..];
+                let pixels = match encoded_format.to_uppercase().as_str() {
+                    "GRAY8" => Pixels::Gray(body.iter().map(|val| *val as f64).collect()),
+                    "GRAY16" => Pixels::Gray(
+                        body.chunks_exact(2)
+                            .map(|val| u16::from_be_bytes([val[0], val[1]]) as f64)
+                            .collect(),
+                    ),
+                    _ => Pixels::Rgb(
+                        body.chunks_exact(3)
+                            .map(|val| (val[0], val[1], val[2]))
+                            .collect(),
+                    ),
+                };
+                ImageData::checked(width, height, pixels)
+            }
+            "JPEG_GRAY8" | "JPEG_RGB" | "JPEG" => {
+                let mut decoder = jpeg_decoder::Decoder::new(data);
+                let decoded = decoder.decode()?;
+                let info = decoder
+                    .info()
+                    .ok_or_else(|| anyhow!("JPEG image has no header"))?;
+                let pixels = match info.pixel_format {
+                    jpeg_decoder::PixelFormat::L8 => {
+                        Pixels::Gray(decoded.iter().map(|val| *val as f64).collect())
+                    }
+                    jpeg_decoder::PixelFormat::L16 => Pixels::Gray(
+                        decoded
+                            .chunks_exact(2)
+                            .map(|val| u16::from_be_bytes([val[0], val[1]]) as f64)
+                            .collect(),
+                    ),
+                    jpeg_decoder::PixelFormat::RGB24 => Pixels::Rgb(
+                        decoded
+                            .chunks_exact(3)
+                            .map(|val| (val[0], val[1], val[2]))
+                            .collect(),
+                    ),
+                    jpeg_decoder::PixelFormat::CMYK32 => {
+                        return Err(anyhow!("CMYK JPEG images are not supported").into())
+                    }
+                };
+                ImageData::checked(info.width as usize, info.height as usize, pixels)
+            }
+            _ => Err(anyhow!("Encoded format [{}] not supported", encoded_format).into()),
+        }
+    }
+
+    fn checked(width: usize, height: usize, pixels: Pixels) -> Result<ImageData, Box<dyn Error>> {
+        let length = match &pixels {
+            Pixels::Gray(values) => values.len(),
+            Pixels::Rgb(values) => values.len(),
+        };
+        if length < width * height {
+            return Err(anyhow!("Image of {}x{} has only {} pixels", width, height, length).into());
+        }
+        Ok(ImageData {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    // The gray level range, ignoring the `clip_percent` darkest and brightest pixels
+    pub fn auto_contrast(&self, clip_percent: f64) -> (f64, f64) {
+        let mut values = match &self.pixels {
+            Pixels::Gray(values) => values
+                .iter()
+                .filter(|val| val.is_finite())
+                .cloned()
+                .collect::<Vec<f64>>(),
+            Pixels::Rgb(_) => return (0.0, 255.0),
+        };
+        if values.is_empty() {
+            return (0.0, 1.0);
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        let skip = ((values.len() as f64) * clip_percent / 100.0) as usize;
+        let low = values[skip.min(values.len() - 1)];
+        let high = values[values.len() - 1 - skip.min(values.len() - 1)];
+        (low, high.max(low))
+    }
+
+    pub fn describe_pixel(&self, x: usize, y: usize) -> Option<String> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let ix = y * self.width + x;
+        match &self.pixels {
+            Pixels::Gray(values) => values.get(ix).map(|val| val.to_string()),
+            Pixels::Rgb(values) => values
+                .get(ix)
+                .map(|(r, g, b)| format!("R {} G {} B {}", r, g, b)),
+        }
+    }
+
+    fn pixel_color(&self, x: usize, y: usize, color_map: ColorMap, clip: (f64, f64)) -> Color {
+        let ix = y * self.width + x;
+        let (r, g, b) = match &self.pixels {
+            Pixels::Gray(values) => {
+                let value = values.get(ix).cloned().unwrap_or_default();
+                let range = clip.1 - clip.0;
+                let t = if range > 0.0 {
+                    (value - clip.0) / range
+                } else {
+                    0.0
+                };
+                color_map.color(t)
+            }
+            Pixels::Rgb(values) => values.get(ix).cloned().unwrap_or_default(),
+        };
+        Color::Rgb(r, g, b)
+    }
+
+    // The number of image pixels per terminal column so that the image fits in `area`.
+    // Every terminal cell holds two pixels vertically.
+    pub fn scale(&self, area: Rect) -> f64 {
+        if area.width == 0 || area.height == 0 {
+            return 1.0;
+        }
+        let scale_x = self.width as f64 / area.width as f64;
+        let scale_y = self.height as f64 / (area.height as f64 * 2.0);
+        scale_x.max(scale_y).max(1.0)
+    }
+}
+
+// Renders the image with one upper half block per cell, the foreground
+// colour is the upper pixel and the background colour the lower pixel.
+pub struct HalfBlockImage<'a> {
+    pub image: &'a ImageData,
+    pub color_map: ColorMap,
+    pub clip: (f64, f64),
+    pub cursor: Option<(usize, usize)>,
+}
+
+impl<'a> Widget for HalfBlockImage<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.image.width == 0 || self.image.height == 0 {
+            return;
+        }
+        let scale = self.image.scale(area);
+        let columns = ((self.image.width as f64 / scale).ceil() as u16).min(area.width);
+        let rows = ((self.image.height as f64 / scale / 2.0).ceil() as u16).min(area.height);
+        let cursor_cell = self
+            .cursor
+            .map(|(x, y)| ((x as f64 / scale) as u16, (y as f64 / scale / 2.0) as u16));
+        for row in 0..rows {
+            for column in 0..columns {
+                let x = ((column as f64 * scale) as usize).min(self.image.width - 1);
+                let y_top = ((row as f64 * 2.0 * scale) as usize).min(self.image.height - 1);
+                let y_bottom =
+                    (((row as f64 * 2.0 + 1.0) * scale) as usize).min(self.image.height - 1);
+                let top = self.image.pixel_color(x, y_top, self.color_map, self.clip);
+                let bottom = self
+                    .image
+                    .pixel_color(x, y_bottom, self.color_map, self.clip);
+                let cell = buf.get_mut(area.x + column, area.y + row);
+                if cursor_cell == Some((column, row)) {
+                    cell.set_symbol("+")
+                        .set_style(Style::default().fg(Color::LightRed).bg(bottom));
+                } else {
+                    cell.set_symbol("▀")
+                        .set_style(Style::default().fg(top).bg(bottom));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod image_data_tests {
+    use super::*;
+
+    // A baseline 1x1 grayscale JPEG whose only block has DC and AC coefficients of 0
+    fn gray_jpeg() -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xDB, 0x00, 0x43, 0x00];
+        data.extend([1; 64]);
+        data.extend([
+            0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x01, 0x00, 0x01, 0x01, 0x01, 0x11, 0x00,
+        ]);
+        // Huffman tables with a single one bit code, for a DC difference of 0 and end of block
+        for class in [0x00, 0x10] {
+            data.extend([0xFF, 0xC4, 0x00, 0x14, class, 0x01]);
+            data.extend([0; 16]);
+        }
+        data.extend([0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00]);
+        data.extend([0x3F, 0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn test_from_attr_value() {
+        let image = ImageData::from_attr_value(
+            &AttrValue::UCharArray(vec![0, 64, 128, 255]),
+            Some(AttrDataFormat::IMAGE),
+            2,
+            2,
+        )
+        .unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, Pixels::Gray(vec![0.0, 64.0, 128.0, 255.0]));
+        assert_eq!(image.describe_pixel(1, 1), Some("255".to_string()));
+        assert_eq!(image.describe_pixel(2, 0), None);
+
+        let image = ImageData::from_attr_value(
+            &AttrValue::UShortArray(vec![1, 2, 3, 65535, 5, 6]),
+            Some(AttrDataFormat::IMAGE),
+            3,
+            2,
+        )
+        .unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.describe_pixel(0, 1), Some("65535".to_string()));
+
+        let short = AttrValue::UShortArray(vec![1, 2, 3]);
+        assert!(ImageData::from_attr_value(&short, Some(AttrDataFormat::IMAGE), 2, 2).is_err());
+        assert!(ImageData::from_attr_value(&short, Some(AttrDataFormat::SPECTRUM), 3, 1).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let image = ImageData::decode("GRAY8", &[0, 2, 0, 1, 10, 20]).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, Pixels::Gray(vec![10.0, 20.0]));
+
+        let image = ImageData::decode("gray16", &[0, 1, 0, 2, 1, 0, 0, 7]).unwrap();
+        assert_eq!(image.pixels, Pixels::Gray(vec![256.0, 7.0]));
+
+        let image = ImageData::decode("RGB24", &[0, 1, 0, 1, 1, 2, 3]).unwrap();
+        assert_eq!(image.describe_pixel(0, 0), Some("R 1 G 2 B 3".to_string()));
+
+        assert!(ImageData::decode("GRAY8", &[0, 2]).is_err());
+        assert!(ImageData::decode("GRAY8", &[0, 2, 0, 2, 1, 2, 3]).is_err());
+        assert!(ImageData::decode("PNG", &[0, 1, 0, 1, 0]).is_err());
+
+        let image = ImageData::decode("JPEG_GRAY8", &gray_jpeg()).unwrap();
+        assert_eq!((image.width, image.height), (1, 1));
+        assert_eq!(image.pixels, Pixels::Gray(vec![128.0]));
+        assert!(ImageData::decode("JPEG", &[0xFF, 0xD8, 0xFF]).is_err());
+    }
+
+    #[test]
+    fn test_auto_contrast() {
+        let constant = ImageData {
+            width: 2,
+            height: 2,
+            pixels: Pixels::Gray(vec![5.0; 4]),
+        };
+        let clip = constant.auto_contrast(1.0);
+        assert_eq!(clip, (5.0, 5.0));
+        // An empty range must not divide by zero
+        assert_eq!(
+            constant.pixel_color(1, 1, ColorMap::Gray, clip),
+            Color::Rgb(0, 0, 0)
+        );
+
+        let ramp = ImageData {
+            width: 10,
+            height: 1,
+            pixels: Pixels::Gray((0..10).map(f64::from).chain([f64::NAN]).collect()),
+        };
+        assert_eq!(ramp.auto_contrast(0.0), (0.0, 9.0));
+        assert_eq!(ramp.auto_contrast(10.0), (1.0, 8.0));
+        assert_eq!(
+            ramp.pixel_color(9, 0, ColorMap::Gray, (1.0, 8.0)),
+            Color::Rgb(255, 255, 255)
+        );
+
+        let empty = ImageData {
+            width: 0,
+            height: 0,
+            pixels: Pixels::Gray(vec![]),
+        };
+        assert_eq!(empty.auto_contrast(1.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_color_maps() {
+        assert_eq!(ColorMap::Gray.color(0.0), (0, 0, 0));
+        assert_eq!(ColorMap::Gray.color(0.5), (128, 128, 128));
+        assert_eq!(ColorMap::Gray.color(2.0), (255, 255, 255));
+        assert_eq!(ColorMap::Gray.color(f64::NAN), (0, 0, 0));
+        assert_eq!(ColorMap::Viridis.color(0.0), VIRIDIS[0]);
+        assert_eq!(ColorMap::Viridis.color(1.0), VIRIDIS[8]);
+        assert_eq!(ColorMap::Jet.color(-1.0), JET[0]);
+        assert_eq!(ColorMap::Jet.color(0.5), JET[2]);
+        assert_eq!(ColorMap::Jet.color(1.0), JET[4]);
+        assert_eq!(ColorMap::Jet.color(0.125), (0, 64, 199));
+
+        let mut color_map = ColorMap::default();
+        for expected in ["viridis", "jet", "gray"] {
+            color_map = color_map.next();
+            assert_eq!(color_map.to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use tango_controls_client_sys::types::AttributeInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitLevel {
+    Normal,
+    Warning,
+    Alarm,
+}
+
+// The alarm, warning and range limits of a scalar attribute.
+// Limits that are not configured are `None`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AttributeLimits {
+    pub min_alarm: Option<f64>,
+    pub max_alarm: Option<f64>,
+    pub min_warning: Option<f64>,
+    pub max_warning: Option<f64>,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+}
+
+// Tango reports limits that are not set as "Not specified"
+fn parse_limit(limit: &str) -> Option<f64> {
+    limit
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|limit| limit.is_finite())
+}
+
+impl AttributeLimits {
+    pub fn from_attribute_info(attribute_info: &AttributeInfo) -> AttributeLimits {
+        AttributeLimits {
+            min_alarm: parse_limit(&attribute_info.min_alarm),
+            max_alarm: parse_limit(&attribute_info.max_alarm),
+            min_warning: parse_limit(&attribute_info.min_warning),
+            max_warning: parse_limit(&attribute_info.max_warning),
+            min_value: parse_limit(&attribute_info.min_value),
+            max_value: parse_limit(&attribute_info.max_value),
+        }
+    }
+
+    // Limits set in `overrides` take precedence
+    pub fn merge(&self, overrides: &AttributeLimits) -> AttributeLimits {
+        AttributeLimits {
+            min_alarm: overrides.min_alarm.or(self.min_alarm),
+            max_alarm: overrides.max_alarm.or(self.max_alarm),
+            min_warning: overrides.min_warning.or(self.min_warning),
+            max_warning: overrides.max_warning.or(self.max_warning),
+            min_value: overrides.min_value.or(self.min_value),
+            max_value: overrides.max_value.or(self.max_value),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) -> bool {
+        match name {
+            "min_alarm" => self.min_alarm = Some(value),
+            "max_alarm" => self.max_alarm = Some(value),
+            "min_warning" => self.min_warning = Some(value),
+            "max_warning" => self.max_warning = Some(value),
+            "min_value" => self.min_value = Some(value),
+            "max_value" => self.max_value = Some(value),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == AttributeLimits::default()
+    }
+
+    // `None` when neither alarm nor warning limits are configured
+    pub fn level(&self, value: f64) -> Option<LimitLevel> {
+        let has_limits = self.min_alarm.is_some()
+            || self.max_alarm.is_some()
+            || self.min_warning.is_some()
+            || self.max_warning.is_some();
+        if !has_limits {
+            return None;
+        }
+        let below = |limit: Option<f64>| matches!(limit, Some(limit) if value < limit);
+        let above = |limit: Option<f64>| matches!(limit, Some(limit) if value > limit);
+        if below(self.min_alarm) || above(self.max_alarm) {
+            Some(LimitLevel::Alarm)
+        } else if below(self.min_warning) || above(self.max_warning) {
+            Some(LimitLevel::Warning)
+        } else {
+            Some(LimitLevel::Normal)
+        }
+    }
+
+    // Where `value` sits between `min_value` and `max_value`, from 0 to 1
+    pub fn position(&self, value: f64) -> Option<f64> {
+        let (min, max) = (self.min_value?, self.max_value?);
+        if max <= min {
+            return None;
+        }
+        Some(((value - min) / (max - min)).clamp(0.0, 1.0))
+    }
+
+    pub fn gauge(&self, value: f64, width: usize) -> Option<String> {
+        let position = self.position(value)?;
+        let filled = (position * width as f64).round() as usize;
+        Some(format!(
+            "{}{}",
+            "█".repeat(filled),
+            "░".repeat(width.saturating_sub(filled))
+        ))
+    }
+}
+
+#[cfg(test)]
+mod limits_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit(" 2.5 "), Some(2.5));
+        assert_eq!(parse_limit("-10"), Some(-10.0));
+        assert_eq!(parse_limit("Not specified"), None);
+        assert_eq!(parse_limit("NaN"), None);
+        assert_eq!(parse_limit("inf"), None);
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(AttributeLimits::default().level(1e9), None);
+
+        let limits = AttributeLimits {
+            min_alarm: Some(-10.0),
+            max_alarm: Some(10.0),
+            min_warning: Some(-5.0),
+            max_warning: Some(5.0),
+            ..AttributeLimits::default()
+        };
+        assert_eq!(limits.level(0.0), Some(LimitLevel::Normal));
+        assert_eq!(limits.level(5.0), Some(LimitLevel::Normal));
+        assert_eq!(limits.level(6.0), Some(LimitLevel::Warning));
+        assert_eq!(limits.level(-6.0), Some(LimitLevel::Warning));
+        // Past both limits the alarm wins
+        assert_eq!(limits.level(11.0), Some(LimitLevel::Alarm));
+        assert_eq!(limits.level(-11.0), Some(LimitLevel::Alarm));
+
+        let max_only = AttributeLimits {
+            max_warning: Some(1.0),
+            ..AttributeLimits::default()
+        };
+        assert_eq!(max_only.level(-1e9), Some(LimitLevel::Normal));
+        assert_eq!(max_only.level(2.0), Some(LimitLevel::Warning));
+    }
+
+    #[test]
+    fn test_merge() {
+        let device = AttributeLimits {
+            min_alarm: Some(0.0),
+            max_alarm: Some(100.0),
+            ..AttributeLimits::default()
+        };
+        let mut overrides = AttributeLimits::default();
+        assert!(overrides.set("max_alarm", 50.0));
+        assert!(overrides.set("max_value", 60.0));
+        assert!(!overrides.set("max_limit", 1.0));
+        assert_eq!(
+            device.merge(&overrides),
+            AttributeLimits {
+                min_alarm: Some(0.0),
+                max_alarm: Some(50.0),
+                max_value: Some(60.0),
+                ..AttributeLimits::default()
+            }
+        );
+        assert_eq!(device.merge(&AttributeLimits::default()), device);
+        assert!(AttributeLimits::default().is_empty());
+        assert!(!overrides.is_empty());
+    }
+
+    #[test]
+    fn test_gauge() {
+        let limits = AttributeLimits {
+            min_value: Some(0.0),
+            max_value: Some(10.0),
+            ..AttributeLimits::default()
+        };
+        assert_eq!(limits.position(2.5), Some(0.25));
+        assert_eq!(limits.position(-5.0), Some(0.0));
+        assert_eq!(limits.position(50.0), Some(1.0));
+        assert_eq!(limits.gauge(5.0, 4), Some("██░░".to_string()));
+        assert_eq!(limits.gauge(20.0, 4), Some("████".to_string()));
+        assert_eq!(limits.gauge(-1.0, 4), Some("░░░░".to_string()));
+
+        let empty_range = AttributeLimits {
+            min_value: Some(10.0),
+            max_value: Some(10.0),
+            ..AttributeLimits::default()
+        };
+        assert_eq!(empty_range.position(10.0), None);
+        assert_eq!(empty_range.gauge(10.0, 4), None);
+        assert_eq!(AttributeLimits::default().gauge(1.0, 4), None);
+    }
+}
@@ -0,0 +1,344 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+
+// Inputs kept in the history of each command
+const HISTORY_SIZE: usize = 50;
+
+// A text input with a cursor, word-wise editing and history. The text can span
+// several lines, ALT+ENTER starts a new one and pasted new lines are kept.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineEditor {
+    text: String,
+    // Byte offset into `text`, always on a character boundary
+    cursor: usize,
+    // The history entry shown, counted back from the latest
+    history_ix: Option<usize>,
+    // The text being typed before browsing the history
+    draft: String,
+}
+
+// What a key did to the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorAction {
+    Edited,
+    Submit,
+    // Up or down on the first or last line
+    HistoryPrevious,
+    HistoryNext,
+    Ignored,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '-'
+}
+
+// Add the text to `history`, skipping repeats of the latest entry
+pub fn push_history(history: &mut Vec<String>, text: &str) {
+    if text.trim().is_empty() || history.last().map(String::as_str) == Some(text) {
+        return;
+    }
+    history.push(text.to_string());
+    if history.len() > HISTORY_SIZE {
+        history.remove(0);
+    }
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn previous_boundary(&self, ix: usize) -> usize {
+        self.text[..ix]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(ix, _)| ix)
+    }
+
+    fn next_boundary(&self, ix: usize) -> usize {
+        self.text[ix..]
+            .chars()
+            .next()
+            .map_or(ix, |c| ix + c.len_utf8())
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |ix| ix + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |ix| self.cursor + ix)
+    }
+
+    // The start of the word before the cursor, skipping spaces and separators first
+    fn word_start(&self) -> usize {
+        let before = &self.text[..self.cursor];
+        let mut chars = before.char_indices().rev().peekable();
+        while let Some((_, c)) = chars.peek() {
+            if is_word_char(*c) {
+                break;
+            }
+            chars.next();
+        }
+        let mut start = chars.peek().map_or(0, |(ix, _)| *ix);
+        for (ix, c) in chars {
+            if !is_word_char(c) {
+                break;
+            }
+            start = ix;
+        }
+        start.min(self.cursor)
+    }
+
+    // The end of the word after the cursor, skipping spaces and separators first
+    fn word_end(&self) -> usize {
+        let mut end = self.cursor;
+        let mut in_word = false;
+        for (ix, c) in self.text[self.cursor..].char_indices() {
+            if is_word_char(c) {
+                in_word = true;
+            } else if in_word {
+                return self.cursor + ix;
+            }
+            end = self.cursor + ix + c.len_utf8();
+        }
+        end
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    // Pasted text keeps its new lines, other control characters are dropped
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .chars()
+            .filter(|c| *c == '\n' || !c.is_control())
+            .collect();
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    // Move the cursor to the same column of the line above or below
+    fn move_line(&mut self, up: bool) -> bool {
+        let line_start = self.line_start();
+        let column = self.text[line_start..self.cursor].chars().count();
+        let target_start = if up {
+            if line_start == 0 {
+                return false;
+            }
+            self.text[..line_start - 1]
+                .rfind('\n')
+                .map_or(0, |ix| ix + 1)
+        } else {
+            let line_end = self.line_end();
+            if line_end == self.text.len() {
+                return false;
+            }
+            line_end + 1
+        };
+        let target_line = self.text[target_start..].split('\n').next().unwrap_or("");
+        self.cursor = target_start
+            + target_line
+                .char_indices()
+                .nth(column)
+                .map_or(target_line.len(), |(ix, _)| ix);
+        true
+    }
+
+    pub fn handle_key(&mut self, key_event: &KeyEvent) -> EditorAction {
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+        match key_event.code {
+            KeyCode::Enter if alt => self.insert_char('\n'),
+            KeyCode::Enter => return EditorAction::Submit,
+            KeyCode::Char('w') if ctrl => self.delete_range(self.word_start(), self.cursor),
+            KeyCode::Char('u') if ctrl => self.delete_range(self.line_start(), self.cursor),
+            KeyCode::Char('k') if ctrl => {
+                let line_end = self.line_end();
+                self.text.replace_range(self.cursor..line_end, "");
+            }
+            KeyCode::Char(_) if ctrl => return EditorAction::Ignored,
+            KeyCode::Char(c) => self.insert_char(c),
+            KeyCode::Backspace if ctrl || alt => self.delete_range(self.word_start(), self.cursor),
+            KeyCode::Backspace => {
+                if self.cursor > 0 {
+                    self.delete_range(self.previous_boundary(self.cursor), self.cursor);
+                }
+            }
+            KeyCode::Delete if ctrl || alt => {
+                let word_end = self.word_end();
+                self.text.replace_range(self.cursor..word_end, "");
+            }
+            KeyCode::Delete => {
+                let next = self.next_boundary(self.cursor);
+                self.text.replace_range(self.cursor..next, "");
+            }
+            KeyCode::Left if ctrl || alt => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.previous_boundary(self.cursor),
+            KeyCode::Right => self.cursor = self.next_boundary(self.cursor),
+            KeyCode::Home if ctrl => self.cursor = 0,
+            KeyCode::End if ctrl => self.cursor = self.text.len(),
+            KeyCode::Home => self.cursor = self.line_start(),
+            KeyCode::End => self.cursor = self.line_end(),
+            KeyCode::Up => {
+                if !self.move_line(true) {
+                    return EditorAction::HistoryPrevious;
+                }
+            }
+            KeyCode::Down => {
+                if !self.move_line(false) {
+                    return EditorAction::HistoryNext;
+                }
+            }
+            _ => return EditorAction::Ignored,
+        }
+        EditorAction::Edited
+    }
+
+    // Show the previous entry of `history`, oldest first
+    pub fn history_previous(&mut self, history: &[String]) {
+        let ix = match self.history_ix {
+            Some(ix) if ix + 1 < history.len() => ix + 1,
+            Some(_) => return,
+            None if history.is_empty() => return,
+            None => {
+                self.draft = self.text.clone();
+                0
+            }
+        };
+        self.history_ix = Some(ix);
+        self.set_text(&history[history.len() - 1 - ix]);
+    }
+
+    // Show the next entry of `history`, or the draft after the latest one
+    pub fn history_next(&mut self, history: &[String]) {
+        match self.history_ix {
+            Some(0) | None => {
+                if self.history_ix.take().is_some() {
+                    let draft = std::mem::take(&mut self.draft);
+                    self.set_text(&draft);
+                }
+            }
+            Some(ix) => {
+                self.history_ix = Some(ix - 1);
+                self.set_text(&history[history.len() - ix]);
+            }
+        }
+    }
+
+    pub fn commit_history(&mut self, history: &mut Vec<String>) {
+        self.history_ix = None;
+        push_history(history, &self.text);
+    }
+
+    pub fn lines(&self) -> Vec<&str> {
+        self.text.split('\n').collect()
+    }
+
+    // The line and display column of the cursor
+    pub fn cursor_position(&self) -> (usize, usize) {
+        let before = &self.text[..self.cursor];
+        let row = before.matches('\n').count();
+        let line_start = self.line_start();
+        (row, before[line_start..].width())
+    }
+}
+
+#[cfg(test)]
+mod line_editor_tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn type_text(editor: &mut LineEditor, text: &str) {
+        for c in text.chars() {
+            editor.handle_key(&key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_cursor_editing() {
+        let mut editor = LineEditor::default();
+        type_text(&mut editor, "[1.5, 2.5, 3]");
+        editor.handle_key(&key(KeyCode::Left, KeyModifiers::CONTROL));
+        assert_eq!(editor.cursor_position(), (0, 11));
+        editor.handle_key(&key(KeyCode::Backspace, KeyModifiers::CONTROL));
+        assert_eq!(editor.text(), "[1.5, 3]");
+        editor.handle_key(&key(KeyCode::Home, KeyModifiers::NONE));
+        editor.handle_key(&key(KeyCode::Delete, KeyModifiers::NONE));
+        editor.handle_key(&key(KeyCode::End, KeyModifiers::NONE));
+        editor.handle_key(&key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(editor.text(), "1.5, 3");
+        editor.handle_key(&key(KeyCode::Left, KeyModifiers::NONE));
+        editor.handle_key(&key(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(editor.text(), "3");
+        assert_eq!(
+            editor.handle_key(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            EditorAction::Submit
+        );
+    }
+
+    #[test]
+    fn test_multi_line_input() {
+        let mut editor = LineEditor::default();
+        editor.insert_str("1 2 3\r\n4 5\r\n6");
+        assert_eq!(editor.lines(), vec!["1 2 3", "4 5", "6"]);
+        assert_eq!(editor.cursor_position(), (2, 1));
+        editor.handle_key(&key(KeyCode::Up, KeyModifiers::NONE));
+        editor.handle_key(&key(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(editor.cursor_position(), (0, 1));
+        assert_eq!(
+            editor.handle_key(&key(KeyCode::Up, KeyModifiers::NONE)),
+            EditorAction::HistoryPrevious
+        );
+        editor.handle_key(&key(KeyCode::End, KeyModifiers::NONE));
+        editor.handle_key(&key(KeyCode::Enter, KeyModifiers::ALT));
+        type_text(&mut editor, "7");
+        assert_eq!(editor.text(), "1 2 3\n7\n4 5\n6");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = Vec::new();
+        let mut editor = LineEditor::default();
+        for text in ["first", "second", "second"] {
+            editor.set_text(text);
+            editor.commit_history(&mut history);
+        }
+        assert_eq!(history, vec!["first", "second"]);
+
+        editor.set_text("draft");
+        editor.history_previous(&history);
+        assert_eq!(editor.text(), "second");
+        editor.history_previous(&history);
+        editor.history_previous(&history);
+        assert_eq!(editor.text(), "first");
+        editor.history_next(&history);
+        assert_eq!(editor.text(), "second");
+        editor.history_next(&history);
+        assert_eq!(editor.text(), "draft");
+    }
+}
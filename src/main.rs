@@ -1,15 +1,38 @@
+mod alerts;
 mod app;
+mod audit_log;
+mod cli;
+mod derived;
+mod expression;
+mod headless;
+mod image_data;
+mod limits;
+mod line_editor;
+mod recorder;
+mod snapshot;
 
 mod stateful_tree;
+mod statistics;
 mod tango_utils;
+mod trend;
+mod value_format;
+mod value_parser;
 mod views;
+mod watchlist_file;
 
+use alerts::{AlertNotify, Alerts};
+use anyhow::anyhow;
 use app::App;
+use audit_log::AuditLog;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event as CEvent, KeyCode, KeyEvent, KeyEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnableLineWrap, EnterAlternateScreen},
 };
+use headless::RecordOptions;
 use log::{error, info};
 use log4rs::{
     append::{
@@ -21,21 +44,27 @@ use log4rs::{
     filter::threshold::ThresholdFilter,
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
+use recorder::{RecordFormat, RecorderConfig};
 use std::io::{self, stdout, Write};
-use std::{env, sync::Arc};
+use std::path::PathBuf;
+use std::{collections::HashMap, env, sync::Arc};
 use std::{
     error::Error,
     sync::mpsc,
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
+use views::command::CommandOutcome;
+use views::watchlist::{retry_delay, update_device_readings, AttributeReading};
 use views::AttributeReadings;
+use watchlist_file::WatchlistFile;
 
 pub enum Event {
     Input(KeyEvent),
+    Paste(String),
     Tick,
     UpdateTangoDeviceReadings(AttributeReadings),
-    UpdateCommandResult(u128, String),
+    UpdateCommandResult(u64, CommandOutcome),
 }
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -46,6 +75,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let matches = parse_commandline_args();
     let tick_rate = matches.value_of("tick_rate").unwrap().parse::<u64>()?;
     let enhanced_graphics = matches.value_of("enhanced_graphics").is_some();
+    let stale_after = matches.value_of("stale_after").unwrap().parse::<f64>()?;
+    let history_size = matches.value_of("history_size").unwrap().parse::<usize>()?;
+    let recorder_config = build_recorder_config(&matches)?;
+    let audit_log = build_audit_log(&matches);
 
     //Set up logging
     let log_config = build_log_config(&matches)?;
@@ -53,6 +86,74 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     info!("Starting up");
 
+    match matches.subcommand() {
+        ("record", Some(record_matches)) => {
+            check_tango_host()?;
+            let options = RecordOptions {
+                watchlist_path: record_matches.value_of("watchlist_path").map(String::from),
+                attributes: record_matches
+                    .values_of("attributes")
+                    .map(|values| values.map(String::from).collect())
+                    .unwrap_or_default(),
+                recorder_config: build_recorder_config(record_matches)?,
+                interval: Duration::from_millis(
+                    record_matches
+                        .value_of("interval")
+                        .unwrap()
+                        .parse::<u64>()?,
+                ),
+                duration: match record_matches.value_of("duration") {
+                    Some(secs) => Some(Duration::from_secs_f64(secs.parse::<f64>()?)),
+                    None => None,
+                },
+            };
+            return headless::run_record(&options);
+        }
+        ("snapshot", Some(snapshot_matches)) => {
+            check_tango_host()?;
+            let attributes: Vec<String> = snapshot_matches
+                .values_of("attributes")
+                .map(|values| values.map(String::from).collect())
+                .unwrap_or_default();
+            return headless::run_snapshot(
+                snapshot_matches.value_of("watchlist_path"),
+                &attributes,
+                snapshot_matches.value_of("snapshot_path"),
+            );
+        }
+        ("diff", Some(diff_matches)) => {
+            // Comparing two files needs no Tango host
+            if !diff_matches.is_present("after") {
+                check_tango_host()?;
+            }
+            return headless::run_diff(
+                diff_matches.value_of("before").unwrap(),
+                diff_matches.value_of("after"),
+                diff_matches.value_of("watchlist_path"),
+                diff_matches.is_present("all"),
+                diff_matches.is_present("json"),
+            );
+        }
+        (subcommand, Some(subcommand_matches)) if cli::SUBCOMMANDS.contains(&subcommand) => {
+            check_tango_host()?;
+            return cli::run(subcommand, subcommand_matches, audit_log.as_ref());
+        }
+        _ => {}
+    }
+
+    // Load the watchlist before taking over the terminal so that errors are readable
+    let watchlist_file = match matches.value_of("watchlist_path") {
+        Some(watchlist_path) => Some(WatchlistFile::load(watchlist_path)?),
+        None => None,
+    };
+    let alerts = match matches.value_of("alerts_path") {
+        Some(alerts_path) => Some(Alerts::load(
+            alerts_path,
+            AlertNotify::parse(matches.value_of("alert_notify").unwrap_or("bell"))?,
+        )?),
+        None => None,
+    };
+
     let tango_host = match env::var("TANGO_HOST") {
         Ok(host) => host,
         Err(_) => String::from(""),
@@ -71,6 +172,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         stdout,
         EnterAlternateScreen,
         EnableMouseCapture,
+        EnableBracketedPaste,
         EnableLineWrap
     )?;
     let backend = CrosstermBackend::new(stdout);
@@ -92,8 +194,12 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .checked_sub(last_tick.elapsed())
                 .unwrap_or_else(|| Duration::from_secs(0));
             if event::poll(timeout).unwrap() {
-                if let CEvent::Key(key) = event::read().unwrap() {
-                    tx.send(Event::Input(key)).unwrap();
+                match event::read().unwrap() {
+                    CEvent::Key(key) if key.kind != KeyEventKind::Release => {
+                        tx.send(Event::Input(key)).unwrap();
+                    }
+                    CEvent::Paste(text) => tx.send(Event::Paste(text)).unwrap(),
+                    _ => {}
                 }
             }
             if last_tick.elapsed() >= tick_rate_duration {
@@ -107,21 +213,93 @@ fn main() -> Result<(), Box<dyn Error>> {
         Ok(the_app) => the_app,
         Err(err) => {
             disable_raw_mode()?;
-            execute!(std::io::stdout(), DisableMouseCapture)?;
+            execute!(
+                std::io::stdout(),
+                DisableMouseCapture,
+                DisableBracketedPaste
+            )?;
             return Err(err);
         }
     };
 
+    app.shared_view_state.stale_after = Duration::from_secs_f64(stale_after);
+    app.shared_view_state.trend_capacity = history_size;
+    app.shared_view_state.recorder_config = recorder_config;
+    if let Some(watchlist_file) = watchlist_file {
+        app.load_watchlist_file(watchlist_file);
+    }
+    if let Some(alerts) = alerts {
+        app.load_alerts(alerts);
+    }
+    if let Some(audit_log) = audit_log {
+        app.load_audit_log(audit_log);
+    }
+
     // Update the watched attributes in a separate thread
     let watch_list = Arc::clone(&app.shared_view_state.watch_list);
+    let attribute_configs = Arc::clone(&app.shared_view_state.attribute_configs);
+    let paused = Arc::clone(&app.shared_view_state.paused);
     let watch_sleep = Duration::from_millis(tick_rate);
+    // Failed configuration queries are retried with the same backoff as failed reads
+    let mut config_retries: HashMap<(String, String), (u32, SystemTime)> = HashMap::new();
     thread::spawn(move || loop {
         thread::sleep(watch_sleep);
         let mut device_attr_map = { watch_list.lock().unwrap().clone() };
+        let paused = { paused.lock().unwrap().clone() };
 
-        for (device_name, attr_map) in device_attr_map.iter_mut() {
-            for (attr_name, attr_reading) in attr_map.iter_mut() {
-                attr_reading.update(device_name, attr_name);
+        // Derived attributes are evaluated from the readings when received
+        for (device_name, attr_map) in device_attr_map
+            .iter_mut()
+            .filter(|(device_name, _)| *device_name != derived::DERIVED_DEVICE)
+        {
+            // Paused attributes keep their last reading
+            let held: Vec<(String, AttributeReading)> = paused
+                .iter()
+                .filter(|(paused_device, _)| paused_device == device_name)
+                .filter_map(|(_, attr_name)| attr_map.remove_entry(attr_name))
+                .collect();
+            update_device_readings(device_name, attr_map);
+            attr_map.extend(held);
+        }
+
+        // Query the configuration, with the limits, of newly watched attributes
+        for (device_name, attr_map) in device_attr_map
+            .iter()
+            .filter(|(device_name, _)| *device_name != derived::DERIVED_DEVICE)
+        {
+            let now = SystemTime::now();
+            for (attr_name, attr_reading) in attr_map {
+                let known = match attribute_configs.lock().unwrap().get(device_name) {
+                    Some(config_map) => config_map.contains_key(attr_name),
+                    None => false,
+                };
+                // A device that failed the read would only time out again
+                if known || !matches!(attr_reading, AttributeReading::Value(_)) {
+                    continue;
+                }
+                let key = (device_name.clone(), attr_name.clone());
+                if let Some((_, retry_at)) = config_retries.get(&key) {
+                    if *retry_at > now {
+                        continue;
+                    }
+                }
+                match tango_utils::get_attribute_info(device_name, attr_name) {
+                    Ok(attribute_info) => {
+                        config_retries.remove(&key);
+                        attribute_configs
+                            .lock()
+                            .unwrap()
+                            .entry(device_name.clone())
+                            .or_default()
+                            .insert(attr_name.clone(), attribute_info);
+                    }
+                    Err(_) => {
+                        let attempts = config_retries
+                            .get(&key)
+                            .map_or(1, |(attempts, _)| attempts + 1);
+                        config_retries.insert(key, (attempts, now + retry_delay(attempts)));
+                    }
+                }
             }
         }
         match tx_watch_list.send(Event::UpdateTangoDeviceReadings(device_attr_map)) {
@@ -138,7 +316,11 @@ fn main() -> Result<(), Box<dyn Error>> {
             Event::Input(event) => match event.code {
                 KeyCode::Esc => {
                     disable_raw_mode()?;
-                    execute!(std::io::stdout(), DisableMouseCapture)?;
+                    execute!(
+                        std::io::stdout(),
+                        DisableMouseCapture,
+                        DisableBracketedPaste
+                    )?;
                     terminal.clear()?;
                     app.should_quit = true;
                     break;
@@ -147,27 +329,23 @@ fn main() -> Result<(), Box<dyn Error>> {
                     app.handle_event(&event);
                 }
             },
+            Event::Paste(text) => app.handle_paste(&text),
             Event::Tick => {
                 app.on_tick();
             }
             Event::UpdateTangoDeviceReadings(updated_device_value_map) => {
                 app.update_device_attr_map(updated_device_value_map);
             }
-            Event::UpdateCommandResult(uuid, result) => {
-                if let Some(mut executed_command) = app
-                    .shared_view_state
-                    .executed_commands
-                    .executed_commands
-                    .get_mut(&uuid)
-                {
-                    executed_command.result = result;
-                }
-            }
+            Event::UpdateCommandResult(id, outcome) => app.finish_command(id, outcome),
         }
 
         if app.should_quit {
             disable_raw_mode()?;
-            execute!(std::io::stdout(), DisableMouseCapture)?;
+            execute!(
+                std::io::stdout(),
+                DisableMouseCapture,
+                DisableBracketedPaste
+            )?;
             break;
         }
     }
@@ -181,6 +359,27 @@ fn validate_tick_rate(v: String) -> Result<(), String> {
     }
 }
 
+fn validate_history_size(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(()),
+        _ => Err("History size should be a number larger than 0".to_string()),
+    }
+}
+
+fn validate_seconds(v: String) -> Result<(), String> {
+    match v.parse::<f64>() {
+        Ok(secs) if secs >= 0.0 => Ok(()),
+        _ => Err("Should be a positive number of seconds".to_string()),
+    }
+}
+
+fn validate_megabytes(v: String) -> Result<(), String> {
+    match v.parse::<f64>() {
+        Ok(megabytes) if megabytes.is_finite() && megabytes > 0.0 => Ok(()),
+        _ => Err("Should be a number of megabytes larger than 0".to_string()),
+    }
+}
+
 fn parse_commandline_args() -> clap::ArgMatches<'static> {
     clap::App::new("tango-controls-tui")
         .version("0.0.3")
@@ -200,6 +399,54 @@ fn parse_commandline_args() -> clap::ArgMatches<'static> {
                 .long("enhanced-graphics")
                 .help("Whether to use unicode symbols for better rendering"),
         )
+        .arg(
+            clap::Arg::with_name("stale_after")
+                .short("s")
+                .long("stale-after")
+                .help("Flag watched values with a timestamp older than this many seconds as stale")
+                .validator(validate_seconds)
+                .default_value("10"),
+        )
+        .arg(
+            clap::Arg::with_name("history_size")
+                .long("history-size")
+                .help("The number of readings to keep per watched attribute for trends")
+                .validator(validate_history_size)
+                .default_value("3600"),
+        )
+        .arg(
+            clap::Arg::with_name("watchlist_path")
+                .short("w")
+                .long("watchlist")
+                .help("A file listing the attributes to watch, with optional limits")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("record_path")
+                .long("record-file")
+                .help("The file that watchlist readings are recorded to when recording is toggled with `r`")
+                .takes_value(true),
+        )
+        .args(&recording_args())
+        .arg(
+            clap::Arg::with_name("alerts_path")
+                .long("alerts")
+                .help("A file of alert rules checked against the watchlist readings")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("alert_notify")
+                .long("alert-notify")
+                .help("How fired alerts are announced besides the banner and the log: bell, osc9 or none, comma separated")
+                .default_value("bell")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("audit_log_path")
+                .long("audit-log")
+                .help("The file that command executions and attribute writes are appended to. Defaults to ~/.tango-controls-tui/audit.jsonl")
+                .takes_value(true),
+        )
         .arg(
             clap::Arg::with_name("logfile_path")
                 .short("l")
@@ -207,9 +454,237 @@ fn parse_commandline_args() -> clap::ArgMatches<'static> {
                 .help("The path to the log file. If not specified logs will be sent to stderr")
                 .takes_value(true),
         )
+        .subcommand(
+            clap::SubCommand::with_name("record")
+                .about("Record attribute readings without the terminal UI")
+                .arg(
+                    clap::Arg::with_name("attributes")
+                        .help("The attributes to record, as domain/family/member/attribute")
+                        .multiple(true),
+                )
+                .arg(
+                    clap::Arg::with_name("watchlist_path")
+                        .short("w")
+                        .long("watchlist")
+                        .help("A watchlist file listing the attributes to record")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("record_path")
+                        .short("o")
+                        .long("output")
+                        .help("The file to record to, - for stdout")
+                        .default_value("-"),
+                )
+                .args(&recording_args())
+                .arg(
+                    clap::Arg::with_name("interval")
+                        .short("i")
+                        .long("interval")
+                        .help("The polling interval in milliseconds")
+                        .validator(validate_tick_rate)
+                        .default_value("1000"),
+                )
+                .arg(
+                    clap::Arg::with_name("duration")
+                        .short("d")
+                        .long("duration")
+                        .help("Stop after this many seconds. Records until interrupted if not set")
+                        .validator(validate_seconds)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("snapshot")
+                .about("Save the current values of attributes to a snapshot file")
+                .arg(
+                    clap::Arg::with_name("attributes")
+                        .help("The attributes to save, as domain/family/member/attribute")
+                        .multiple(true),
+                )
+                .arg(
+                    clap::Arg::with_name("watchlist_path")
+                        .short("w")
+                        .long("watchlist")
+                        .help("A watchlist file listing the attributes to save")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("snapshot_path")
+                        .short("o")
+                        .long("output")
+                        .help("The snapshot file. Defaults to a file named after the current time")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("diff")
+                .about("Compare a snapshot with another snapshot or with the current values")
+                .arg(
+                    clap::Arg::with_name("before")
+                        .help("The snapshot file to compare against")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("after")
+                        .help("A second snapshot file. The current values are read if not set"),
+                )
+                .arg(
+                    clap::Arg::with_name("watchlist_path")
+                        .short("w")
+                        .long("watchlist")
+                        .help("Read the current values of this watchlist instead of the snapshot attributes")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("Also list the unchanged attributes"),
+                )
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("ls")
+                .about("List the exported devices")
+                .arg(
+                    clap::Arg::with_name("pattern")
+                        .help("Only list devices matching the pattern, e.g. sys/*")
+                        .default_value("*"),
+                )
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("attrs")
+                .about("List the attributes of a device with their values")
+                .arg(clap::Arg::with_name("device").required(true))
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("cmds")
+                .about("List the commands of a device")
+                .arg(clap::Arg::with_name("device").required(true))
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("read")
+                .about("Read attributes")
+                .arg(
+                    clap::Arg::with_name("attributes")
+                        .help("The attributes to read, as domain/family/member/attribute")
+                        .required(true)
+                        .multiple(true),
+                )
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("write")
+                .about("Write an attribute, the value is parsed as the type of its current value")
+                .arg(
+                    clap::Arg::with_name("attribute")
+                        .help("The attribute to write, as domain/family/member/attribute")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("value")
+                        .help("The value, spectrums as e.g. [1, 2, 3]")
+                        .required(true)
+                        .allow_hyphen_values(true),
+                )
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("exec")
+                .about("Execute a command")
+                .arg(clap::Arg::with_name("device").required(true))
+                .arg(clap::Arg::with_name("command").required(true))
+                .arg(
+                    clap::Arg::with_name("argument")
+                        .help("The command argument, parsed as the input type of the command")
+                        .allow_hyphen_values(true),
+                )
+                .arg(json_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("info")
+                .about("Show the state, status and other information of a device")
+                .arg(clap::Arg::with_name("device").required(true))
+                .arg(json_arg()),
+        )
         .get_matches()
 }
 
+// The subcommands skip the terminal UI so fail instead of printing a message
+fn check_tango_host() -> Result<(), Box<dyn Error>> {
+    if env::var("TANGO_HOST").unwrap_or_default().is_empty() {
+        return Err(anyhow!("TANGO_HOST not set").into());
+    }
+    Ok(())
+}
+
+fn json_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("json")
+        .long("json")
+        .help("Print the result as JSON")
+}
+
+// The recording options shared by the watchlist and the `record` subcommand
+fn recording_args() -> Vec<clap::Arg<'static, 'static>> {
+    vec![
+        clap::Arg::with_name("record_format")
+            .long("record-format")
+            .help(
+                "The format of the recording, csv or jsonl. Defaults to the file extension or csv",
+            )
+            .possible_values(&["csv", "jsonl"])
+            .takes_value(true),
+        clap::Arg::with_name("rotate_size")
+            .long("rotate-size")
+            .help("Start a new recording file once it reaches this many megabytes")
+            .validator(validate_megabytes)
+            .takes_value(true),
+        clap::Arg::with_name("rotate_interval")
+            .long("rotate-interval")
+            .help("Start a new recording file after this many seconds")
+            .validator(validate_seconds)
+            .takes_value(true),
+    ]
+}
+
+fn build_recorder_config(matches: &clap::ArgMatches) -> Result<RecorderConfig, Box<dyn Error>> {
+    let path = matches.value_of("record_path").map(PathBuf::from);
+    let format = match matches.value_of("record_format") {
+        Some(format) => RecordFormat::parse(format)?,
+        None => match path.as_ref().and_then(|path| path.extension()) {
+            Some(extension) if extension == "jsonl" => RecordFormat::Jsonl,
+            _ => RecordFormat::Csv,
+        },
+    };
+    let rotate_size = match matches.value_of("rotate_size") {
+        Some(megabytes) => Some((megabytes.parse::<f64>()? * 1_000_000.0) as u64),
+        None => None,
+    };
+    let rotate_interval = match matches.value_of("rotate_interval") {
+        Some(secs) => Some(Duration::from_secs_f64(secs.parse::<f64>()?)),
+        None => None,
+    };
+    Ok(RecorderConfig {
+        path,
+        format,
+        rotate_size,
+        rotate_interval,
+    })
+}
+
+// Without a home directory nothing is logged unless a file is given
+fn build_audit_log(matches: &clap::ArgMatches) -> Option<AuditLog> {
+    let path = match matches.value_of("audit_log_path") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => AuditLog::default_path(),
+    };
+    path.map(AuditLog::new)
+}
+
 fn build_log_config(matches: &clap::ArgMatches) -> Result<log4rs::Config, Box<dyn Error>> {
     // Build the logger
     // Write to a file if specified, otherwise write to stderr
@@ -0,0 +1,393 @@
+use crate::tango_utils::display_attribute_quality;
+use crate::views::watchlist::AttributeReading;
+use crate::views::{AttributeName, AttributeReadings, DeviceName};
+use anyhow::anyhow;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const CSV_HEADER: &str = "device,attribute,value,quality,source_timestamp,received_timestamp";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl RecordFormat {
+    pub fn parse(format: &str) -> Result<RecordFormat, Box<dyn Error>> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(RecordFormat::Csv),
+            "jsonl" | "json" => Ok(RecordFormat::Jsonl),
+            _ => Err(anyhow!("Unknown record format [{}], expected csv or jsonl", format).into()),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordFormat::Csv => "csv",
+            RecordFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+impl fmt::Display for RecordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecorderConfig {
+    // When not set a file named after the start time is created in the working directory
+    pub path: Option<PathBuf>,
+    pub format: RecordFormat,
+    pub rotate_size: Option<u64>,
+    pub rotate_interval: Option<Duration>,
+}
+
+// One recorded line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedReading {
+    pub device_name: String,
+    pub attr_name: String,
+    pub value: String,
+    pub quality: String,
+    pub source_time_stamp: Option<SystemTime>,
+    pub received_time_stamp: SystemTime,
+}
+
+fn display_time_stamp(time_stamp: SystemTime) -> String {
+    let date_time: DateTime<Utc> = time_stamp.into();
+    date_time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn display_local_time_stamp(time_stamp: SystemTime) -> String {
+    let date_time: DateTime<Local> = time_stamp.into();
+    date_time.to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+// Quote CSV fields that contain separators, quotes or new lines
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl RecordedReading {
+    pub fn to_line(&self, format: RecordFormat) -> String {
+        let source_time_stamp = self
+            .source_time_stamp
+            .map(display_time_stamp)
+            .unwrap_or_default();
+        let received_time_stamp = display_local_time_stamp(self.received_time_stamp);
+        match format {
+            RecordFormat::Csv => [
+                self.device_name.as_str(),
+                self.attr_name.as_str(),
+                self.value.as_str(),
+                self.quality.as_str(),
+                source_time_stamp.as_str(),
+                received_time_stamp.as_str(),
+            ]
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<String>>()
+            .join(","),
+            RecordFormat::Jsonl => serde_json::json!({
+                "device": self.device_name,
+                "attribute": self.attr_name,
+                "value": self.value,
+                "quality": self.quality,
+                "source_timestamp": source_time_stamp,
+                "received_timestamp": received_time_stamp,
+            })
+            .to_string(),
+        }
+    }
+}
+
+// Where the readings are written to
+#[derive(Debug)]
+enum RecordOutput {
+    File(BufWriter<File>),
+    Stdout(io::Stdout),
+}
+
+impl Write for RecordOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            RecordOutput::File(writer) => writer.write(buf),
+            RecordOutput::Stdout(stdout) => stdout.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            RecordOutput::File(writer) => writer.flush(),
+            RecordOutput::Stdout(stdout) => stdout.flush(),
+        }
+    }
+}
+
+// Appends every new watchlist reading to a file, rotating it by size or age.
+// Rotated files are renamed with the time of rotation and recording continues
+// in a new file at the original path. A path of `-` writes to stdout.
+#[derive(Debug)]
+pub struct Recorder {
+    config: RecorderConfig,
+    path: PathBuf,
+    writer: RecordOutput,
+    bytes_written: u64,
+    opened_at: SystemTime,
+    lines_in_file: u64,
+    pub lines_written: u64,
+    // The source timestamp of the last recorded reading, `None` after an error
+    last_recorded: BTreeMap<(DeviceName, AttributeName), Option<SystemTime>>,
+}
+
+impl Recorder {
+    pub fn start(config: &RecorderConfig) -> Result<Recorder, Box<dyn Error>> {
+        let path = match &config.path {
+            Some(path) => path.clone(),
+            None => PathBuf::from(format!(
+                "watchlist-{}.{}",
+                Local::now().format("%Y%m%d-%H%M%S"),
+                config.format.extension()
+            )),
+        };
+        let (writer, bytes_written) = Recorder::open(&path, config.format)?;
+        Ok(Recorder {
+            config: config.clone(),
+            path,
+            writer,
+            bytes_written,
+            opened_at: SystemTime::now(),
+            lines_in_file: 0,
+            lines_written: 0,
+            last_recorded: BTreeMap::default(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(path: &Path, format: RecordFormat) -> Result<(RecordOutput, u64), Box<dyn Error>> {
+        let (mut writer, mut bytes_written) = if path == Path::new("-") {
+            (RecordOutput::Stdout(io::stdout()), 0)
+        } else {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| anyhow!("Could not open {}: {}", path.display(), err))?;
+            let bytes_written = file.metadata()?.len();
+            (RecordOutput::File(BufWriter::new(file)), bytes_written)
+        };
+        if format == RecordFormat::Csv && bytes_written == 0 {
+            writeln!(writer, "{}", CSV_HEADER)?;
+            bytes_written += CSV_HEADER.len() as u64 + 1;
+        }
+        Ok((writer, bytes_written))
+    }
+
+    fn needs_rotation(&self, now: SystemTime) -> bool {
+        // Never rotate a file without readings
+        if self.lines_in_file == 0 || matches!(self.writer, RecordOutput::Stdout(_)) {
+            return false;
+        }
+        let too_big = matches!(self.config.rotate_size, Some(size) if self.bytes_written >= size);
+        let too_old = match (
+            self.config.rotate_interval,
+            now.duration_since(self.opened_at),
+        ) {
+            (Some(interval), Ok(age)) => age >= interval,
+            _ => false,
+        };
+        too_big || too_old
+    }
+
+    fn rotate(&mut self, now: SystemTime) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let date_time: DateTime<Local> = now.into();
+        let mut rotated_path = self.path.with_file_name(format!(
+            "{}-{}.{}",
+            stem,
+            date_time.format("%Y%m%d-%H%M%S"),
+            self.config.format.extension()
+        ));
+        // Rotating twice within a second
+        let mut ix = 1;
+        while rotated_path.exists() {
+            rotated_path = self.path.with_file_name(format!(
+                "{}-{}-{}.{}",
+                stem,
+                date_time.format("%Y%m%d-%H%M%S"),
+                ix,
+                self.config.format.extension()
+            ));
+            ix += 1;
+        }
+        fs::rename(&self.path, &rotated_path)?;
+        let (writer, bytes_written) = Recorder::open(&self.path, self.config.format)?;
+        self.writer = writer;
+        self.bytes_written = bytes_written;
+        self.opened_at = now;
+        self.lines_in_file = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str, now: SystemTime) -> Result<(), Box<dyn Error>> {
+        if self.needs_rotation(now) {
+            self.rotate(now)?;
+        }
+        writeln!(self.writer, "{}", line)?;
+        self.bytes_written += line.len() as u64 + 1;
+        self.lines_in_file += 1;
+        self.lines_written += 1;
+        Ok(())
+    }
+
+    // Record the readings that changed since the last call
+    pub fn record(&mut self, attr_map: &AttributeReadings) -> Result<(), Box<dyn Error>> {
+        let now = SystemTime::now();
+        for (device_name, attr_readings) in attr_map {
+            for (attr_name, attr_reading) in attr_readings {
+                let key = (device_name.clone(), attr_name.clone());
+                let last_recorded = self.last_recorded.get(&key).cloned();
+                let recorded_reading = match attr_reading {
+                    AttributeReading::Value(reading) => {
+                        if reading.time_stamp.is_none() || last_recorded == Some(reading.time_stamp)
+                        {
+                            continue;
+                        }
+                        self.last_recorded.insert(key, reading.time_stamp);
+                        RecordedReading {
+                            device_name: device_name.clone(),
+                            attr_name: attr_name.clone(),
+                            value: reading.value.clone(),
+                            quality: reading
+                                .quality
+                                .map(display_attribute_quality)
+                                .unwrap_or_default(),
+                            source_time_stamp: reading.time_stamp,
+                            received_time_stamp: reading.received_time_stamp.unwrap_or(now),
+                        }
+                    }
+                    AttributeReading::Error(err) => {
+                        // Only record when the attribute starts failing
+                        if last_recorded == Some(None) {
+                            continue;
+                        }
+                        self.last_recorded.insert(key, None);
+                        RecordedReading {
+                            device_name: device_name.clone(),
+                            attr_name: attr_name.clone(),
+                            value: err.message.clone(),
+                            quality: err.kind.to_string(),
+                            source_time_stamp: None,
+                            received_time_stamp: now,
+                        }
+                    }
+                };
+                self.write_line(&recorded_reading.to_line(self.config.format), now)?;
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod recorder_tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_reading_lines() {
+        let recorded_reading = RecordedReading {
+            device_name: "sys/tg_test/1".to_string(),
+            attr_name: "string_scalar".to_string(),
+            value: "a, \"quoted\" value".to_string(),
+            quality: "VALID".to_string(),
+            source_time_stamp: Some(SystemTime::UNIX_EPOCH + Duration::from_millis(1500)),
+            received_time_stamp: SystemTime::UNIX_EPOCH,
+        };
+        let csv_line = recorded_reading.to_line(RecordFormat::Csv);
+        assert!(csv_line.starts_with(
+            "sys/tg_test/1,string_scalar,\"a, \"\"quoted\"\" value\",VALID,1970-01-01T00:00:01.500000Z,"
+        ));
+
+        let json_line = recorded_reading.to_line(RecordFormat::Jsonl);
+        let parsed: serde_json::Value = serde_json::from_str(&json_line).unwrap();
+        assert_eq!(parsed["value"], "a, \"quoted\" value");
+        assert_eq!(parsed["source_timestamp"], "1970-01-01T00:00:01.500000Z");
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join("recorder_rotation");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("readings.csv");
+        let mut recorder = Recorder::start(&RecorderConfig {
+            path: Some(path.clone()),
+            format: RecordFormat::Csv,
+            rotate_size: Some(10),
+            rotate_interval: Some(Duration::from_secs(60)),
+        })
+        .unwrap();
+        let rotated_name = |time: SystemTime, suffix: &str| {
+            let date_time: DateTime<Local> = time.into();
+            dir.join(format!(
+                "readings-{}{}.csv",
+                date_time.format("%Y%m%d-%H%M%S"),
+                suffix
+            ))
+        };
+        let read = |path: &Path| fs::read_to_string(path).unwrap();
+
+        // The header alone is past the size, but a file without readings is never rotated
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        recorder.write_line("a", now).unwrap();
+        recorder.write_line("b", now).unwrap();
+        // Rotating again within the same second
+        recorder.write_line("c", now).unwrap();
+        recorder.writer.flush().unwrap();
+        assert_eq!(read(&rotated_name(now, "")), format!("{}\na\n", CSV_HEADER));
+        assert_eq!(
+            read(&rotated_name(now, "-1")),
+            format!("{}\nb\n", CSV_HEADER)
+        );
+        assert_eq!(read(&path), format!("{}\nc\n", CSV_HEADER));
+        assert_eq!(recorder.lines_written, 3);
+
+        recorder.config.rotate_size = None;
+        recorder
+            .write_line("d", now + Duration::from_secs(30))
+            .unwrap();
+        let later = now + Duration::from_secs(61);
+        recorder.write_line("e", later).unwrap();
+        recorder.writer.flush().unwrap();
+        assert_eq!(
+            read(&rotated_name(later, "")),
+            format!("{}\nc\nd\n", CSV_HEADER)
+        );
+        assert_eq!(read(&path), format!("{}\ne\n", CSV_HEADER));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
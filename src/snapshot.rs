@@ -0,0 +1,321 @@
+use crate::tango_utils::{attr_value_as_f64, attr_value_to_json, display_attribute_quality};
+use crate::views::watchlist::AttributeReading;
+use crate::views::AttributeReadings;
+use anyhow::anyhow;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+// The value of one attribute when the snapshot was taken
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SnapshotEntry {
+    pub value: String,
+    // The value as JSON, compared rather than the text when both sides have it
+    pub data: Option<Value>,
+    pub number: Option<f64>,
+    pub quality: String,
+    pub time_stamp: Option<SystemTime>,
+}
+
+// Every watched value at one point in time, saved as a JSON file:
+//
+// {"taken_at": "2024-01-01T10:00:00.000000Z",
+//  "attributes": {"sys/tg_test/1/double_scalar":
+//      {"value": "1.5", "data": 1.5, "quality": "VALID", "timestamp": "..."}}}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub taken_at: SystemTime,
+    // Keyed by the full attribute name
+    pub entries: BTreeMap<String, SnapshotEntry>,
+}
+
+fn display_time_stamp(time_stamp: SystemTime) -> String {
+    let date_time: DateTime<Utc> = time_stamp.into();
+    date_time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_time_stamp(time_stamp: &str) -> Result<SystemTime, Box<dyn Error>> {
+    let date_time = DateTime::parse_from_rfc3339(time_stamp)
+        .map_err(|err| anyhow!("Invalid timestamp [{}]: {}", time_stamp, err))?;
+    Ok(date_time.with_timezone(&Utc).into())
+}
+
+impl Snapshot {
+    // Attributes that were not read yet are left out
+    pub fn capture(attr_map: &AttributeReadings, taken_at: SystemTime) -> Snapshot {
+        let mut entries = BTreeMap::new();
+        for (device_name, attr_readings) in attr_map {
+            for (attr_name, attr_reading) in attr_readings {
+                let entry = match attr_reading {
+                    AttributeReading::Value(reading) => match &reading.data {
+                        Some(data) => SnapshotEntry {
+                            value: reading.value.clone(),
+                            data: Some(attr_value_to_json(data)),
+                            number: attr_value_as_f64(data),
+                            quality: reading
+                                .quality
+                                .map(display_attribute_quality)
+                                .unwrap_or_default(),
+                            time_stamp: reading.time_stamp,
+                        },
+                        None => continue,
+                    },
+                    AttributeReading::Error(err) => SnapshotEntry {
+                        value: err.message.clone(),
+                        quality: err.kind.to_string(),
+                        ..SnapshotEntry::default()
+                    },
+                };
+                entries.insert(format!("{}/{}", device_name, attr_name), entry);
+            }
+        }
+        Snapshot { taken_at, entries }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let attributes: serde_json::Map<String, Value> = self
+            .entries
+            .iter()
+            .map(|(name, entry)| {
+                let mut attribute = json!({
+                    "value": entry.value,
+                    "quality": entry.quality,
+                });
+                if let Some(data) = &entry.data {
+                    attribute["data"] = data.clone();
+                }
+                if let Some(number) = entry.number {
+                    attribute["number"] = json!(number);
+                }
+                if let Some(time_stamp) = entry.time_stamp {
+                    attribute["timestamp"] = json!(display_time_stamp(time_stamp));
+                }
+                (name.clone(), attribute)
+            })
+            .collect();
+        json!({
+            "taken_at": display_time_stamp(self.taken_at),
+            "attributes": attributes,
+        })
+    }
+
+    pub fn parse(content: &str) -> Result<Snapshot, Box<dyn Error>> {
+        let root: Value = serde_json::from_str(content)?;
+        let taken_at = root["taken_at"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing taken_at"))?;
+        let attributes = root["attributes"]
+            .as_object()
+            .ok_or_else(|| anyhow!("Missing attributes"))?;
+        let mut entries = BTreeMap::new();
+        for (name, attribute) in attributes {
+            let time_stamp = match attribute["timestamp"].as_str() {
+                Some(time_stamp) => Some(parse_time_stamp(time_stamp)?),
+                None => None,
+            };
+            entries.insert(
+                name.clone(),
+                SnapshotEntry {
+                    value: attribute["value"].as_str().unwrap_or_default().to_string(),
+                    data: attribute.get("data").cloned(),
+                    number: attribute["number"].as_f64(),
+                    quality: attribute["quality"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    time_stamp,
+                },
+            );
+        }
+        Ok(Snapshot {
+            taken_at: parse_time_stamp(taken_at)?,
+            entries,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, format!("{:#}\n", self.to_json()))
+            .map_err(|err| anyhow!("Could not write snapshot {}: {}", path.display(), err))?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Could not read snapshot {}: {}", path.display(), err))?;
+        Snapshot::parse(&content)
+            .map_err(|err| anyhow!("Invalid snapshot {}: {}", path.display(), err).into())
+    }
+
+    // The default file name, named after the time it was taken
+    pub fn file_name(&self) -> String {
+        let date_time: DateTime<Local> = self.taken_at.into();
+        format!("snapshot-{}.json", date_time.format("%Y%m%d-%H%M%S"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffStatus {
+    Changed,
+    // Only in the first snapshot
+    Missing,
+    // Only in the second snapshot
+    Added,
+    Unchanged,
+}
+
+impl fmt::Display for DiffStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffStatus::Changed => write!(f, "changed"),
+            DiffStatus::Missing => write!(f, "missing"),
+            DiffStatus::Added => write!(f, "added"),
+            DiffStatus::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotDiff {
+    pub name: String,
+    pub status: DiffStatus,
+    pub before: Option<SnapshotEntry>,
+    pub after: Option<SnapshotEntry>,
+    // `after - before` of numeric values
+    pub delta: Option<f64>,
+}
+
+fn same_value(before: &SnapshotEntry, after: &SnapshotEntry) -> bool {
+    let same = match (&before.data, &after.data) {
+        (Some(before_data), Some(after_data)) => before_data == after_data,
+        _ => before.value == after.value,
+    };
+    same && before.quality == after.quality
+}
+
+// Compare every attribute of two snapshots, ordered by name
+pub fn diff(before: &Snapshot, after: &Snapshot) -> Vec<SnapshotDiff> {
+    let mut names: Vec<&String> = before.entries.keys().chain(after.entries.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| {
+            let before_entry = before.entries.get(name);
+            let after_entry = after.entries.get(name);
+            let status = match (before_entry, after_entry) {
+                (Some(before_entry), Some(after_entry))
+                    if same_value(before_entry, after_entry) =>
+                {
+                    DiffStatus::Unchanged
+                }
+                (Some(_), Some(_)) => DiffStatus::Changed,
+                (Some(_), None) => DiffStatus::Missing,
+                (None, _) => DiffStatus::Added,
+            };
+            let delta = match (
+                before_entry.and_then(|entry| entry.number),
+                after_entry.and_then(|entry| entry.number),
+            ) {
+                (Some(before_number), Some(after_number)) => Some(after_number - before_number),
+                _ => None,
+            };
+            SnapshotDiff {
+                name: name.clone(),
+                status,
+                before: before_entry.cloned(),
+                after: after_entry.cloned(),
+                delta,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use crate::views::watchlist::{ReadError, ReadErrorKind, Reading};
+    use std::time::Duration;
+    use tango_controls_client_sys::types::{AttrQuality, AttrValue};
+
+    fn readings(values: &[(&str, AttrValue)]) -> AttributeReadings {
+        let mut attr_map = AttributeReadings::default();
+        for (attr_name, value) in values {
+            attr_map
+                .entry("sys/tg_test/1".to_string())
+                .or_default()
+                .insert(
+                    attr_name.to_string(),
+                    AttributeReading::Value(Reading {
+                        value: format!("{}", value),
+                        data: Some(value.clone()),
+                        quality: Some(AttrQuality::ATTR_VALID),
+                        time_stamp: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1)),
+                        ..Reading::default()
+                    }),
+                );
+        }
+        attr_map
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut attr_map = readings(&[
+            ("double_scalar", AttrValue::Double(1.5)),
+            ("string_scalar", AttrValue::String("a".to_string())),
+        ]);
+        attr_map.get_mut("sys/tg_test/1").unwrap().insert(
+            "long_scalar".to_string(),
+            AttributeReading::Error(ReadError::new(ReadErrorKind::Unreachable, "timeout")),
+        );
+        let snapshot = Snapshot::capture(&attr_map, SystemTime::UNIX_EPOCH);
+        let parsed = Snapshot::parse(&snapshot.to_json().to_string()).unwrap();
+        assert_eq!(parsed, snapshot);
+        assert_eq!(
+            parsed.entries["sys/tg_test/1/long_scalar"].quality,
+            "UNREACHABLE"
+        );
+        assert!(Snapshot::parse("{\"attributes\": {}}").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let before = Snapshot::capture(
+            &readings(&[
+                ("double_scalar", AttrValue::Double(1.5)),
+                ("long_scalar", AttrValue::Long(3)),
+                ("short_scalar", AttrValue::Short(1)),
+            ]),
+            SystemTime::UNIX_EPOCH,
+        );
+        let after = Snapshot::capture(
+            &readings(&[
+                ("double_scalar", AttrValue::Double(2.0)),
+                ("long_scalar", AttrValue::Long(3)),
+                ("ushort_scalar", AttrValue::UShort(1)),
+            ]),
+            SystemTime::UNIX_EPOCH,
+        );
+        let diffs = diff(&before, &after);
+        let statuses: Vec<(&str, DiffStatus)> = diffs
+            .iter()
+            .map(|diff| (diff.name.as_str(), diff.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("sys/tg_test/1/double_scalar", DiffStatus::Changed),
+                ("sys/tg_test/1/long_scalar", DiffStatus::Unchanged),
+                ("sys/tg_test/1/short_scalar", DiffStatus::Missing),
+                ("sys/tg_test/1/ushort_scalar", DiffStatus::Added),
+            ]
+        );
+        assert_eq!(diffs[0].delta, Some(0.5));
+        assert_eq!(diffs[1].delta, Some(0.0));
+        assert_eq!(diffs[2].delta, None);
+    }
+}
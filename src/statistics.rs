@@ -0,0 +1,133 @@
+use std::time::SystemTime;
+
+// Running statistics of a watched attribute since it was added or last reset.
+// The mean and variance use Welford's online algorithm so no history is kept.
+#[derive(Debug, Clone)]
+pub struct AttributeStatistics {
+    pub since: SystemTime,
+    // Readings with a new source timestamp
+    pub updates: u64,
+    // Readings whose value differs from the previous one
+    pub changes: u64,
+    // Number of times reading the attribute started failing
+    pub errors: u64,
+    // The source timestamp of the first reading with the current value
+    pub last_changed: Option<SystemTime>,
+    // Numeric samples included in min, max, mean and standard deviation
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    mean: f64,
+    m2: f64,
+    last_time_stamp: Option<SystemTime>,
+    last_value: Option<String>,
+    in_error: bool,
+}
+
+impl AttributeStatistics {
+    pub fn new(since: SystemTime) -> AttributeStatistics {
+        AttributeStatistics {
+            since,
+            updates: 0,
+            changes: 0,
+            errors: 0,
+            last_changed: None,
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            last_time_stamp: None,
+            last_value: None,
+            in_error: false,
+        }
+    }
+
+    pub fn record_value(
+        &mut self,
+        value: &str,
+        number: Option<f64>,
+        time_stamp: Option<SystemTime>,
+    ) {
+        self.in_error = false;
+        // Polling the same reading twice is not an update
+        if time_stamp.is_some() && time_stamp == self.last_time_stamp {
+            return;
+        }
+        self.last_time_stamp = time_stamp;
+        self.updates += 1;
+
+        match &self.last_value {
+            Some(last_value) if last_value == value => {}
+            Some(_) => {
+                self.changes += 1;
+                self.last_changed = time_stamp;
+            }
+            None => self.last_changed = time_stamp,
+        }
+        self.last_value = Some(value.to_string());
+
+        if let Some(number) = number.filter(|number| number.is_finite()) {
+            self.count += 1;
+            self.min = self.min.min(number);
+            self.max = self.max.max(number);
+            let delta = number - self.mean;
+            self.mean += delta / self.count as f64;
+            self.m2 += delta * (number - self.mean);
+        }
+    }
+
+    pub fn record_error(&mut self) {
+        if !self.in_error {
+            self.errors += 1;
+            self.in_error = true;
+        }
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    // The sample standard deviation
+    pub fn std_dev(&self) -> Option<f64> {
+        (self.count > 1).then(|| (self.m2 / (self.count - 1) as f64).sqrt())
+    }
+
+    // Updates per second since the statistics started
+    pub fn update_rate(&self, now: SystemTime) -> Option<f64> {
+        let elapsed = now.duration_since(self.since).ok()?.as_secs_f64();
+        (elapsed > 0.0).then(|| self.updates as f64 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod statistics_tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_attribute_statistics() {
+        let start = SystemTime::UNIX_EPOCH;
+        let mut statistics = AttributeStatistics::new(start);
+        for (ix, value) in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].iter().enumerate() {
+            let time_stamp = start + Duration::from_secs(ix as u64 + 1);
+            statistics.record_value(&value.to_string(), Some(*value), Some(time_stamp));
+            // The same reading polled again
+            statistics.record_value(&value.to_string(), Some(*value), Some(time_stamp));
+        }
+        statistics.record_error();
+        statistics.record_error();
+
+        assert_eq!(statistics.updates, 8);
+        assert_eq!(statistics.changes, 4);
+        assert_eq!(statistics.errors, 1);
+        assert_eq!(statistics.min, 2.0);
+        assert_eq!(statistics.max, 9.0);
+        assert_eq!(statistics.mean(), Some(5.0));
+        assert!((statistics.std_dev().unwrap() - 2.138_089_935).abs() < 1e-6);
+        assert_eq!(
+            statistics.update_rate(start + Duration::from_secs(4)),
+            Some(2.0)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// A fixed size history of the numeric values of a watched attribute.
+// Once full the oldest point is dropped for every new point.
+#[derive(Debug, Clone)]
+pub struct Trend {
+    pub points: VecDeque<(SystemTime, f64)>,
+    pub capacity: usize,
+}
+
+impl Trend {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            points: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, time_stamp: SystemTime, value: f64) {
+        // Polling the same reading twice should not add a point
+        if let Some((last_time_stamp, _)) = self.points.back() {
+            if *last_time_stamp == time_stamp {
+                return;
+            }
+        }
+        if self.points.len() >= self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back((time_stamp, value));
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.points.back().map(|(_, value)| *value)
+    }
+
+    // The points in the window `(end - window_secs, end]` with the time
+    // expressed as seconds from the start of the window.
+    pub fn window(&self, end: SystemTime, window_secs: f64) -> Vec<(f64, f64)> {
+        self.points
+            .iter()
+            .filter_map(|(time_stamp, value)| {
+                let before_end = match end.duration_since(*time_stamp) {
+                    Ok(age) => age.as_secs_f64(),
+                    Err(_) => return None,
+                };
+                if before_end > window_secs {
+                    return None;
+                }
+                Some((window_secs - before_end, *value))
+            })
+            .collect()
+    }
+
+    // The value closest in time to `x` seconds from the start of the window
+    pub fn value_at(&self, end: SystemTime, window_secs: f64, x: f64) -> Option<f64> {
+        self.window(end, window_secs)
+            .into_iter()
+            .min_by(|a, b| (a.0 - x).abs().total_cmp(&(b.0 - x).abs()))
+            .map(|(_, value)| value)
+    }
+
+    pub fn sparkline(&self, width: usize) -> String {
+        let values: Vec<f64> = self
+            .points
+            .iter()
+            .rev()
+            .take(width)
+            .map(|(_, value)| *value)
+            .collect::<Vec<f64>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let (min, max) = bounds(&values);
+        values
+            .iter()
+            .map(|value| {
+                // NaN and infinite readings leave a gap
+                if !value.is_finite() {
+                    return ' ';
+                }
+                if max - min <= f64::EPSILON {
+                    return SPARK_BARS[SPARK_BARS.len() / 2];
+                }
+                let ix = ((value - min) / (max - min) * (SPARK_BARS.len() - 1) as f64).round();
+                SPARK_BARS[(ix as usize).min(SPARK_BARS.len() - 1)]
+            })
+            .collect()
+    }
+}
+
+pub fn bounds(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+            (min.min(*value), max.max(*value))
+        })
+}
+
+#[cfg(test)]
+mod trend_tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn trend(values: &[f64]) -> Trend {
+        let mut trend = Trend::new(values.len());
+        for (ix, value) in values.iter().enumerate() {
+            trend.push(at(ix as u64), *value);
+        }
+        trend
+    }
+
+    #[test]
+    fn test_capacity_and_window() {
+        let mut trend = Trend::new(3);
+        for secs in [1, 2, 2, 3, 4] {
+            trend.push(at(secs), secs as f64);
+        }
+        // The repeated timestamp is skipped and the oldest point dropped
+        assert_eq!(trend.points.len(), 3);
+        assert_eq!(trend.last(), Some(4.0));
+        assert_eq!(trend.window(at(4), 1.5), vec![(0.5, 3.0), (1.5, 4.0)]);
+        assert_eq!(trend.value_at(at(4), 1.5, 0.0), Some(3.0));
+        assert!(trend.window(at(10), 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(trend(&[0.0, 7.0, 3.5]).sparkline(3), "▁█▅");
+        assert_eq!(trend(&[0.0, 7.0, 3.5]).sparkline(2), "█▁");
+        assert_eq!(trend(&[2.0, 2.0]).sparkline(2), "▅▅");
+        assert_eq!(trend(&[f64::NAN, f64::NAN]).sparkline(2), "  ");
+        assert_eq!(
+            trend(&[0.0, f64::INFINITY, 7.0, f64::NEG_INFINITY, f64::NAN]).sparkline(5),
+            "▁ █  "
+        );
+    }
+}
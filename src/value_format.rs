@@ -0,0 +1,550 @@
+use ratatui::style::{Color, Modifier, Style};
+use std::fmt;
+use tango_controls_client_sys::types::{AttrValue, AttributeInfo, DevState};
+
+// How the numbers of a watchlist row are rendered
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    // The attribute's format string, or the plain value without one
+    #[default]
+    Format,
+    Hex,
+    Binary,
+    Scientific,
+    // Scaled to an SI prefix, e.g. `12.346 kV`
+    Engineering,
+}
+
+impl DisplayMode {
+    pub fn next(&self) -> DisplayMode {
+        match self {
+            DisplayMode::Format => DisplayMode::Hex,
+            DisplayMode::Hex => DisplayMode::Binary,
+            DisplayMode::Binary => DisplayMode::Scientific,
+            DisplayMode::Scientific => DisplayMode::Engineering,
+            DisplayMode::Engineering => DisplayMode::Format,
+        }
+    }
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisplayMode::Format => write!(f, "Format"),
+            DisplayMode::Hex => write!(f, "Hex"),
+            DisplayMode::Binary => write!(f, "Binary"),
+            DisplayMode::Scientific => write!(f, "Scientific"),
+            DisplayMode::Engineering => write!(f, "Engineering"),
+        }
+    }
+}
+
+// The format and unit configured on an attribute
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ValueFormat {
+    pub format: Option<String>,
+    pub unit: Option<String>,
+}
+
+// Tango reports settings that are not set as "Not specified", "No unit" and so on
+fn configured(setting: &str) -> Option<String> {
+    let setting = setting.trim();
+    match setting {
+        "" | "Not specified" | "No unit" | "No format" | "No display unit" => None,
+        _ => Some(setting.to_string()),
+    }
+}
+
+impl ValueFormat {
+    pub fn from_attribute_info(attribute_info: &AttributeInfo) -> ValueFormat {
+        ValueFormat {
+            format: configured(&attribute_info.format),
+            unit: configured(&attribute_info.unit),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn as_f64(&self) -> f64 {
+        match self {
+            Number::Int(val) => *val as f64,
+            Number::Float(val) => *val,
+        }
+    }
+
+    fn as_i128(&self) -> i128 {
+        match self {
+            Number::Int(val) => *val,
+            Number::Float(val) => val.round() as i128,
+        }
+    }
+}
+
+// A single `%[flags][width][.precision]conversion` specification
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct PrintfSpec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    zero: bool,
+    alternate: bool,
+    width: usize,
+    precision: Option<usize>,
+    conversion: char,
+}
+
+// Split a printf format into the text before the specification, the specification
+// and the text after it. Only the first specification is used.
+fn parse_printf(format: &str) -> Option<(String, PrintfSpec, String)> {
+    let chars: Vec<char> = format.chars().collect();
+    let mut prefix = String::new();
+    let mut ix = 0;
+    while ix < chars.len() {
+        if chars[ix] == '%' {
+            if chars.get(ix + 1) == Some(&'%') {
+                prefix.push('%');
+                ix += 2;
+                continue;
+            }
+            break;
+        }
+        prefix.push(chars[ix]);
+        ix += 1;
+    }
+    ix += 1;
+    let mut spec = PrintfSpec::default();
+    while let Some(c) = chars.get(ix) {
+        match c {
+            '-' => spec.left = true,
+            '+' => spec.plus = true,
+            ' ' => spec.space = true,
+            '0' => spec.zero = true,
+            '#' => spec.alternate = true,
+            _ => break,
+        }
+        ix += 1;
+    }
+    while let Some(digit) = chars.get(ix).and_then(|c| c.to_digit(10)) {
+        spec.width = spec.width * 10 + digit as usize;
+        ix += 1;
+    }
+    if chars.get(ix) == Some(&'.') {
+        ix += 1;
+        let mut precision = 0;
+        while let Some(digit) = chars.get(ix).and_then(|c| c.to_digit(10)) {
+            precision = precision * 10 + digit as usize;
+            ix += 1;
+        }
+        spec.precision = Some(precision);
+    }
+    // Length modifiers make no difference here
+    while matches!(chars.get(ix), Some('h' | 'l' | 'L' | 'q' | 'j' | 'z' | 't')) {
+        ix += 1;
+    }
+    spec.conversion = *chars.get(ix)?;
+    if !"diufFeEgGxXos".contains(spec.conversion) {
+        return None;
+    }
+    let suffix = chars[ix + 1..]
+        .iter()
+        .collect::<String>()
+        .replace("%%", "%");
+    Some((prefix, spec, suffix))
+}
+
+// `1.500000e+03` rather than Rust's `1.5e3`
+fn format_exponential(value: f64, precision: usize, upper: bool) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or_default();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    let e = if upper { 'E' } else { 'e' };
+    format!("{}{}{}{:02}", mantissa, e, sign, exponent.abs())
+}
+
+fn trim_fraction_zeros(formatted: &str) -> String {
+    if !formatted.contains('.') {
+        return formatted.to_string();
+    }
+    let (number, exponent) = match formatted.find(['e', 'E']) {
+        Some(ix) => formatted.split_at(ix),
+        None => (formatted, ""),
+    };
+    let number = number.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", number, exponent)
+}
+
+fn format_general(value: f64, precision: usize, spec: &PrintfSpec) -> String {
+    let precision = precision.max(1);
+    let exponent = match value {
+        val if val == 0.0 || !val.is_finite() => 0,
+        _ => format!("{:.*e}", precision - 1, value)
+            .split_once('e')
+            .and_then(|(_, exponent)| exponent.parse::<i32>().ok())
+            .unwrap_or_default(),
+    };
+    let upper = spec.conversion == 'G';
+    let formatted = if exponent < -4 || exponent >= precision as i32 {
+        format_exponential(value, precision - 1, upper)
+    } else {
+        format!("{:.*}", (precision as i32 - 1 - exponent) as usize, value)
+    };
+    if spec.alternate {
+        formatted
+    } else {
+        trim_fraction_zeros(&formatted)
+    }
+}
+
+fn apply_printf(spec: &PrintfSpec, number: Number) -> String {
+    let value = number.as_f64();
+    let mut body = match spec.conversion {
+        'd' | 'i' | 'u' => number.as_i128().abs().to_string(),
+        'f' | 'F' => format!("{:.*}", spec.precision.unwrap_or(6), value.abs()),
+        'e' | 'E' => format_exponential(
+            value.abs(),
+            spec.precision.unwrap_or(6),
+            spec.conversion == 'E',
+        ),
+        'g' | 'G' => format_general(value.abs(), spec.precision.unwrap_or(6), spec),
+        'x' => format!("{:x}", number.as_i128() as i64),
+        'X' => format!("{:X}", number.as_i128() as i64),
+        'o' => format!("{:o}", number.as_i128() as i64),
+        _ => match number {
+            Number::Int(val) => val.abs().to_string(),
+            Number::Float(val) => val.abs().to_string(),
+        },
+    };
+    if spec.alternate && "xX".contains(spec.conversion) {
+        body = format!("0{}{}", spec.conversion, body);
+    }
+    let signed = !"xXo".contains(spec.conversion);
+    let sign = match (signed, value.is_sign_negative() && value != 0.0) {
+        (true, true) => "-",
+        (true, false) if spec.plus => "+",
+        (true, false) if spec.space => " ",
+        _ => "",
+    };
+    let length = sign.len() + body.chars().count();
+    let padding = spec.width.saturating_sub(length);
+    if spec.left {
+        format!("{}{}{}", sign, body, " ".repeat(padding))
+    } else if spec.zero {
+        format!("{}{}{}", sign, "0".repeat(padding), body)
+    } else {
+        format!("{}{}{}", " ".repeat(padding), sign, body)
+    }
+}
+
+// Format a number with a C printf style format like `%6.3f` or `Gain %d dB`.
+// Formats that cannot be applied give `None`. Integers are kept as they are,
+// 64 bit values do not fit in a f64.
+fn format_printf(format: &str, number: Number) -> Option<String> {
+    let (prefix, spec, suffix) = parse_printf(format)?;
+    Some(format!(
+        "{}{}{}",
+        prefix,
+        apply_printf(&spec, number),
+        suffix
+    ))
+}
+
+const SI_PREFIXES: [&str; 17] = [
+    "y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y",
+];
+
+// `12.346 k` for 12345.6, the unit follows the prefix
+pub fn format_engineering(value: f64, decimals: usize, unit: &str) -> String {
+    let exponent = if value == 0.0 || !value.is_finite() {
+        0
+    } else {
+        (value.abs().log10() / 3.0).floor() as i32 * 3
+    };
+    let exponent = exponent.clamp(-24, 24);
+    let mantissa = value / 10f64.powi(exponent);
+    let prefix = SI_PREFIXES[((exponent + 24) / 3) as usize];
+    format!("{:.*} {}{}", decimals, mantissa, prefix, unit)
+        .trim_end()
+        .to_string()
+}
+
+fn number_of(attr_value: &AttrValue) -> Option<Number> {
+    match attr_value {
+        AttrValue::UChar(val) => Some(Number::Int(*val as i128)),
+        AttrValue::Short(val) => Some(Number::Int(*val as i128)),
+        AttrValue::UShort(val) => Some(Number::Int(*val as i128)),
+        AttrValue::Long(val) => Some(Number::Int(*val as i128)),
+        AttrValue::ULong(val) => Some(Number::Int(*val as i128)),
+        AttrValue::Long64(val) => Some(Number::Int(*val as i128)),
+        AttrValue::ULong64(val) => Some(Number::Int(*val as i128)),
+        AttrValue::Float(val) => Some(Number::Float(*val as f64)),
+        AttrValue::Double(val) => Some(Number::Float(*val)),
+        _ => None,
+    }
+}
+
+fn numbers_of(attr_value: &AttrValue) -> Option<(Vec<Number>, u32)> {
+    fn ints<T: Copy + Into<i128>>(vals: &[T]) -> Vec<Number> {
+        vals.iter().map(|val| Number::Int((*val).into())).collect()
+    }
+    match attr_value {
+        AttrValue::UCharArray(vals) => Some((ints(vals), 8)),
+        AttrValue::ShortArray(vals) => Some((ints(vals), 16)),
+        AttrValue::UShortArray(vals) => Some((ints(vals), 16)),
+        AttrValue::LongArray(vals) => Some((ints(vals), 32)),
+        AttrValue::ULongArray(vals) => Some((ints(vals), 32)),
+        AttrValue::Long64Array(vals) => Some((ints(vals), 64)),
+        AttrValue::ULong64Array(vals) => Some((ints(vals), 64)),
+        AttrValue::FloatArray(vals) => Some((
+            vals.iter().map(|val| Number::Float(*val as f64)).collect(),
+            32,
+        )),
+        AttrValue::DoubleArray(vals) => {
+            Some((vals.iter().map(|val| Number::Float(*val)).collect(), 64))
+        }
+        _ => None,
+    }
+}
+
+// The number of bits of an integer type, to pad hex and binary values
+fn bit_width(attr_value: &AttrValue) -> u32 {
+    match attr_value {
+        AttrValue::UChar(_) => 8,
+        AttrValue::Short(_) | AttrValue::UShort(_) => 16,
+        AttrValue::Long(_) | AttrValue::ULong(_) => 32,
+        _ => 64,
+    }
+}
+
+fn format_number(number: Number, bits: u32, format: &ValueFormat, mode: DisplayMode) -> String {
+    let unit = format.unit.as_deref().unwrap_or_default();
+    let with_unit = |text: String| match unit {
+        "" => text,
+        _ => format!("{} {}", text, unit),
+    };
+    // The precision of the format string, if any, carries over to the other modes
+    let precision = format
+        .format
+        .as_deref()
+        .and_then(parse_printf)
+        .and_then(|(_, spec, _)| spec.precision);
+    match (mode, number) {
+        (DisplayMode::Hex, Number::Int(val)) => {
+            let mask = u64::MAX >> (64 - bits);
+            format!(
+                "0x{:0width$X}",
+                (val as u64) & mask,
+                width = bits as usize / 4
+            )
+        }
+        (DisplayMode::Binary, Number::Int(val)) => {
+            let mask = u64::MAX >> (64 - bits);
+            format!("0b{:0width$b}", (val as u64) & mask, width = bits as usize)
+        }
+        (DisplayMode::Scientific, _) => with_unit(format_exponential(
+            number.as_f64(),
+            precision.unwrap_or(3),
+            false,
+        )),
+        (DisplayMode::Engineering, _) => {
+            format_engineering(number.as_f64(), precision.unwrap_or(3), unit)
+        }
+        // Floats have no hex or binary representation
+        _ => {
+            let text = match &format.format {
+                Some(printf) => format_printf(printf, number),
+                None => None,
+            };
+            let text = text.unwrap_or_else(|| match number {
+                Number::Int(val) => val.to_string(),
+                Number::Float(val) => val.to_string(),
+            });
+            with_unit(text)
+        }
+    }
+}
+
+// Render a reading with the attribute's format and unit in the given mode.
+// Values that are not numbers keep their plain rendering.
+pub fn format_value(attr_value: &AttrValue, format: &ValueFormat, mode: DisplayMode) -> String {
+    if let Some(number) = number_of(attr_value) {
+        return format_number(number, bit_width(attr_value), format, mode);
+    }
+    match attr_value {
+        AttrValue::Boolean(val) => val.to_string(),
+        AttrValue::DevState(state) => state.to_string(),
+        AttrValue::DevEnum(val) => format!("#{}", val),
+        AttrValue::String(val) => val.clone(),
+        _ => match numbers_of(attr_value) {
+            // Arrays are left alone unless there is something to apply
+            Some((numbers, bits)) if mode != DisplayMode::Format || format.format.is_some() => {
+                let plain = ValueFormat {
+                    format: format.format.clone(),
+                    unit: None,
+                };
+                let items: Vec<String> = numbers
+                    .into_iter()
+                    .map(|number| format_number(number, bits, &plain, mode).trim().to_string())
+                    .collect();
+                match &format.unit {
+                    Some(unit) => format!("[{}] {}", items.join(", "), unit),
+                    None => format!("[{}]", items.join(", ")),
+                }
+            }
+            _ => format!("{}", attr_value),
+        },
+    }
+}
+
+// The colours of the device states in the Tango tools
+pub fn state_color(state: &DevState) -> Color {
+    match state {
+        DevState::ON | DevState::OPEN | DevState::EXTRACT => Color::Green,
+        DevState::OFF | DevState::CLOSE | DevState::INSERT => Color::White,
+        DevState::MOVING => Color::LightBlue,
+        DevState::STANDBY => Color::Yellow,
+        DevState::FAULT => Color::Red,
+        DevState::INIT => Color::LightYellow,
+        DevState::RUNNING => Color::LightGreen,
+        DevState::ALARM => Color::LightRed,
+        DevState::DISABLE => Color::Magenta,
+        DevState::UNKNOWN => Color::DarkGray,
+    }
+}
+
+// Booleans, states and enums are shown as coloured words
+pub fn value_style(attr_value: &AttrValue) -> Option<Style> {
+    match attr_value {
+        AttrValue::Boolean(true) => Some(Style::default().fg(Color::Green)),
+        AttrValue::Boolean(false) => Some(Style::default().fg(Color::DarkGray)),
+        AttrValue::DevState(state) => Some(
+            Style::default()
+                .fg(state_color(state))
+                .add_modifier(Modifier::BOLD),
+        ),
+        AttrValue::DevEnum(_) => Some(Style::default().fg(Color::Cyan)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod value_format_tests {
+    use super::*;
+
+    #[test]
+    fn test_format_printf() {
+        let cases = vec![
+            ("%6.3f", 1.23456, " 1.235"),
+            ("%-8.2f|", 2.5, "2.50    |"),
+            ("%+d", 42.0, "+42"),
+            ("%05d", -42.0, "-0042"),
+            ("%x", 255.0, "ff"),
+            ("%#X", 255.0, "0XFF"),
+            ("%.2e", 12345.678, "1.23e+04"),
+            ("%E", 0.00015, "1.500000E-04"),
+            ("%g", 0.0001, "0.0001"),
+            ("%g", 1234567.0, "1.23457e+06"),
+            ("%.3g", 2.5, "2.5"),
+            ("Gain %d dB (100%%)", 12.0, "Gain 12 dB (100%)"),
+            ("%d", 2.6, "3"),
+        ];
+        for (format, value, expected) in cases {
+            assert_eq!(
+                format_printf(format, Number::Float(value)).unwrap(),
+                expected,
+                "{}",
+                format
+            );
+        }
+        assert_eq!(
+            format_printf("%d", Number::Int(i128::from(u64::MAX))).unwrap(),
+            "18446744073709551615"
+        );
+        assert_eq!(format_printf("no spec", Number::Int(1)), None);
+        assert_eq!(format_printf("%k", Number::Float(1.0)), None);
+    }
+
+    #[test]
+    fn test_format_value() {
+        let format = ValueFormat {
+            format: Some("%6.2f".to_string()),
+            unit: Some("V".to_string()),
+        };
+        let value = AttrValue::Double(12345.678);
+        assert_eq!(
+            format_value(&value, &format, DisplayMode::Format),
+            "12345.68 V"
+        );
+        assert_eq!(
+            format_value(&value, &format, DisplayMode::Scientific),
+            "1.23e+04 V"
+        );
+        assert_eq!(
+            format_value(&value, &format, DisplayMode::Engineering),
+            "12.35 kV"
+        );
+        // No hex for floats
+        assert_eq!(
+            format_value(&value, &format, DisplayMode::Hex),
+            "12345.68 V"
+        );
+
+        let plain = ValueFormat::default();
+        assert_eq!(
+            format_value(&AttrValue::Short(-1), &plain, DisplayMode::Hex),
+            "0xFFFF"
+        );
+        assert_eq!(
+            format_value(&AttrValue::UChar(5), &plain, DisplayMode::Binary),
+            "0b00000101"
+        );
+        assert_eq!(
+            format_value(
+                &AttrValue::Double(0.000_002_5),
+                &plain,
+                DisplayMode::Engineering
+            ),
+            "2.500 µ"
+        );
+        assert_eq!(
+            format_value(
+                &AttrValue::UShortArray(vec![1, 255]),
+                &plain,
+                DisplayMode::Hex
+            ),
+            "[0x0001, 0x00FF]"
+        );
+        assert_eq!(
+            format_value(
+                &AttrValue::DevState(DevState::ON),
+                &format,
+                DisplayMode::Hex
+            ),
+            "ON"
+        );
+
+        // 64 bit integers keep every digit with a format
+        let integer = ValueFormat {
+            format: Some("%d".to_string()),
+            unit: None,
+        };
+        assert_eq!(
+            format_value(&AttrValue::ULong64(u64::MAX), &integer, DisplayMode::Format),
+            "18446744073709551615"
+        );
+        assert_eq!(
+            format_value(
+                &AttrValue::Long64(i64::MIN + 1),
+                &integer,
+                DisplayMode::Format
+            ),
+            "-9223372036854775807"
+        );
+    }
+}
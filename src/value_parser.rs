@@ -0,0 +1,1258 @@
+use crate::tango_utils::display_attr_value_type;
+use std::error::Error;
+use std::fmt;
+use tango_controls_client_sys::types::{AttrValue, CmdArgType, CommandData, DevEncoded, DevState};
+
+// The states accepted in command arguments, matched case-insensitively
+const STATES: [DevState; 14] = [
+    DevState::ON,
+    DevState::OFF,
+    DevState::CLOSE,
+    DevState::OPEN,
+    DevState::INSERT,
+    DevState::EXTRACT,
+    DevState::MOVING,
+    DevState::STANDBY,
+    DevState::FAULT,
+    DevState::INIT,
+    DevState::RUNNING,
+    DevState::ALARM,
+    DevState::DISABLE,
+    DevState::UNKNOWN,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    // Brackets, quotes or commas that do not fit together
+    Syntax,
+    // A list where a single value is expected, or the other way around
+    Shape,
+    Empty,
+    InvalidNumber,
+    OutOfRange,
+    InvalidBoolean,
+    InvalidState,
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub message: String,
+    // The 1-based character of the input the error points at
+    pub column: Option<usize>,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, column: Option<usize>, message: String) -> ParseError {
+        ParseError {
+            kind,
+            message,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "{} at column {}", self.message, column),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug)]
+struct Scalar {
+    text: String,
+    quoted: bool,
+    column: usize,
+}
+
+// The input is read as a tree of values and lists before it is typed
+#[derive(Debug)]
+enum Node {
+    Scalar(Scalar),
+    List(Vec<Node>, usize),
+}
+
+impl Node {
+    fn column(&self) -> usize {
+        match self {
+            Node::Scalar(scalar) => scalar.column,
+            Node::List(_, column) => *column,
+        }
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    ix: usize,
+}
+
+impl Lexer {
+    fn column(&self) -> usize {
+        self.ix + 1
+    }
+
+    fn syntax_error(column: usize, message: &str) -> ParseError {
+        ParseError::new(ParseErrorKind::Syntax, Some(column), message.to_string())
+    }
+
+    // The values up to the closing bracket, or up to the end of the input at the
+    // top level. Commas and whitespace both separate values.
+    fn sequence(&mut self, open_column: Option<usize>) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = Vec::new();
+        let mut comma_column: Option<usize> = None;
+        loop {
+            let column = self.column();
+            match self.chars.get(self.ix) {
+                None => {
+                    if let Some(open_column) = open_column {
+                        return Err(Lexer::syntax_error(open_column, "Missing closing ]"));
+                    }
+                    if let Some(comma_column) = comma_column {
+                        return Err(Lexer::syntax_error(comma_column, "Missing value after ,"));
+                    }
+                    return Ok(nodes);
+                }
+                Some(']') => {
+                    if open_column.is_none() {
+                        return Err(Lexer::syntax_error(column, "Unexpected ]"));
+                    }
+                    if let Some(comma_column) = comma_column {
+                        return Err(Lexer::syntax_error(comma_column, "Missing value after ,"));
+                    }
+                    self.ix += 1;
+                    return Ok(nodes);
+                }
+                Some(',') => {
+                    if nodes.is_empty() || comma_column.is_some() {
+                        return Err(Lexer::syntax_error(column, "Missing value before ,"));
+                    }
+                    comma_column = Some(column);
+                    self.ix += 1;
+                }
+                Some(c) if c.is_whitespace() => self.ix += 1,
+                Some('[') => {
+                    self.ix += 1;
+                    let items = self.sequence(Some(column))?;
+                    nodes.push(Node::List(items, column));
+                    comma_column = None;
+                }
+                Some('"') | Some('\'') => {
+                    nodes.push(Node::Scalar(self.quoted()?));
+                    comma_column = None;
+                }
+                Some(_) => {
+                    nodes.push(Node::Scalar(self.bare()));
+                    comma_column = None;
+                }
+            }
+        }
+    }
+
+    // A value up to the next separator or bracket, quotes inside are kept
+    fn bare(&mut self) -> Scalar {
+        let column = self.column();
+        let mut text = String::new();
+        while let Some(&c) = self.chars.get(self.ix) {
+            if c.is_whitespace() || matches!(c, ',' | '[' | ']') {
+                break;
+            }
+            text.push(c);
+            self.ix += 1;
+        }
+        Scalar {
+            text,
+            quoted: false,
+            column,
+        }
+    }
+
+    // A string in single or double quotes with the JSON escapes
+    fn quoted(&mut self) -> Result<Scalar, ParseError> {
+        let column = self.column();
+        let quote = self.chars[self.ix];
+        self.ix += 1;
+        let mut text = String::new();
+        loop {
+            match self.chars.get(self.ix) {
+                None => {
+                    return Err(Lexer::syntax_error(
+                        column,
+                        &format!("Missing closing {}", quote),
+                    ))
+                }
+                Some(&c) if c == quote => {
+                    self.ix += 1;
+                    break;
+                }
+                Some('\\') => {
+                    self.ix += 1;
+                    text.push(self.escape()?);
+                }
+                Some(&c) => {
+                    text.push(c);
+                    self.ix += 1;
+                }
+            }
+        }
+        match self.chars.get(self.ix) {
+            Some(&c) if !c.is_whitespace() && !matches!(c, ',' | ']') => Err(Lexer::syntax_error(
+                self.column(),
+                "Expected , after the closing quote",
+            )),
+            _ => Ok(Scalar {
+                text,
+                quoted: true,
+                column,
+            }),
+        }
+    }
+
+    // The character after a backslash, any character but the JSON escapes stands
+    // for itself
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let column = self.ix;
+        let c = *self
+            .chars
+            .get(self.ix)
+            .ok_or_else(|| Lexer::syntax_error(column, "Nothing to escape"))?;
+        self.ix += 1;
+        let escaped = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'u' => {
+                let mut code = self.hex_code(column)?;
+                // A surrogate pair, as JSON encodes characters outside the BMP
+                if (0xD800..0xDC00).contains(&code)
+                    && self.chars.get(self.ix) == Some(&'\\')
+                    && self.chars.get(self.ix + 1) == Some(&'u')
+                {
+                    self.ix += 2;
+                    let low = self.hex_code(column)?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(Lexer::syntax_error(column, "Invalid surrogate pair"));
+                    }
+                    code = 0x10000 + ((code - 0xD800) << 10) + low.wrapping_sub(0xDC00);
+                }
+                char::from_u32(code)
+                    .ok_or_else(|| Lexer::syntax_error(column, "Invalid \\u escape"))?
+            }
+            c => c,
+        };
+        Ok(escaped)
+    }
+
+    fn hex_code(&mut self, column: usize) -> Result<u32, ParseError> {
+        let digits: String = self.chars.iter().skip(self.ix).take(4).collect();
+        if digits.len() != 4 {
+            return Err(Lexer::syntax_error(column, "Invalid \\u escape"));
+        }
+        self.ix += 4;
+        u32::from_str_radix(&digits, 16)
+            .map_err(|_| Lexer::syntax_error(column, "Invalid \\u escape"))
+    }
+}
+
+fn parse_nodes(data: &str) -> Result<Vec<Node>, ParseError> {
+    let mut lexer = Lexer {
+        chars: data.chars().collect(),
+        ix: 0,
+    };
+    lexer.sequence(None)
+}
+
+fn required() -> ParseError {
+    ParseError::new(
+        ParseErrorKind::Empty,
+        None,
+        "A value is required".to_string(),
+    )
+}
+
+fn shape_error(column: usize, message: &str) -> ParseError {
+    ParseError::new(ParseErrorKind::Shape, Some(column), message.to_string())
+}
+
+// The value of a scalar type
+fn single(nodes: &[Node]) -> Result<&Scalar, ParseError> {
+    match nodes {
+        [] => Err(required()),
+        [Node::Scalar(scalar)] => Ok(scalar),
+        [Node::List(_, column), ..] => Err(shape_error(*column, "Expected a single value")),
+        [_, second, ..] => Err(shape_error(second.column(), "Expected a single value")),
+    }
+}
+
+fn scalars(nodes: &[Node]) -> Result<Vec<&Scalar>, ParseError> {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Scalar(scalar) => Ok(scalar),
+            Node::List(_, column) => Err(shape_error(*column, "Nested lists are not allowed")),
+        })
+        .collect()
+}
+
+// The items of an array, bracketed as `[1, 2, 3]` or bare as `1 2 3`
+fn items(nodes: &[Node]) -> Result<Vec<&Scalar>, ParseError> {
+    match nodes {
+        [Node::List(items, _)] => scalars(items),
+        _ => scalars(nodes),
+    }
+}
+
+// The two lists of the mixed types, entered as `[1, 2][a, b]` or as the JSON
+// `[[1, 2], ["a", "b"]]`
+fn pair(nodes: &[Node]) -> Result<(&Node, &Node), ParseError> {
+    match nodes {
+        [first, second] => Ok((first, second)),
+        [Node::List(items, _)] if items.len() == 2 => Ok((&items[0], &items[1])),
+        [] => Err(required()),
+        [first, ..] => Err(shape_error(
+            first.column(),
+            "Expected two lists like [1, 2][a, b]",
+        )),
+    }
+}
+
+fn list_items(node: &Node) -> Result<Vec<&Scalar>, ParseError> {
+    match node {
+        Node::List(items, _) => scalars(items),
+        Node::Scalar(scalar) => Err(shape_error(scalar.column, "Expected a list")),
+    }
+}
+
+fn parse_all<T>(
+    scalars: Vec<&Scalar>,
+    parse: fn(&Scalar) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    scalars.into_iter().map(parse).collect()
+}
+
+// Command arguments need at least one item in each array
+fn parse_required<T>(
+    scalars: Vec<&Scalar>,
+    parse: fn(&Scalar) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    if scalars.is_empty() {
+        return Err(required());
+    }
+    parse_all(scalars, parse)
+}
+
+trait Integer: Sized {
+    const MIN: i128;
+    const MAX: i128;
+    fn from_i128(value: i128) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($int:ty),*) => {$(
+        impl Integer for $int {
+            const MIN: i128 = <$int>::MIN as i128;
+            const MAX: i128 = <$int>::MAX as i128;
+            fn from_i128(value: i128) -> Self {
+                value as $int
+            }
+        }
+    )*};
+}
+
+impl_integer!(u8, i16, u16, i32, u32, i64, u64);
+
+fn number_text(scalar: &Scalar) -> Result<&str, ParseError> {
+    if scalar.quoted {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidNumber,
+            Some(scalar.column),
+            format!("Expected a number, not the text \"{}\"", scalar.text),
+        ));
+    }
+    Ok(&scalar.text)
+}
+
+// The sign, radix and digits of an integer like `-0x1F`
+fn split_radix(text: &str) -> (bool, u32, &str) {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    match unsigned.get(..2) {
+        Some("0x") | Some("0X") => (negative, 16, &unsigned[2..]),
+        Some("0b") | Some("0B") => (negative, 2, &unsigned[2..]),
+        Some("0o") | Some("0O") => (negative, 8, &unsigned[2..]),
+        _ => (negative, 10, unsigned),
+    }
+}
+
+fn parse_i128(scalar: &Scalar) -> Result<i128, ParseError> {
+    let text = number_text(scalar)?;
+    let invalid = || {
+        ParseError::new(
+            ParseErrorKind::InvalidNumber,
+            Some(scalar.column),
+            format!("[{}] is not an integer", text),
+        )
+    };
+    let out_of_range = || {
+        ParseError::new(
+            ParseErrorKind::OutOfRange,
+            Some(scalar.column),
+            format!("[{}] is out of range", text),
+        )
+    };
+    let is_digits =
+        |digits: &str, radix: u32| !digits.is_empty() && digits.chars().all(|c| c.is_digit(radix));
+    let (negative, radix, digits) = split_radix(text);
+    let magnitude = if is_digits(digits, radix) {
+        i128::from_str_radix(digits, radix).map_err(|_| out_of_range())?
+    } else if radix == 10 {
+        // Scientific notation of a whole number, e.g. 1e3, computed exactly
+        let (mantissa, exponent) = digits.split_once(['e', 'E']).ok_or_else(invalid)?;
+        let exponent = exponent.strip_prefix('+').unwrap_or(exponent);
+        if !is_digits(mantissa, 10) || !is_digits(exponent, 10) {
+            return Err(invalid());
+        }
+        let mantissa = mantissa.parse::<i128>().map_err(|_| out_of_range())?;
+        if mantissa == 0 {
+            0
+        } else {
+            exponent
+                .parse::<u32>()
+                .ok()
+                .and_then(|exponent| 10i128.checked_pow(exponent))
+                .and_then(|scale| mantissa.checked_mul(scale))
+                .ok_or_else(out_of_range)?
+        }
+    } else {
+        return Err(invalid());
+    };
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+fn parse_integer<T: Integer>(scalar: &Scalar) -> Result<T, ParseError> {
+    let value = parse_i128(scalar)?;
+    if value < T::MIN || value > T::MAX {
+        return Err(ParseError::new(
+            ParseErrorKind::OutOfRange,
+            Some(scalar.column),
+            format!(
+                "[{}] is out of range, expected {} to {}",
+                scalar.text,
+                T::MIN,
+                T::MAX
+            ),
+        ));
+    }
+    Ok(T::from_i128(value))
+}
+
+fn parse_f64(scalar: &Scalar) -> Result<f64, ParseError> {
+    let text = number_text(scalar)?;
+    if split_radix(text).1 != 10 {
+        return Ok(parse_i128(scalar)? as f64);
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_infinite() && !text.to_lowercase().contains("inf") => {
+            Err(ParseError::new(
+                ParseErrorKind::OutOfRange,
+                Some(scalar.column),
+                format!("[{}] is out of range", text),
+            ))
+        }
+        Ok(value) => Ok(value),
+        Err(_) => Err(ParseError::new(
+            ParseErrorKind::InvalidNumber,
+            Some(scalar.column),
+            format!("[{}] is not a number", text),
+        )),
+    }
+}
+
+// Parsed directly rather than through f64 to get the nearest f32 of the text
+fn parse_f32(scalar: &Scalar) -> Result<f32, ParseError> {
+    let text = number_text(scalar)?;
+    if split_radix(text).1 != 10 {
+        return Ok(parse_i128(scalar)? as f32);
+    }
+    match text.parse::<f32>() {
+        Ok(value) if value.is_infinite() && !text.to_lowercase().contains("inf") => {
+            Err(ParseError::new(
+                ParseErrorKind::OutOfRange,
+                Some(scalar.column),
+                format!("[{}] is out of range for a float", text),
+            ))
+        }
+        Ok(value) => Ok(value),
+        Err(_) => Err(ParseError::new(
+            ParseErrorKind::InvalidNumber,
+            Some(scalar.column),
+            format!("[{}] is not a number", text),
+        )),
+    }
+}
+
+fn parse_bool(scalar: &Scalar) -> Result<bool, ParseError> {
+    match scalar.text.to_lowercase().as_str() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidBoolean,
+            Some(scalar.column),
+            format!(
+                "[{}] is not a boolean, expected true, false, 1 or 0",
+                scalar.text
+            ),
+        )),
+    }
+}
+
+fn parse_state(scalar: &Scalar) -> Result<DevState, ParseError> {
+    STATES
+        .iter()
+        .find(|state| format!("{:?}", state).eq_ignore_ascii_case(&scalar.text))
+        .copied()
+        .ok_or_else(|| {
+            ParseError::new(
+                ParseErrorKind::InvalidState,
+                Some(scalar.column),
+                format!("[{}] is not a state, e.g. ON, OFF or FAULT", scalar.text),
+            )
+        })
+}
+
+fn parse_text(scalar: &Scalar) -> Result<String, ParseError> {
+    Ok(scalar.text.clone())
+}
+
+// The format and the bytes, or a quoted text sent as UTF-8, e.g. `[GRAY8][0, 255]`
+// or `[json]['{"a": 1}']`
+fn parse_encoded(nodes: &[Node]) -> Result<DevEncoded, ParseError> {
+    fn values(node: &Node) -> Result<Vec<&Scalar>, ParseError> {
+        match node {
+            Node::Scalar(scalar) => Ok(vec![scalar]),
+            Node::List(items, _) => scalars(items),
+        }
+    }
+    let (format_node, data_node) = pair(nodes)?;
+    let format = match values(format_node)?.as_slice() {
+        [format] => format.text.clone(),
+        _ => {
+            return Err(shape_error(
+                format_node.column(),
+                "Expected a single encoded format",
+            ))
+        }
+    };
+    let data = values(data_node)?;
+    let bytes = match data.as_slice() {
+        [text] if text.quoted => text.text.as_bytes().to_vec(),
+        _ => parse_all(data, parse_integer::<u8>)?,
+    };
+    Ok((format, bytes))
+}
+
+// An array of encoded values in the JSON form, e.g. `[["GRAY8", [0, 255]], ["json", "{}"]]`
+fn parse_encoded_array(nodes: &[Node]) -> Result<Vec<DevEncoded>, ParseError> {
+    let elements = match nodes {
+        [Node::List(elements, _)] => elements.as_slice(),
+        _ => nodes,
+    };
+    elements
+        .iter()
+        .map(|element| match element {
+            Node::List(pair_nodes, _) => parse_encoded(pair_nodes),
+            Node::Scalar(scalar) => Err(shape_error(
+                scalar.column,
+                "Expected a list like [\"format\", [1, 2]]",
+            )),
+        })
+        .collect()
+}
+
+// The input syntax of a command argument type, shown before anything is entered
+pub fn command_input_hint(data_type: CmdArgType) -> &'static str {
+    match data_type {
+        CmdArgType::DevVoid => "< No parameter, press ENTER >",
+        CmdArgType::DevBoolean => "< true or false >",
+        CmdArgType::DevString | CmdArgType::ConstDevString => "< Text >",
+        CmdArgType::DevState => "< State, e.g. ON >",
+        CmdArgType::DevEnum => "< Enum index, e.g. 2 >",
+        CmdArgType::DevVarBooleanArray => "< [true, false] >",
+        CmdArgType::DevVarStringArray => "< [a, \"b c\"] >",
+        CmdArgType::DevVarLongStringArray | CmdArgType::DevVarDoubleStringArray => {
+            "< [1, 2][a, \"b c\"] >"
+        }
+        CmdArgType::DevEncoded => "< [format][1, 2, 3] or [format][\"text\"] >",
+        CmdArgType::DevVarCharArray
+        | CmdArgType::DevVarShortArray
+        | CmdArgType::DevVarLongArray
+        | CmdArgType::DevVarFloatArray
+        | CmdArgType::DevVarDoubleArray
+        | CmdArgType::DevVarUShortArray
+        | CmdArgType::DevVarULongArray
+        | CmdArgType::DevVarLong64Array
+        | CmdArgType::DevVarULong64Array => "< [1, 2, 3] >",
+        _ => "< Enter parameter >",
+    }
+}
+
+// Parse a command argument. Numbers can be decimal, hex (0x), binary (0b), octal
+// (0o) or in scientific notation, arrays are bracketed or JSON lists and strings
+// with commas or spaces are quoted.
+pub fn parse_command_data(data: &str, data_type: CmdArgType) -> Result<CommandData, ParseError> {
+    // Strings are sent as entered, quotes included
+    if let CmdArgType::DevString | CmdArgType::ConstDevString = data_type {
+        return Ok(CommandData::String(data.to_string()));
+    }
+    let nodes = parse_nodes(data)?;
+    let res = match data_type {
+        CmdArgType::DevVoid => match nodes.first() {
+            None => CommandData::Void,
+            Some(node) => return Err(shape_error(node.column(), "The command takes no parameter")),
+        },
+        CmdArgType::DevBoolean => CommandData::Boolean(parse_bool(single(&nodes)?)?),
+        CmdArgType::DevShort => CommandData::Short(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevLong => CommandData::Long(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevFloat => CommandData::Float(parse_f32(single(&nodes)?)?),
+        CmdArgType::DevDouble => CommandData::Double(parse_f64(single(&nodes)?)?),
+        CmdArgType::DevUShort => CommandData::UShort(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevULong => CommandData::ULong(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevLong64 => CommandData::Long64(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevULong64 => CommandData::ULong64(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevState => CommandData::DevState(parse_state(single(&nodes)?)?),
+        CmdArgType::DevEnum => CommandData::DevEnum(parse_integer(single(&nodes)?)?),
+        CmdArgType::DevVarCharArray => {
+            CommandData::CharArray(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarShortArray => {
+            CommandData::ShortArray(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarLongArray => {
+            CommandData::LongArray(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarFloatArray => {
+            CommandData::FloatArray(parse_required(items(&nodes)?, parse_f32)?)
+        }
+        CmdArgType::DevVarDoubleArray => {
+            CommandData::DoubleArray(parse_required(items(&nodes)?, parse_f64)?)
+        }
+        CmdArgType::DevVarUShortArray => {
+            CommandData::UShortArray(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarULongArray => {
+            CommandData::ULongArray(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarLong64Array => {
+            CommandData::Long64Array(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarULong64Array => {
+            CommandData::ULong64Array(parse_required(items(&nodes)?, parse_integer)?)
+        }
+        CmdArgType::DevVarBooleanArray => {
+            CommandData::BooleanArray(parse_required(items(&nodes)?, parse_bool)?)
+        }
+        CmdArgType::DevVarStringArray => {
+            CommandData::StringArray(parse_required(items(&nodes)?, parse_text)?)
+        }
+        CmdArgType::DevVarLongStringArray => {
+            let (longs, strings) = pair(&nodes)?;
+            CommandData::LongStringArray(
+                parse_required(list_items(longs)?, parse_integer)?,
+                parse_required(list_items(strings)?, parse_text)?,
+            )
+        }
+        CmdArgType::DevVarDoubleStringArray => {
+            let (doubles, strings) = pair(&nodes)?;
+            CommandData::DoubleStringArray(
+                parse_required(list_items(doubles)?, parse_f64)?,
+                parse_required(list_items(strings)?, parse_text)?,
+            )
+        }
+        CmdArgType::DevEncoded => CommandData::DevEncoded(parse_encoded(&nodes)?),
+        _ => {
+            return Err(ParseError::new(
+                ParseErrorKind::Unsupported,
+                None,
+                format!("Command input type [{:?}] not supported", data_type),
+            ))
+        }
+    };
+    Ok(res)
+}
+
+// Parse an attribute write as the same type as `current`, with the syntax of
+// the command arguments. Arrays may be empty.
+pub fn parse_attr_value(data: &str, current: &AttrValue) -> Result<AttrValue, ParseError> {
+    if let AttrValue::String(_) = current {
+        return Ok(AttrValue::String(data.to_string()));
+    }
+    let nodes = parse_nodes(data)?;
+    let res = match current {
+        AttrValue::Boolean(_) => AttrValue::Boolean(parse_bool(single(&nodes)?)?),
+        AttrValue::UChar(_) => AttrValue::UChar(parse_integer(single(&nodes)?)?),
+        AttrValue::Short(_) => AttrValue::Short(parse_integer(single(&nodes)?)?),
+        AttrValue::UShort(_) => AttrValue::UShort(parse_integer(single(&nodes)?)?),
+        AttrValue::Long(_) => AttrValue::Long(parse_integer(single(&nodes)?)?),
+        AttrValue::ULong(_) => AttrValue::ULong(parse_integer(single(&nodes)?)?),
+        AttrValue::Long64(_) => AttrValue::Long64(parse_integer(single(&nodes)?)?),
+        AttrValue::ULong64(_) => AttrValue::ULong64(parse_integer(single(&nodes)?)?),
+        AttrValue::Float(_) => AttrValue::Float(parse_f32(single(&nodes)?)?),
+        AttrValue::Double(_) => AttrValue::Double(parse_f64(single(&nodes)?)?),
+        AttrValue::DevEnum(_) => AttrValue::DevEnum(parse_integer(single(&nodes)?)?),
+        AttrValue::DevState(_) => AttrValue::DevState(parse_state(single(&nodes)?)?),
+        AttrValue::DevEncoded(_) => AttrValue::DevEncoded(parse_encoded(&nodes)?),
+        AttrValue::BooleanArray(_) => {
+            AttrValue::BooleanArray(parse_all(items(&nodes)?, parse_bool)?)
+        }
+        AttrValue::UCharArray(_) => {
+            AttrValue::UCharArray(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::ShortArray(_) => {
+            AttrValue::ShortArray(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::UShortArray(_) => {
+            AttrValue::UShortArray(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::LongArray(_) => AttrValue::LongArray(parse_all(items(&nodes)?, parse_integer)?),
+        AttrValue::ULongArray(_) => {
+            AttrValue::ULongArray(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::Long64Array(_) => {
+            AttrValue::Long64Array(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::ULong64Array(_) => {
+            AttrValue::ULong64Array(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::FloatArray(_) => AttrValue::FloatArray(parse_all(items(&nodes)?, parse_f32)?),
+        AttrValue::DoubleArray(_) => AttrValue::DoubleArray(parse_all(items(&nodes)?, parse_f64)?),
+        AttrValue::StringArray(_) => AttrValue::StringArray(parse_all(items(&nodes)?, parse_text)?),
+        AttrValue::DevEnumArray(_) => {
+            AttrValue::DevEnumArray(parse_all(items(&nodes)?, parse_integer)?)
+        }
+        AttrValue::DevStateArray(_) => {
+            AttrValue::DevStateArray(parse_all(items(&nodes)?, parse_state)?)
+        }
+        AttrValue::DevEncodedArray(_) => AttrValue::DevEncodedArray(parse_encoded_array(&nodes)?),
+        _ => {
+            return Err(ParseError::new(
+                ParseErrorKind::Unsupported,
+                None,
+                format!(
+                    "Writing attributes of type [{}] is not supported",
+                    display_attr_value_type(current)
+                ),
+            ))
+        }
+    };
+    Ok(res)
+}
+
+// Render a string in double quotes with the JSON escapes, so that it parses
+// back unchanged
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn format_list<T>(values: &[T], format: impl Fn(&T) -> String) -> String {
+    let items: Vec<String> = values.iter().map(format).collect();
+    format!("[{}]", items.join(", "))
+}
+
+// Debug prints the shortest text that parses back to the same float
+fn format_float<F: fmt::Debug>(value: &F) -> String {
+    format!("{:?}", value)
+}
+
+fn format_state(state: &DevState) -> String {
+    format!("{:?}", state)
+}
+
+// Encoded text is shown as text, anything else as the bytes
+fn encoded_text(data: &[u8]) -> Option<&str> {
+    std::str::from_utf8(data).ok().filter(|text| {
+        !text.is_empty()
+            && text
+                .chars()
+                .all(|c| !c.is_control() || matches!(c, '\n' | '\t' | '\r'))
+    })
+}
+
+// The two list syntax of a single value, e.g. `["GRAY8"][0, 255]` or `["json"]["{}"]`
+fn format_encoded((format, data): &DevEncoded) -> String {
+    match encoded_text(data) {
+        Some(text) => format!("[{}][{}]", quote(format), quote(text)),
+        None => format!("[{}]{}", quote(format), format_list(data, u8::to_string)),
+    }
+}
+
+// The JSON syntax of an array element, e.g. `["GRAY8", [0, 255]]`
+fn format_encoded_element((format, data): &DevEncoded) -> String {
+    match encoded_text(data) {
+        Some(text) => format!("[{}, {}]", quote(format), quote(text)),
+        None => format!("[{}, {}]", quote(format), format_list(data, u8::to_string)),
+    }
+}
+
+// Render a command argument or result in the syntax `parse_command_data` reads
+// back to the same value. Command arguments cannot be empty arrays though.
+pub fn format_command_data(command_data: &CommandData) -> String {
+    match command_data {
+        CommandData::Void => String::new(),
+        CommandData::Boolean(val) => val.to_string(),
+        CommandData::Short(val) => val.to_string(),
+        CommandData::UShort(val) => val.to_string(),
+        CommandData::Long(val) => val.to_string(),
+        CommandData::ULong(val) => val.to_string(),
+        CommandData::Long64(val) => val.to_string(),
+        CommandData::ULong64(val) => val.to_string(),
+        CommandData::Float(val) => format_float(val),
+        CommandData::Double(val) => format_float(val),
+        CommandData::String(val) => val.clone(),
+        CommandData::DevState(val) => format_state(val),
+        CommandData::DevEncoded(val) => format_encoded(val),
+        CommandData::DevEnum(val) => val.to_string(),
+        CommandData::BooleanArray(vals) => format_list(vals, bool::to_string),
+        CommandData::CharArray(vals) => format_list(vals, u8::to_string),
+        CommandData::ShortArray(vals) => format_list(vals, i16::to_string),
+        CommandData::UShortArray(vals) => format_list(vals, u16::to_string),
+        CommandData::LongArray(vals) => format_list(vals, i32::to_string),
+        CommandData::ULongArray(vals) => format_list(vals, u32::to_string),
+        CommandData::Long64Array(vals) => format_list(vals, i64::to_string),
+        CommandData::ULong64Array(vals) => format_list(vals, u64::to_string),
+        CommandData::FloatArray(vals) => format_list(vals, format_float),
+        CommandData::DoubleArray(vals) => format_list(vals, format_float),
+        CommandData::StringArray(vals) => format_list(vals, |val| quote(val)),
+        CommandData::LongStringArray(longs, strings) => format!(
+            "{}{}",
+            format_list(longs, i32::to_string),
+            format_list(strings, |val| quote(val))
+        ),
+        CommandData::DoubleStringArray(doubles, strings) => format!(
+            "{}{}",
+            format_list(doubles, format_float),
+            format_list(strings, |val| quote(val))
+        ),
+    }
+}
+
+// Render an attribute value in the syntax `parse_attr_value` reads back to the
+// same value
+pub fn format_attr_value(attr_value: &AttrValue) -> String {
+    match attr_value {
+        AttrValue::Boolean(val) => val.to_string(),
+        AttrValue::UChar(val) => val.to_string(),
+        AttrValue::Short(val) => val.to_string(),
+        AttrValue::UShort(val) => val.to_string(),
+        AttrValue::Long(val) => val.to_string(),
+        AttrValue::ULong(val) => val.to_string(),
+        AttrValue::Long64(val) => val.to_string(),
+        AttrValue::ULong64(val) => val.to_string(),
+        AttrValue::Float(val) => format_float(val),
+        AttrValue::Double(val) => format_float(val),
+        AttrValue::String(val) => val.clone(),
+        AttrValue::DevState(val) => format_state(val),
+        AttrValue::DevEncoded(val) => format_encoded(val),
+        AttrValue::DevEnum(val) => val.to_string(),
+        AttrValue::BooleanArray(vals) => format_list(vals, bool::to_string),
+        AttrValue::UCharArray(vals) => format_list(vals, u8::to_string),
+        AttrValue::ShortArray(vals) => format_list(vals, i16::to_string),
+        AttrValue::UShortArray(vals) => format_list(vals, u16::to_string),
+        AttrValue::LongArray(vals) => format_list(vals, i32::to_string),
+        AttrValue::ULongArray(vals) => format_list(vals, u32::to_string),
+        AttrValue::Long64Array(vals) => format_list(vals, i64::to_string),
+        AttrValue::ULong64Array(vals) => format_list(vals, u64::to_string),
+        AttrValue::FloatArray(vals) => format_list(vals, format_float),
+        AttrValue::DoubleArray(vals) => format_list(vals, format_float),
+        AttrValue::StringArray(vals) => format_list(vals, |val| quote(val)),
+        AttrValue::DevStateArray(vals) => format_list(vals, format_state),
+        AttrValue::DevEncodedArray(vals) => format_list(vals, format_encoded_element),
+        AttrValue::DevEnumArray(vals) => format_list(vals, i16::to_string),
+    }
+}
+
+#[cfg(test)]
+mod value_parser_tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn error_of(data: &str, data_type: CmdArgType) -> (ParseErrorKind, Option<usize>) {
+        let err = parse_command_data(data, data_type).unwrap_err();
+        (err.kind, err.column)
+    }
+
+    #[test]
+    fn test_number_syntax() {
+        let tests = vec![
+            ("0x1F", CmdArgType::DevShort, CommandData::Short(31)),
+            ("-0b101", CmdArgType::DevLong, CommandData::Long(-5)),
+            ("0o17", CmdArgType::DevUShort, CommandData::UShort(15)),
+            ("1e3", CmdArgType::DevULong, CommandData::ULong(1000)),
+            ("2E+2", CmdArgType::DevLong, CommandData::Long(200)),
+            (
+                "12345678901234567891e0",
+                CmdArgType::DevULong64,
+                CommandData::ULong64(12345678901234567891),
+            ),
+            (
+                "-2.5e-3",
+                CmdArgType::DevDouble,
+                CommandData::Double(-0.0025),
+            ),
+            ("0xff", CmdArgType::DevFloat, CommandData::Float(255.0)),
+            (
+                "[10, 200, 0xff]",
+                CmdArgType::DevVarCharArray,
+                CommandData::CharArray(vec![10, 200, 255]),
+            ),
+            (
+                "[true, FALSE, 1, 0]",
+                CmdArgType::DevVarBooleanArray,
+                CommandData::BooleanArray(vec![true, false, true, false]),
+            ),
+            (
+                r#"["\ud83d\ude00"]"#,
+                CmdArgType::DevVarStringArray,
+                CommandData::StringArray(vec!["😀".to_string()]),
+            ),
+            (
+                "fault",
+                CmdArgType::DevState,
+                CommandData::DevState(DevState::FAULT),
+            ),
+        ];
+        for (data, data_type, expected) in tests {
+            assert_eq!(
+                parse_command_data(data, data_type).unwrap(),
+                expected,
+                "{}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_attr_value_parse() {
+        let tests = vec![
+            ("1", AttrValue::Boolean(false), AttrValue::Boolean(true)),
+            ("-3", AttrValue::Short(0), AttrValue::Short(-3)),
+            ("2.5", AttrValue::Double(0.0), AttrValue::Double(2.5)),
+            (
+                " a b ",
+                AttrValue::String(String::new()),
+                AttrValue::String(" a b ".to_string()),
+            ),
+            (
+                "[1, 2, 3]",
+                AttrValue::LongArray(vec![]),
+                AttrValue::LongArray(vec![1, 2, 3]),
+            ),
+        ];
+        for (data, current, expected) in tests {
+            assert_eq!(parse_attr_value(data, &current).unwrap(), expected);
+        }
+        assert!(parse_attr_value("yes", &AttrValue::Boolean(false)).is_err());
+        assert!(parse_attr_value("70000", &AttrValue::Short(0)).is_err());
+    }
+
+    #[test]
+    fn test_list_syntax() {
+        let bare_and_bracketed = vec![
+            "1,2,3",
+            " 1,2,3 ",
+            "[1,2,3]",
+            "1, 2, 3",
+            "1 2 3",
+            "[1, 2, 3]",
+            "[ 1, 2, 3 ]",
+            " [ 1, 2, 3 ] ",
+            " [ 1 , 2 ,  3 ] ",
+        ];
+        for data in bare_and_bracketed {
+            assert_eq!(
+                parse_command_data(data, CmdArgType::DevVarLongArray).unwrap(),
+                CommandData::LongArray(vec![1, 2, 3]),
+                "{}",
+                data
+            );
+        }
+        assert_eq!(
+            parse_command_data(r#"["a\"b", "é\n", 'c, d']"#, CmdArgType::DevVarStringArray)
+                .unwrap(),
+            CommandData::StringArray(vec![
+                "a\"b".to_string(),
+                "é\n".to_string(),
+                "c, d".to_string()
+            ])
+        );
+        assert_eq!(
+            parse_command_data(
+                r#"[[1.5, 2], ["a", "b"]]"#,
+                CmdArgType::DevVarDoubleStringArray
+            )
+            .unwrap(),
+            CommandData::DoubleStringArray(vec![1.5, 2.0], vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            parse_attr_value("[]", &AttrValue::DoubleArray(vec![1.0])).unwrap(),
+            AttrValue::DoubleArray(vec![])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        use ParseErrorKind::*;
+        let tests = vec![
+            ("yes", CmdArgType::DevBoolean, (InvalidBoolean, Some(1))),
+            (
+                "[1, 300]",
+                CmdArgType::DevVarCharArray,
+                (OutOfRange, Some(5)),
+            ),
+            ("70000", CmdArgType::DevShort, (OutOfRange, Some(1))),
+            ("-1", CmdArgType::DevULong, (OutOfRange, Some(1))),
+            ("1.5", CmdArgType::DevLong, (InvalidNumber, Some(1))),
+            ("1.0", CmdArgType::DevLong, (InvalidNumber, Some(1))),
+            ("2.000", CmdArgType::DevLong, (InvalidNumber, Some(1))),
+            ("12e-1", CmdArgType::DevLong, (InvalidNumber, Some(1))),
+            (
+                "12345678901234567891e1",
+                CmdArgType::DevULong64,
+                (OutOfRange, Some(1)),
+            ),
+            ("1e40", CmdArgType::DevLong64, (OutOfRange, Some(1))),
+            ("1e400", CmdArgType::DevDouble, (OutOfRange, Some(1))),
+            ("1e39", CmdArgType::DevFloat, (OutOfRange, Some(1))),
+            (
+                "[1, 2, x]",
+                CmdArgType::DevVarLongArray,
+                (InvalidNumber, Some(8)),
+            ),
+            ("[1, 2", CmdArgType::DevVarLongArray, (Syntax, Some(1))),
+            ("[1,, 2]", CmdArgType::DevVarLongArray, (Syntax, Some(4))),
+            ("[1, 2,]", CmdArgType::DevVarLongArray, (Syntax, Some(6))),
+            ("[1, [2]]", CmdArgType::DevVarLongArray, (Shape, Some(5))),
+            ("1 2", CmdArgType::DevLong, (Shape, Some(3))),
+            ("\"5\"", CmdArgType::DevLong, (InvalidNumber, Some(1))),
+            ("[a, \"b]", CmdArgType::DevVarStringArray, (Syntax, Some(5))),
+            (
+                r#"["\ud83d\u0041"]"#,
+                CmdArgType::DevVarStringArray,
+                (Syntax, Some(3)),
+            ),
+            ("[]", CmdArgType::DevVarLongArray, (Empty, None)),
+            ("on", CmdArgType::DevVoid, (Shape, Some(1))),
+            ("OPENED", CmdArgType::DevState, (InvalidState, Some(1))),
+            ("", CmdArgType::DevPipeBlob, (Unsupported, None)),
+        ];
+        for (data, data_type, expected) in tests {
+            assert_eq!(error_of(data, data_type), expected, "{}", data);
+        }
+        assert_eq!(
+            parse_command_data("[1, 300]", CmdArgType::DevVarCharArray)
+                .unwrap_err()
+                .to_string(),
+            "[300] is out of range, expected 0 to 255 at column 5"
+        );
+    }
+
+    #[test]
+    fn test_format_values() {
+        let tests = vec![
+            (
+                CommandData::DoubleArray(vec![1.0, -2.5e-7]),
+                "[1.0, -2.5e-7]",
+            ),
+            (
+                CommandData::LongStringArray(vec![1, 2], vec!["a \"b\"".to_string()]),
+                r#"[1, 2]["a \"b\""]"#,
+            ),
+            (
+                CommandData::DevEncoded(("json".to_string(), b"{}".to_vec())),
+                r#"["json"]["{}"]"#,
+            ),
+            (
+                CommandData::DevEncoded(("GRAY8".to_string(), vec![0, 255])),
+                r#"["GRAY8"][0, 255]"#,
+            ),
+            (CommandData::DevState(DevState::ON), "ON"),
+        ];
+        for (command_data, expected) in tests {
+            assert_eq!(format_command_data(&command_data), expected);
+        }
+        assert_eq!(
+            format_attr_value(&AttrValue::DevEncodedArray(vec![(
+                "GRAY8".to_string(),
+                vec![1]
+            )])),
+            r#"[["GRAY8", [1]]]"#
+        );
+        let nan = format_command_data(&CommandData::Double(f64::NAN));
+        assert!(matches!(
+            parse_command_data(&nan, CmdArgType::DevDouble),
+            Ok(CommandData::Double(val)) if val.is_nan()
+        ));
+    }
+
+    // NaN never equals itself, so it is checked separately above
+    fn float64() -> impl Strategy<Value = f64> {
+        use proptest::num::f64::*;
+        POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+    }
+
+    fn float32() -> impl Strategy<Value = f32> {
+        use proptest::num::f32::*;
+        POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+    }
+
+    fn state() -> impl Strategy<Value = DevState> {
+        proptest::sample::select(STATES.to_vec())
+    }
+
+    fn encoded() -> impl Strategy<Value = DevEncoded> {
+        let data = prop_oneof![
+            vec(any::<u8>(), 0..8),
+            any::<String>().prop_map(String::into_bytes),
+        ];
+        (any::<String>(), data)
+    }
+
+    // Arrays are not empty, as command arguments need a value
+    fn command_data() -> impl Strategy<Value = CommandData> {
+        prop_oneof![
+            Just(CommandData::Void),
+            any::<bool>().prop_map(CommandData::Boolean),
+            any::<i16>().prop_map(CommandData::Short),
+            any::<u16>().prop_map(CommandData::UShort),
+            any::<i32>().prop_map(CommandData::Long),
+            any::<u32>().prop_map(CommandData::ULong),
+            any::<i64>().prop_map(CommandData::Long64),
+            any::<u64>().prop_map(CommandData::ULong64),
+            float32().prop_map(CommandData::Float),
+            float64().prop_map(CommandData::Double),
+            any::<String>().prop_map(CommandData::String),
+            state().prop_map(CommandData::DevState),
+            encoded().prop_map(CommandData::DevEncoded),
+            any::<i16>().prop_map(CommandData::DevEnum),
+            vec(any::<bool>(), 1..5).prop_map(CommandData::BooleanArray),
+            vec(any::<u8>(), 1..5).prop_map(CommandData::CharArray),
+            vec(any::<i16>(), 1..5).prop_map(CommandData::ShortArray),
+            vec(any::<u16>(), 1..5).prop_map(CommandData::UShortArray),
+            vec(any::<i32>(), 1..5).prop_map(CommandData::LongArray),
+            vec(any::<u32>(), 1..5).prop_map(CommandData::ULongArray),
+            vec(any::<i64>(), 1..5).prop_map(CommandData::Long64Array),
+            vec(any::<u64>(), 1..5).prop_map(CommandData::ULong64Array),
+            vec(float32(), 1..5).prop_map(CommandData::FloatArray),
+            vec(float64(), 1..5).prop_map(CommandData::DoubleArray),
+            vec(any::<String>(), 1..5).prop_map(CommandData::StringArray),
+            (vec(any::<i32>(), 1..5), vec(any::<String>(), 1..5))
+                .prop_map(|(longs, strings)| CommandData::LongStringArray(longs, strings)),
+            (vec(float64(), 1..5), vec(any::<String>(), 1..5))
+                .prop_map(|(doubles, strings)| CommandData::DoubleStringArray(doubles, strings)),
+        ]
+    }
+
+    fn command_arg_type(command_data: &CommandData) -> CmdArgType {
+        match command_data {
+            CommandData::Void => CmdArgType::DevVoid,
+            CommandData::Boolean(_) => CmdArgType::DevBoolean,
+            CommandData::Short(_) => CmdArgType::DevShort,
+            CommandData::UShort(_) => CmdArgType::DevUShort,
+            CommandData::Long(_) => CmdArgType::DevLong,
+            CommandData::ULong(_) => CmdArgType::DevULong,
+            CommandData::Long64(_) => CmdArgType::DevLong64,
+            CommandData::ULong64(_) => CmdArgType::DevULong64,
+            CommandData::Float(_) => CmdArgType::DevFloat,
+            CommandData::Double(_) => CmdArgType::DevDouble,
+            CommandData::String(_) => CmdArgType::DevString,
+            CommandData::DevState(_) => CmdArgType::DevState,
+            CommandData::DevEncoded(_) => CmdArgType::DevEncoded,
+            CommandData::DevEnum(_) => CmdArgType::DevEnum,
+            CommandData::BooleanArray(_) => CmdArgType::DevVarBooleanArray,
+            CommandData::CharArray(_) => CmdArgType::DevVarCharArray,
+            CommandData::ShortArray(_) => CmdArgType::DevVarShortArray,
+            CommandData::UShortArray(_) => CmdArgType::DevVarUShortArray,
+            CommandData::LongArray(_) => CmdArgType::DevVarLongArray,
+            CommandData::ULongArray(_) => CmdArgType::DevVarULongArray,
+            CommandData::Long64Array(_) => CmdArgType::DevVarLong64Array,
+            CommandData::ULong64Array(_) => CmdArgType::DevVarULong64Array,
+            CommandData::FloatArray(_) => CmdArgType::DevVarFloatArray,
+            CommandData::DoubleArray(_) => CmdArgType::DevVarDoubleArray,
+            CommandData::StringArray(_) => CmdArgType::DevVarStringArray,
+            CommandData::LongStringArray(_, _) => CmdArgType::DevVarLongStringArray,
+            CommandData::DoubleStringArray(_, _) => CmdArgType::DevVarDoubleStringArray,
+        }
+    }
+
+    fn attr_value() -> impl Strategy<Value = AttrValue> {
+        prop_oneof![
+            any::<bool>().prop_map(AttrValue::Boolean),
+            any::<u8>().prop_map(AttrValue::UChar),
+            any::<i16>().prop_map(AttrValue::Short),
+            any::<u16>().prop_map(AttrValue::UShort),
+            any::<i32>().prop_map(AttrValue::Long),
+            any::<u32>().prop_map(AttrValue::ULong),
+            any::<i64>().prop_map(AttrValue::Long64),
+            any::<u64>().prop_map(AttrValue::ULong64),
+            float32().prop_map(AttrValue::Float),
+            float64().prop_map(AttrValue::Double),
+            any::<String>().prop_map(AttrValue::String),
+            state().prop_map(AttrValue::DevState),
+            encoded().prop_map(AttrValue::DevEncoded),
+            any::<i16>().prop_map(AttrValue::DevEnum),
+            vec(any::<bool>(), 0..5).prop_map(AttrValue::BooleanArray),
+            vec(any::<u8>(), 0..5).prop_map(AttrValue::UCharArray),
+            vec(any::<i16>(), 0..5).prop_map(AttrValue::ShortArray),
+            vec(any::<u16>(), 0..5).prop_map(AttrValue::UShortArray),
+            vec(any::<i32>(), 0..5).prop_map(AttrValue::LongArray),
+            vec(any::<u32>(), 0..5).prop_map(AttrValue::ULongArray),
+            vec(any::<i64>(), 0..5).prop_map(AttrValue::Long64Array),
+            vec(any::<u64>(), 0..5).prop_map(AttrValue::ULong64Array),
+            vec(float32(), 0..5).prop_map(AttrValue::FloatArray),
+            vec(float64(), 0..5).prop_map(AttrValue::DoubleArray),
+            vec(any::<String>(), 0..5).prop_map(AttrValue::StringArray),
+            vec(state(), 0..5).prop_map(AttrValue::DevStateArray),
+            vec(encoded(), 0..5).prop_map(AttrValue::DevEncodedArray),
+            vec(any::<i16>(), 0..5).prop_map(AttrValue::DevEnumArray),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_command_data_round_trip(command_data in command_data()) {
+            let text = format_command_data(&command_data);
+            let parsed = parse_command_data(&text, command_arg_type(&command_data));
+            prop_assert_eq!(parsed, Ok(command_data), "{}", text);
+        }
+
+        #[test]
+        fn test_attr_value_round_trip(attr_value in attr_value()) {
+            let text = format_attr_value(&attr_value);
+            let parsed = parse_attr_value(&text, &attr_value);
+            prop_assert_eq!(parsed, Ok(attr_value), "{}", text);
+        }
+    }
+}
@@ -1,35 +1,100 @@
+pub mod chart;
 pub mod command;
 pub mod confirm_command;
 pub mod explorer;
+pub mod history;
+pub mod image;
+pub mod spectrum;
 pub mod watchlist;
 
+use chart::ViewChart;
 use command::ViewCommand;
 use confirm_command::ViewConfirmCommand;
 use explorer::ViewExplorerHome;
-use std::collections::BTreeMap;
+use history::ViewHistory;
+use image::ViewImage;
+use spectrum::ViewSpectrum;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use watchlist::ViewWatchList;
 
-use crate::tango_utils::TangoDevicesLookup;
-use crate::views::watchlist::AttributeReading;
+use crate::alerts::Alerts;
+use crate::audit_log::{AuditEntry, AuditLog};
+use crate::derived::DerivedAttributes;
+use crate::limits::AttributeLimits;
+use crate::recorder::{Recorder, RecorderConfig};
+use crate::snapshot::Snapshot;
+use crate::statistics::AttributeStatistics;
+use crate::tango_utils::{expand_attribute_pattern, TangoDevicesLookup};
+use crate::trend::Trend;
+use crate::value_format::ValueFormat;
+use crate::views::watchlist::{AttributeReading, PageLayout};
+use crate::watchlist_file::split_attribute_name;
 use crate::{Event, VERSION, WEBSITE};
+use chrono::{DateTime, Local};
 use crossterm::event::KeyEvent;
+use log::{error, info};
 use ratatui::symbols::line::DOUBLE_VERTICAL;
 use ratatui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Tabs},
     Frame,
 };
 use std::hash::Hash;
+use tango_controls_client_sys::types::AttributeInfo;
 
 use self::command::ExecutedCommands;
 pub type DeviceName = String;
 pub type AttributeName = String;
 pub type AttributeReadings = BTreeMap<DeviceName, BTreeMap<AttributeName, AttributeReading>>;
+pub type AttributeTrends = BTreeMap<DeviceName, BTreeMap<AttributeName, Trend>>;
+pub type AttributeStatisticsMap =
+    BTreeMap<DeviceName, BTreeMap<AttributeName, AttributeStatistics>>;
+pub type AttributeConfigs = BTreeMap<DeviceName, BTreeMap<AttributeName, AttributeInfo>>;
+pub type AttributeLimitsMap = BTreeMap<DeviceName, BTreeMap<AttributeName, AttributeLimits>>;
+
+// A `domain/family/member/attribute` pattern with `*` wildcards and the
+// entries it expanded to against the device list
+#[derive(Debug, Default, Clone)]
+pub struct WildcardWatch {
+    pub pattern: String,
+    pub limits: AttributeLimits,
+    pub matches: BTreeSet<(DeviceName, AttributeName)>,
+}
+
+// A named watchlist page, all pages share the polled `watch_list`
+#[derive(Debug, Default, Clone)]
+pub struct WatchPage {
+    pub name: String,
+    pub entries: BTreeSet<(DeviceName, AttributeName)>,
+    pub wildcards: Vec<WildcardWatch>,
+    pub layout: PageLayout,
+}
+
+impl WatchPage {
+    pub fn new(name: &str) -> WatchPage {
+        WatchPage {
+            name: name.to_string(),
+            ..WatchPage::default()
+        }
+    }
+}
+
+// The readings when the display was frozen, polling carries on underneath
+#[derive(Debug, Default, Clone)]
+pub struct FrozenReadings {
+    pub readings: AttributeReadings,
+    // New readings received since
+    pub buffered_updates: usize,
+}
+
+pub const DEFAULT_PAGE_NAME: &str = "Main";
 
 // The SharedViewState is information that are shared between the different tabs
 // and sections within the tab itself.
@@ -38,9 +103,49 @@ pub struct SharedViewState<'a> {
     pub tango_host: Option<String>,
     pub selected_device: Option<String>,
     pub watch_list: Arc<Mutex<AttributeReadings>>,
+    // Shown instead of the latest readings while the display is frozen
+    pub frozen: Option<FrozenReadings>,
+    // Attributes that are not polled
+    pub paused: Arc<Mutex<BTreeSet<(DeviceName, AttributeName)>>>,
+    pub pages: Vec<WatchPage>,
+    pub current_page: usize,
     pub current_view: View,
     pub tango_devices_lookup: TangoDevicesLookup<'a>,
     pub executed_commands: ExecutedCommands,
+    pub stale_after: Duration,
+    pub trends: AttributeTrends,
+    pub trend_capacity: usize,
+    pub statistics: AttributeStatisticsMap,
+    pub chart_attributes: Vec<(DeviceName, AttributeName)>,
+    pub detail_attribute: Option<(DeviceName, AttributeName)>,
+    pub attribute_configs: Arc<Mutex<AttributeConfigs>>,
+    pub user_limits: AttributeLimitsMap,
+    pub recorder_config: RecorderConfig,
+    pub recorder: Option<Recorder>,
+    pub recorder_error: Option<String>,
+    // Where the last snapshot was saved, or why it failed
+    pub snapshot_status: Option<Result<String, String>>,
+    pub derived: DerivedAttributes,
+    pub alerts: Alerts,
+    // Where executions are logged, and the entries logged so far for the history tab
+    pub audit_log: Option<AuditLog>,
+    pub audit_entries: Vec<AuditEntry>,
+    pub audit_error: Option<String>,
+    // Set to rebuild the device tree from the database
+    pub refresh_devices: bool,
+    // The confirmation popup asks to delete the current page instead of running the command
+    pub confirm_delete_page: bool,
+}
+
+fn retain_referenced<T>(
+    map: &mut BTreeMap<DeviceName, BTreeMap<AttributeName, T>>,
+    referenced: &BTreeSet<(DeviceName, AttributeName)>,
+) {
+    for (device_name, attr_map) in map.iter_mut() {
+        attr_map
+            .retain(|attr_name, _| referenced.contains(&(device_name.clone(), attr_name.clone())));
+    }
+    map.retain(|_, attr_map| !attr_map.is_empty());
 }
 
 impl SharedViewState<'_> {
@@ -49,25 +154,313 @@ impl SharedViewState<'_> {
             tango_host: None,
             selected_device: None,
             watch_list: Arc::default(),
+            frozen: None,
+            paused: Arc::default(),
+            pages: vec![WatchPage::new(DEFAULT_PAGE_NAME)],
+            current_page: 0,
             current_view: View::Explorer,
             tango_devices_lookup: TangoDevicesLookup::default(),
             executed_commands: ExecutedCommands::new(tx_commands),
+            stale_after: Duration::from_secs(10),
+            trends: AttributeTrends::default(),
+            trend_capacity: 3600,
+            statistics: AttributeStatisticsMap::default(),
+            chart_attributes: Vec::new(),
+            detail_attribute: None,
+            attribute_configs: Arc::default(),
+            user_limits: AttributeLimitsMap::default(),
+            recorder_config: RecorderConfig::default(),
+            recorder: None,
+            recorder_error: None,
+            snapshot_status: None,
+            derived: DerivedAttributes::default(),
+            alerts: Alerts::default(),
+            audit_log: None,
+            audit_entries: Vec::new(),
+            audit_error: None,
+            refresh_devices: false,
+            confirm_delete_page: false,
         }
     }
 
     pub fn add_watch_attribute(&mut self, attribute_name: String) {
-        if let Some(current_device) = &self.selected_device {
-            // Add the device if not present
-            self.watch_list
-                .lock()
-                .unwrap()
-                .entry(current_device.clone())
-                .or_insert_with(BTreeMap::default);
-            // Add the attribute if not present
-            if let Some(attr_map) = self.watch_list.lock().unwrap().get_mut(current_device) {
-                attr_map
-                    .entry(attribute_name)
-                    .or_insert_with(AttributeReading::default);
+        if let Some(current_device) = self.selected_device.clone() {
+            self.add_watch_entry(current_device, attribute_name);
+        };
+    }
+
+    // Watch the attribute on the current page
+    pub fn add_watch_entry(&mut self, device_name: String, attribute_name: String) {
+        self.add_page_entry(self.current_page, device_name, attribute_name);
+    }
+
+    pub fn add_page_entry(&mut self, page_ix: usize, device_name: String, attribute_name: String) {
+        if let Some(page) = self.pages.get_mut(page_ix) {
+            page.entries
+                .insert((device_name.clone(), attribute_name.clone()));
+        }
+        // Add the device if not present
+        self.watch_list
+            .lock()
+            .unwrap()
+            .entry(device_name.clone())
+            .or_insert_with(BTreeMap::default);
+        // Add the attribute if not present
+        if let Some(attr_map) = self.watch_list.lock().unwrap().get_mut(&device_name) {
+            attr_map
+                .entry(attribute_name)
+                .or_insert_with(AttributeReading::default);
+        }
+    }
+
+    pub fn page(&self) -> &WatchPage {
+        &self.pages[self.current_page]
+    }
+
+    pub fn page_mut(&mut self) -> &mut WatchPage {
+        &mut self.pages[self.current_page]
+    }
+
+    // The index of the named page, created if it does not exist yet
+    pub fn page_ix(&mut self, name: &str) -> usize {
+        match self.pages.iter().position(|page| page.name == name) {
+            Some(page_ix) => page_ix,
+            None => {
+                self.pages.push(WatchPage::new(name));
+                self.pages.len() - 1
+            }
+        }
+    }
+
+    pub fn add_page(&mut self, name: &str) -> bool {
+        if name.is_empty() || self.pages.iter().any(|page| page.name == name) {
+            return false;
+        }
+        self.pages.push(WatchPage::new(name));
+        self.current_page = self.pages.len() - 1;
+        true
+    }
+
+    pub fn rename_page(&mut self, name: &str) -> bool {
+        if name.is_empty() || self.pages.iter().any(|page| page.name == name) {
+            return false;
+        }
+        self.page_mut().name = name.to_string();
+        true
+    }
+
+    // The last page is never deleted
+    pub fn delete_page(&mut self) -> bool {
+        if self.pages.len() < 2 {
+            return false;
+        }
+        self.pages.remove(self.current_page);
+        self.current_page = self.current_page.min(self.pages.len() - 1);
+        self.prune_watch_list();
+        true
+    }
+
+    pub fn next_page(&mut self) {
+        self.current_page = (self.current_page + 1) % self.pages.len();
+    }
+
+    pub fn previous_page(&mut self) {
+        self.current_page = (self.current_page + self.pages.len() - 1) % self.pages.len();
+    }
+
+    // Match the wildcard entries of every page against the current device list,
+    // adding new matches and dropping the ones that no longer match
+    pub fn expand_wildcards(&mut self) {
+        let devices = self.tango_devices_lookup.devices.clone();
+        for page_ix in 0..self.pages.len() {
+            for wildcard_ix in 0..self.pages[page_ix].wildcards.len() {
+                let wildcard = self.pages[page_ix].wildcards[wildcard_ix].clone();
+                let matches: BTreeSet<(DeviceName, AttributeName)> =
+                    expand_attribute_pattern(&wildcard.pattern, &devices)
+                        .into_iter()
+                        .collect();
+                for entry in wildcard.matches.difference(&matches) {
+                    self.pages[page_ix].entries.remove(entry);
+                }
+                for (device_name, attr_name) in &matches {
+                    if !wildcard.limits.is_empty() {
+                        self.user_limits
+                            .entry(device_name.clone())
+                            .or_default()
+                            .insert(attr_name.clone(), wildcard.limits);
+                    }
+                    self.add_page_entry(page_ix, device_name.clone(), attr_name.clone());
+                }
+                info!(
+                    "Watch pattern {} matches {} attributes",
+                    wildcard.pattern,
+                    matches.len()
+                );
+                self.pages[page_ix].wildcards[wildcard_ix].matches = matches;
+            }
+        }
+        self.prune_watch_list();
+    }
+
+    // Stop polling attributes that are not on any page and that no derived
+    // attribute or alert rule depends on
+    fn prune_watch_list(&mut self) {
+        let mut referenced: BTreeSet<(DeviceName, AttributeName)> = self
+            .pages
+            .iter()
+            .flat_map(|page| page.entries.iter().cloned())
+            .collect();
+        let inputs = self
+            .derived
+            .values()
+            .chain(self.alerts.rules.iter().map(|rule| &rule.condition))
+            .flat_map(|expression| expression.inputs());
+        referenced.extend(inputs.filter_map(|input| split_attribute_name(&input)));
+        retain_referenced(&mut self.watch_list.lock().unwrap(), &referenced);
+        // Forget the state of the dropped attributes, they start afresh when watched again
+        retain_referenced(&mut self.user_limits, &referenced);
+        retain_referenced(&mut self.trends, &referenced);
+        retain_referenced(&mut self.statistics, &referenced);
+        self.paused
+            .lock()
+            .unwrap()
+            .retain(|entry| referenced.contains(entry));
+    }
+
+    // The device limits of the attribute with the limits from the watchlist file applied
+    pub fn attribute_limits(&self, device_name: &str, attribute_name: &str) -> AttributeLimits {
+        let device_limits = self
+            .attribute_configs
+            .lock()
+            .unwrap()
+            .get(device_name)
+            .and_then(|attr_map| attr_map.get(attribute_name))
+            .map(AttributeLimits::from_attribute_info)
+            .unwrap_or_default();
+        match self
+            .user_limits
+            .get(device_name)
+            .and_then(|attr_map| attr_map.get(attribute_name))
+        {
+            Some(user_limits) => device_limits.merge(user_limits),
+            None => device_limits,
+        }
+    }
+
+    // The display format and unit configured on the device
+    pub fn value_format(&self, device_name: &str, attribute_name: &str) -> ValueFormat {
+        self.attribute_configs
+            .lock()
+            .unwrap()
+            .get(device_name)
+            .and_then(|attr_map| attr_map.get(attribute_name))
+            .map(ValueFormat::from_attribute_info)
+            .unwrap_or_default()
+    }
+
+    // Start recording the watchlist readings, or stop if already recording
+    pub fn toggle_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            info!(
+                "Stopped recording to {} after {} lines",
+                recorder.path().display(),
+                recorder.lines_written
+            );
+            return;
+        }
+        match Recorder::start(&self.recorder_config) {
+            Ok(recorder) => {
+                info!("Recording to {}", recorder.path().display());
+                self.recorder = Some(recorder);
+                self.recorder_error = None;
+            }
+            Err(err) => {
+                error!("Could not start recording: {}", err);
+                self.recorder_error = Some(err.to_string());
+            }
+        }
+    }
+
+    // Freeze the displayed readings, or show the latest ones again
+    pub fn toggle_freeze(&mut self) {
+        self.frozen = match self.frozen {
+            Some(_) => None,
+            None => Some(FrozenReadings {
+                readings: self.watch_list.lock().unwrap().clone(),
+                buffered_updates: 0,
+            }),
+        };
+    }
+
+    // Stop or resume polling an attribute
+    pub fn toggle_paused(&mut self, device_name: DeviceName, attr_name: AttributeName) {
+        let mut paused = self.paused.lock().unwrap();
+        let entry = (device_name, attr_name);
+        if !paused.remove(&entry) {
+            info!("Paused polling {}/{}", entry.0, entry.1);
+            paused.insert(entry);
+        }
+    }
+
+    pub fn is_paused(&self, device_name: &str, attr_name: &str) -> bool {
+        self.paused
+            .lock()
+            .unwrap()
+            .contains(&(device_name.to_string(), attr_name.to_string()))
+    }
+
+    // Read the audit log again, other sessions and the subcommands append to it too
+    pub fn load_audit_entries(&mut self) {
+        if let Some(audit_log) = &self.audit_log {
+            match audit_log.load() {
+                Ok(entries) => {
+                    self.audit_entries = entries;
+                    self.audit_error = None;
+                }
+                Err(err) => {
+                    error!("Could not load the audit log: {}", err);
+                    self.audit_error = Some(err.to_string());
+                }
+            }
+        }
+    }
+
+    // Log the finished command for the history tab and the audit log
+    pub fn audit_command(&mut self, id: u64) {
+        let (audit_log, executed_command) = match (
+            &self.audit_log,
+            self.executed_commands.executed_commands.get(&id),
+        ) {
+            (Some(audit_log), Some(executed_command)) => (audit_log, executed_command),
+            _ => return,
+        };
+        let entry = audit_log.command_entry(executed_command);
+        if let Err(err) = audit_log.append(&entry) {
+            error!("Could not write to the audit log: {}", err);
+            self.audit_error = Some(err.to_string());
+        }
+        self.audit_entries.push(entry);
+    }
+
+    // Save the current watchlist values to a snapshot file in the working directory
+    pub fn save_snapshot(&mut self) {
+        let snapshot = match self.watch_list.lock() {
+            Ok(watch_list) => Snapshot::capture(&watch_list, SystemTime::now()),
+            Err(_) => return,
+        };
+        let path = PathBuf::from(snapshot.file_name());
+        self.snapshot_status = match snapshot.save(&path) {
+            Ok(_) => {
+                info!(
+                    "Saved a snapshot of {} attributes to {}",
+                    snapshot.entries.len(),
+                    path.display()
+                );
+                Some(Ok(path.display().to_string()))
+            }
+            Err(err) => {
+                error!("Could not save the snapshot: {}", err);
+                Some(Err(err.to_string()))
             }
         };
     }
@@ -86,11 +479,17 @@ impl SharedViewState<'_> {
         }
     }
     pub fn toggle_current_view(&mut self) {
+        // Leaving the popup cancels a page deletion
+        self.confirm_delete_page = false;
         match self.current_view {
-            View::Command => self.current_view = View::Explorer,
+            View::Command => self.current_view = View::History,
+            View::History => self.current_view = View::Explorer,
             View::WatchList => self.current_view = View::Command,
             View::Explorer => self.current_view = View::WatchList,
             View::ConfirmCommand => self.current_view = View::Command,
+            View::Chart => self.current_view = View::WatchList,
+            View::Spectrum => self.current_view = View::WatchList,
+            View::Image => self.current_view = View::WatchList,
         }
     }
 }
@@ -101,6 +500,10 @@ pub enum View {
     ConfirmCommand,
     WatchList,
     Explorer,
+    Chart,
+    Spectrum,
+    Image,
+    History,
 }
 
 impl fmt::Display for View {
@@ -110,6 +513,10 @@ impl fmt::Display for View {
             View::ConfirmCommand => write!(f, "ConfirmCommand"),
             View::WatchList => write!(f, "WatchList"),
             View::Explorer => write!(f, "Explorer"),
+            View::Chart => write!(f, "Chart"),
+            View::Spectrum => write!(f, "Spectrum"),
+            View::Image => write!(f, "Image"),
+            View::History => write!(f, "History"),
         }
     }
 }
@@ -119,6 +526,10 @@ pub enum ViewType<'a> {
     WatchList(ViewWatchList),
     Command(ViewCommand),
     ConfirmCommand(ViewConfirmCommand),
+    Chart(ViewChart),
+    Spectrum(ViewSpectrum),
+    Image(ViewImage),
+    History(ViewHistory),
 }
 
 // The views are stored in a hashmap.
@@ -130,6 +541,10 @@ impl<'a> fmt::Display for ViewType<'a> {
             ViewType::WatchList(_) => write!(f, "Watchlist"),
             ViewType::Command(_) => write!(f, "Command"),
             ViewType::ConfirmCommand(_) => write!(f, "Popup"),
+            ViewType::Chart(_) => write!(f, "Chart"),
+            ViewType::Spectrum(_) => write!(f, "Spectrum"),
+            ViewType::Image(_) => write!(f, "Image"),
+            ViewType::History(_) => write!(f, "History"),
         }
     }
 }
@@ -140,7 +555,13 @@ impl From<&ViewType<'_>> for usize {
             ViewType::Explorer(_) => 0,
             ViewType::WatchList(_) => 1,
             ViewType::Command(_) => 2,
-            ViewType::ConfirmCommand(_) => 3,
+            // The confirmation pops up over the command tab
+            ViewType::ConfirmCommand(_) => 2,
+            // The chart, spectrum and image are part of the watchlist tab
+            ViewType::Chart(_) => 1,
+            ViewType::Spectrum(_) => 1,
+            ViewType::Image(_) => 1,
+            ViewType::History(_) => 3,
         }
     }
 }
@@ -152,6 +573,10 @@ impl From<ViewType<'_>> for View {
             ViewType::WatchList(_) => View::WatchList,
             ViewType::Command(_) => View::Command,
             ViewType::ConfirmCommand(_) => View::ConfirmCommand,
+            ViewType::Chart(_) => View::Chart,
+            ViewType::Spectrum(_) => View::Spectrum,
+            ViewType::Image(_) => View::Image,
+            ViewType::History(_) => View::History,
         }
     }
 }
@@ -163,6 +588,10 @@ impl From<&ViewType<'_>> for View {
             ViewType::WatchList(_) => View::WatchList,
             ViewType::Command(_) => View::Command,
             ViewType::ConfirmCommand(_) => View::ConfirmCommand,
+            ViewType::Chart(_) => View::Chart,
+            ViewType::Spectrum(_) => View::Spectrum,
+            ViewType::Image(_) => View::Image,
+            ViewType::History(_) => View::History,
         }
     }
 }
@@ -264,31 +693,23 @@ pub trait Draw {
             })
             .collect();
 
-        let left_rows: Vec<Row> = rows.iter().take(3).cloned().collect();
-        let left_table = Table::new(left_rows)
-            .style(Style::default().fg(Color::White))
-            .widths(&[
-                Constraint::Length(10),
-                Constraint::Length(15),
-                Constraint::Length(15),
-            ])
-            .column_spacing(1);
-        f.render_widget(left_table, chunks[0]);
-
-        let right_rows: Vec<Row> = rows.iter().skip(3).cloned().collect();
-        let right_table = Table::new(right_rows)
-            .style(Style::default().fg(Color::White))
-            .widths(&[
-                Constraint::Length(10),
-                Constraint::Length(15),
-                Constraint::Length(15),
-            ])
-            .column_spacing(1);
-        f.render_widget(right_table, chunks[1]);
+        // Fill the columns top to bottom
+        let rows_per_column = (area.height.saturating_sub(2) as usize).max(1);
+        for (column_rows, chunk) in rows.chunks(rows_per_column).zip(chunks.iter()) {
+            let table = Table::new(column_rows.to_vec())
+                .style(Style::default().fg(Color::White))
+                .widths(&[
+                    Constraint::Length(10),
+                    Constraint::Length(15),
+                    Constraint::Length(15),
+                ])
+                .column_spacing(1);
+            f.render_widget(table, *chunk);
+        }
     }
 
     fn draw_tabs<B: Backend>(&self, f: &mut Frame<B>, area: Rect, tab_index: usize) {
-        let tab_titles = ["Explorer", "Watchlist", "Command"]
+        let tab_titles = ["Explorer", "Watchlist", "Command", "History"]
             .iter()
             .cloned()
             .map(Line::from)
@@ -349,16 +770,18 @@ pub trait Draw {
         tab_index: usize,
     ) {
         let size = f.size();
+        let alert_lines = shared_view_state.alerts.unacknowledged().len() as u16;
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(0)
             .constraints(
                 [
-                    Constraint::Length(2), // TANGO HOST, Repo, version, name
-                    Constraint::Length(6), // Instructions
-                    Constraint::Length(3), // Tabs
-                    Constraint::Min(2),    // Explorer
+                    Constraint::Length(2),                  // TANGO HOST, Repo, version, name
+                    Constraint::Length(6),                  // Instructions
+                    Constraint::Length(alert_lines.min(5)), // Alerts
+                    Constraint::Length(3),                  // Tabs
+                    Constraint::Min(2),                     // Explorer
                 ]
                 .as_ref(),
             )
@@ -366,7 +789,114 @@ pub trait Draw {
 
         self.draw_header(f, chunks[0], shared_view_state);
         self.draw_menu(f, chunks[1], shared_view_state);
-        self.draw_tabs(f, chunks[2], tab_index);
-        self.draw_body(f, chunks[3], shared_view_state);
+        if alert_lines > 0 {
+            self.draw_alert_banner(f, chunks[2], shared_view_state);
+        }
+        self.draw_tabs(f, chunks[3], tab_index);
+        self.draw_body(f, chunks[4], shared_view_state);
+    }
+
+    // Fired alerts that have not been acknowledged with CTRL+a
+    fn draw_alert_banner<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let lines: Vec<Line> = shared_view_state
+            .alerts
+            .unacknowledged()
+            .iter()
+            .map(|alert| {
+                let fired_at: DateTime<Local> = alert.fired_at.into();
+                Line::from(format!(
+                    " ALERT {} {}: {}  (CTRL+a to acknowledge)",
+                    fired_at.format("%H:%M:%S"),
+                    alert.name,
+                    alert.condition
+                ))
+            })
+            .collect();
+        let banner = Paragraph::new(lines).style(
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        );
+        f.render_widget(banner, area);
+    }
+}
+
+#[cfg(test)]
+mod views_tests {
+    use super::*;
+
+    fn attr_names<T>(attr_map: Option<&BTreeMap<AttributeName, T>>) -> Vec<AttributeName> {
+        attr_map
+            .map(|attr_map| attr_map.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_delete_page_prunes() {
+        let (tx, _rx) = mpsc::channel();
+        let mut shared_view_state = SharedViewState::new(tx);
+        let kept = ("a/b/c".to_string(), "kept".to_string());
+        let dropped = ("a/b/c".to_string(), "dropped".to_string());
+        shared_view_state.add_watch_entry(kept.0.clone(), kept.1.clone());
+        assert!(shared_view_state.add_page("Other"));
+        shared_view_state.add_watch_entry(kept.0.clone(), kept.1.clone());
+        shared_view_state.add_watch_entry(dropped.0.clone(), dropped.1.clone());
+        for (device_name, attr_name) in [&kept, &dropped] {
+            shared_view_state
+                .user_limits
+                .entry(device_name.clone())
+                .or_default()
+                .insert(attr_name.clone(), AttributeLimits::default());
+            shared_view_state
+                .trends
+                .entry(device_name.clone())
+                .or_default()
+                .insert(attr_name.clone(), Trend::new(10));
+            shared_view_state
+                .statistics
+                .entry(device_name.clone())
+                .or_default()
+                .insert(
+                    attr_name.clone(),
+                    AttributeStatistics::new(SystemTime::now()),
+                );
+            shared_view_state.toggle_paused(device_name.clone(), attr_name.clone());
+        }
+
+        assert!(shared_view_state.delete_page());
+        assert_eq!(shared_view_state.pages.len(), 1);
+        let device_name = kept.0.as_str();
+        assert_eq!(
+            attr_names(
+                shared_view_state
+                    .watch_list
+                    .lock()
+                    .unwrap()
+                    .get(device_name)
+            ),
+            vec!["kept"]
+        );
+        assert_eq!(
+            attr_names(shared_view_state.user_limits.get(device_name)),
+            vec!["kept"]
+        );
+        assert_eq!(
+            attr_names(shared_view_state.trends.get(device_name)),
+            vec!["kept"]
+        );
+        assert_eq!(
+            attr_names(shared_view_state.statistics.get(device_name)),
+            vec!["kept"]
+        );
+        assert!(shared_view_state.is_paused(&kept.0, &kept.1));
+        assert!(!shared_view_state.is_paused(&dropped.0, &dropped.1));
+        // The last page stays
+        assert!(!shared_view_state.delete_page());
     }
 }
@@ -0,0 +1,320 @@
+use crate::trend::bounds;
+use crate::views::{Draw, MenuOption, SharedViewState, View};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    symbols,
+    text::{Line, Span},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
+    Frame,
+};
+use std::convert::From;
+use std::time::SystemTime;
+
+const WINDOWS_SECS: [f64; 6] = [10.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+const CURSOR_STEPS: f64 = 50.0;
+const SERIES_COLORS: [Color; 6] = [
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::LightRed,
+    Color::LightBlue,
+];
+
+#[derive(Debug)]
+pub struct ViewChart {
+    window_ix: usize,
+    autoscale: bool,
+    y_bounds: [f64; 2],
+    paused_at: Option<SystemTime>,
+    // Seconds from the start of the window
+    cursor: Option<f64>,
+}
+
+impl Default for ViewChart {
+    fn default() -> Self {
+        ViewChart::new()
+    }
+}
+
+impl ViewChart {
+    pub fn new() -> ViewChart {
+        ViewChart {
+            window_ix: 2,
+            autoscale: true,
+            y_bounds: [0.0, 1.0],
+            paused_at: None,
+            cursor: None,
+        }
+    }
+
+    fn window_secs(&self) -> f64 {
+        WINDOWS_SECS[self.window_ix]
+    }
+
+    fn end_time(&self) -> SystemTime {
+        self.paused_at.unwrap_or_else(SystemTime::now)
+    }
+
+    fn display_window(window_secs: f64) -> String {
+        if window_secs < 60.0 {
+            format!("{}s", window_secs)
+        } else if window_secs < 3600.0 {
+            format!("{}m", window_secs / 60.0)
+        } else {
+            format!("{}h", window_secs / 3600.0)
+        }
+    }
+
+    // The plotted series in the current window, in the order they were selected
+    fn series(&self, shared_view_state: &SharedViewState) -> Vec<(String, Vec<(f64, f64)>)> {
+        let end = self.end_time();
+        shared_view_state
+            .chart_attributes
+            .iter()
+            .map(|(device_name, attr_name)| {
+                let points = shared_view_state
+                    .trends
+                    .get(device_name)
+                    .and_then(|trend_map| trend_map.get(attr_name))
+                    .map(|trend| trend.window(end, self.window_secs()))
+                    .unwrap_or_default();
+                (format!("{}/{}", device_name, attr_name), points)
+            })
+            .collect()
+    }
+
+    fn fit_y_bounds(series: &[(String, Vec<(f64, f64)>)]) -> [f64; 2] {
+        let values: Vec<f64> = series
+            .iter()
+            .flat_map(|(_, points)| points.iter().map(|(_, y)| *y))
+            .collect();
+        let (min, max) = bounds(&values);
+        if !min.is_finite() || !max.is_finite() {
+            return [0.0, 1.0];
+        }
+        let padding = if max - min > f64::EPSILON {
+            (max - min) * 0.05
+        } else {
+            min.abs().max(1.0) * 0.05
+        };
+        [min - padding, max + padding]
+    }
+
+    fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
+        let window_secs = self.window_secs();
+        let cursor_step = window_secs / CURSOR_STEPS;
+        match key_event.code {
+            KeyCode::Char('+') if self.window_ix + 1 < WINDOWS_SECS.len() => {
+                self.window_ix += 1;
+            }
+            KeyCode::Char('-') if self.window_ix > 0 => {
+                self.window_ix -= 1;
+            }
+            KeyCode::Char('a') => {
+                if self.autoscale {
+                    // Hold the bounds currently on screen
+                    self.y_bounds = ViewChart::fit_y_bounds(&self.series(shared_view_state));
+                }
+                self.autoscale = !self.autoscale;
+            }
+            KeyCode::Char('p') => {
+                self.paused_at = match self.paused_at {
+                    Some(_) => None,
+                    None => Some(SystemTime::now()),
+                };
+            }
+            KeyCode::Left => {
+                let cursor = self.cursor.unwrap_or(window_secs);
+                self.cursor = Some((cursor - cursor_step).max(0.0));
+            }
+            KeyCode::Right => {
+                let cursor = self.cursor.unwrap_or(0.0);
+                self.cursor = Some((cursor + cursor_step).min(window_secs));
+            }
+            KeyCode::Char('x') => {
+                self.cursor = None;
+            }
+            KeyCode::Backspace => {
+                shared_view_state.current_view = View::WatchList;
+            }
+            _ => {}
+        }
+        if let Some(cursor) = self.cursor {
+            self.cursor = Some(cursor.min(self.window_secs()));
+        }
+    }
+
+    fn draw_chart<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Min(5),
+                    Constraint::Length(shared_view_state.chart_attributes.len() as u16 + 2),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        let window_secs = self.window_secs();
+        let series = self.series(shared_view_state);
+        let y_bounds = if self.autoscale {
+            ViewChart::fit_y_bounds(&series)
+        } else {
+            self.y_bounds
+        };
+        let cursor_line = self
+            .cursor
+            .map(|cursor| vec![(cursor, y_bounds[0]), (cursor, y_bounds[1])])
+            .unwrap_or_default();
+
+        let mut datasets: Vec<Dataset> = series
+            .iter()
+            .enumerate()
+            .map(|(ix, (name, points))| {
+                Dataset::default()
+                    .name(name.clone())
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(SERIES_COLORS[ix % SERIES_COLORS.len()]))
+                    .data(points)
+            })
+            .collect();
+        if !cursor_line.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .marker(symbols::Marker::Dot)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(Color::DarkGray))
+                    .data(&cursor_line),
+            );
+        }
+
+        let mut title = format!(
+            " Trend - window {} - autoscale {}",
+            ViewChart::display_window(window_secs),
+            if self.autoscale { "on" } else { "off" }
+        );
+        if self.paused_at.is_some() {
+            title.push_str(" - PAUSED");
+        }
+        let x_labels = vec![
+            Span::raw(format!("-{}", ViewChart::display_window(window_secs))),
+            Span::raw(format!("-{}", ViewChart::display_window(window_secs / 2.0))),
+            Span::raw("now"),
+        ];
+        let y_labels = vec![
+            Span::raw(format!("{:.3}", y_bounds[0])),
+            Span::raw(format!("{:.3}", (y_bounds[0] + y_bounds[1]) / 2.0)),
+            Span::raw(format!("{:.3}", y_bounds[1])),
+        ];
+        let chart = Chart::new(datasets)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .x_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([0.0, window_secs])
+                    .labels(x_labels),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds(y_bounds)
+                    .labels(y_labels),
+            );
+        f.render_widget(chart, chunks[0]);
+
+        // The value of each series under the cursor, or the latest value
+        let end = self.end_time();
+        let readout: Vec<Line> = shared_view_state
+            .chart_attributes
+            .iter()
+            .enumerate()
+            .map(|(ix, (device_name, attr_name))| {
+                let trend = shared_view_state
+                    .trends
+                    .get(device_name)
+                    .and_then(|trend_map| trend_map.get(attr_name));
+                let value = match (trend, self.cursor) {
+                    (Some(trend), Some(cursor)) => trend.value_at(end, window_secs, cursor),
+                    (Some(trend), None) => trend.last(),
+                    (None, _) => None,
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{}/{}: ", device_name, attr_name),
+                        Style::default().fg(SERIES_COLORS[ix % SERIES_COLORS.len()]),
+                    ),
+                    Span::raw(value.map(|v| v.to_string()).unwrap_or_default()),
+                ])
+            })
+            .collect();
+        let readout_title = match self.cursor {
+            Some(cursor) => format!(" Cursor at -{:.1}s ", window_secs - cursor),
+            None => " Latest ".to_string(),
+        };
+        let readout = Paragraph::new(readout)
+            .block(Block::default().borders(Borders::ALL).title(readout_title));
+        f.render_widget(readout, chunks[1]);
+    }
+}
+
+impl Draw for ViewChart {
+    fn get_view_menu_items(&self, _shared_view_state: &mut SharedViewState) -> Vec<MenuOption> {
+        vec![
+            MenuOption {
+                key: "+,-".to_string(),
+                description: "Time window".to_string(),
+            },
+            MenuOption {
+                key: "a".to_string(),
+                description: "Autoscale".to_string(),
+            },
+            MenuOption {
+                key: "p".to_string(),
+                description: "Pause".to_string(),
+            },
+            MenuOption {
+                key: "←,→,x".to_string(),
+                description: "Cursor".to_string(),
+            },
+            MenuOption {
+                key: "BACKSPACE".to_string(),
+                description: "Watchlist".to_string(),
+            },
+        ]
+    }
+
+    fn draw_body<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        self.draw_chart(f, area, shared_view_state);
+    }
+
+    fn handle_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) -> usize {
+        self.handle_event(key_event, shared_view_state);
+        1
+    }
+}
+
+impl From<ViewChart> for usize {
+    fn from(_item: ViewChart) -> usize {
+        1
+    }
+}
@@ -1,9 +1,13 @@
 use crate::{
+    audit_log::{AuditAction, AuditEntry},
+    line_editor::{push_history, EditorAction, LineEditor},
     tango_utils,
-    views::{Draw, SharedViewState},
+    value_parser::{command_input_hint, format_command_data},
+    views::{watchlist::ErrorDetail, Draw, SharedViewState},
     Event,
 };
-use crossterm::event::{KeyCode, KeyEvent};
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use log::error;
 use ratatui::{
     backend::Backend,
@@ -11,33 +15,190 @@ use ratatui::{
     layout::Direction,
     layout::Layout,
     layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::{collections::BTreeMap, convert::From, sync::mpsc, thread};
-use tango_controls_client_sys::types::CmdArgType;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::From,
+    sync::mpsc,
+    thread,
+};
+use tango_controls_client_sys::error::TangoError;
+use tango_controls_client_sys::types::{CmdArgType, CommandData};
 
 use super::{MenuOption, View};
 
-#[derive(Debug, Default)]
+// The input box grows with the lines of the parameter up to this many
+const MAX_INPUT_LINES: usize = 6;
+
+// The reasons Tango gives when the device did not answer in time
+const TIMEOUT_REASONS: [&str; 2] = ["API_DeviceTimedOut", "API_CommandTimedOut"];
+
+#[derive(Debug, Default, PartialEq, Eq)]
 enum Focus {
     #[default]
     Input,
+    History,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandStatus {
+    Pending,
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+impl fmt::Display for CommandStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandStatus::Pending => write!(f, "Pending"),
+            CommandStatus::Succeeded => write!(f, "Succeeded"),
+            CommandStatus::Failed => write!(f, "Failed"),
+            CommandStatus::TimedOut => write!(f, "Timed out"),
+        }
+    }
+}
+
+impl CommandStatus {
+    // The name in the audit log
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandStatus::Pending => "pending",
+            CommandStatus::Succeeded => "succeeded",
+            CommandStatus::Failed => "failed",
+            CommandStatus::TimedOut => "timed_out",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<CommandStatus> {
+        [
+            CommandStatus::Pending,
+            CommandStatus::Succeeded,
+            CommandStatus::Failed,
+            CommandStatus::TimedOut,
+        ]
+        .into_iter()
+        .find(|status| status.name() == name)
+    }
+
+    pub fn from_error(err: &CommandError) -> CommandStatus {
+        if err.timed_out() {
+            CommandStatus::TimedOut
+        } else {
+            CommandStatus::Failed
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            CommandStatus::Pending => Color::Yellow,
+            CommandStatus::Succeeded => Color::Green,
+            CommandStatus::Failed => Color::Red,
+            CommandStatus::TimedOut => Color::Magenta,
+        }
+    }
+}
+
+// Why a command failed, with every Tango failure for the details panel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandError {
+    pub message: String,
+    pub details: Vec<ErrorDetail>,
+}
+
+impl CommandError {
+    pub fn from_error(err: &(dyn Error + 'static)) -> CommandError {
+        let details = match err.downcast_ref::<TangoError>() {
+            Some(tango_error) => ErrorDetail::from_tango_error(tango_error),
+            None => Vec::new(),
+        };
+        let message = match details.first() {
+            Some(detail) => detail.desc.clone(),
+            None => err.to_string(),
+        };
+        CommandError { message, details }
+    }
+
+    fn timed_out(&self) -> bool {
+        self.details
+            .iter()
+            .any(|detail| TIMEOUT_REASONS.contains(&detail.reason.as_str()))
+    }
+}
+
+// What the command returned, sent back from the thread that executed it
+pub type CommandOutcome = Result<CommandData, CommandError>;
+
+#[derive(Debug, Clone)]
 pub struct ExecutedCommand {
+    pub id: u64,
+    pub device_name: String,
     pub command: String,
+    pub in_type: Option<CmdArgType>,
     pub parameter: String,
-    pub result: String,
-    pub device_name: String,
+    pub status: CommandStatus,
+    pub started: SystemTime,
+    pub finished: Option<SystemTime>,
+    pub output: Option<CommandData>,
+    pub error: Option<CommandError>,
+}
+
+impl ExecutedCommand {
+    pub fn duration(&self) -> Option<Duration> {
+        self.finished?.duration_since(self.started).ok()
+    }
+
+    pub fn finish(&mut self, outcome: CommandOutcome, finished: SystemTime) {
+        self.finished = Some(finished);
+        match outcome {
+            Ok(output) => {
+                self.status = CommandStatus::Succeeded;
+                self.output = Some(output);
+            }
+            Err(err) => {
+                self.status = CommandStatus::from_error(&err);
+                self.error = Some(err);
+            }
+        }
+    }
+
+    // The output in the parameter syntax, or why there is none
+    pub fn result_text(&self) -> String {
+        match (&self.output, &self.error) {
+            (Some(output), _) => format_command_data(output),
+            (None, Some(err)) => err.message.clone(),
+            (None, None) => "In progress".to_string(),
+        }
+    }
+}
+
+pub fn display_duration(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) if duration < Duration::from_secs(1) => {
+            format!("{} ms", duration.as_millis())
+        }
+        Some(duration) => format!("{:.2} s", duration.as_secs_f64()),
+        None => "-".to_string(),
+    }
+}
+
+pub fn display_time(time: SystemTime) -> String {
+    let date_time: DateTime<Local> = time.into();
+    date_time.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
 }
 
 #[derive(Debug)]
 pub struct ExecutedCommands {
-    pub executed_commands: BTreeMap<u128, ExecutedCommand>,
+    // Keyed by the ID, in the order the commands were executed
+    pub executed_commands: BTreeMap<u64, ExecutedCommand>,
+    next_id: u64,
     pub current_command: Option<String>,
     pub current_command_in_type: Option<CmdArgType>,
     pub current_parsed_parameter: Option<String>,
@@ -48,17 +209,10 @@ pub struct ExecutedCommands {
 }
 
 impl ExecutedCommands {
-    pub fn get_millis_since_epoch(&self) -> u128 {
-        let start = SystemTime::now();
-        start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis()
-    }
-
     pub fn new(tx_commands: mpsc::Sender<Event>) -> Self {
         Self {
             executed_commands: BTreeMap::default(),
+            next_id: 1,
             current_command: None,
             current_parameter: None,
             current_device: None,
@@ -69,99 +223,244 @@ impl ExecutedCommands {
         }
     }
 
-    pub fn execute_command(&mut self, device_name: String, command: String, parameter: String) {
-        let seconds_since_epoch = self.get_millis_since_epoch();
+    pub fn execute_command(
+        &mut self,
+        device_name: String,
+        command: String,
+        in_type: Option<CmdArgType>,
+        parameter: String,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
         let execute_command = ExecutedCommand {
+            id,
+            device_name: device_name.clone(),
             command: command.clone(),
+            in_type,
             parameter: parameter.clone(),
-            device_name: device_name.clone(),
-            result: String::from("In Progress"),
+            status: CommandStatus::Pending,
+            started: SystemTime::now(),
+            finished: None,
+            output: None,
+            error: None,
         };
-        self.executed_commands
-            .insert(seconds_since_epoch, execute_command);
+        self.executed_commands.insert(id, execute_command);
 
         let tx_commands = self.tx_commands.clone();
         thread::spawn(move || {
-            let res = match tango_utils::execute_tango_command(
+            let outcome = tango_utils::execute_tango_command(
                 device_name.as_str(),
                 command.as_str(),
                 parameter.as_str(),
-            ) {
-                Ok(command_data) => format!("{:?}", command_data),
-                Err(err) => {
-                    error!("Command Error {}", err);
-                    err.to_string()
-                }
-            };
-            match tx_commands.send(Event::UpdateCommandResult(seconds_since_epoch, res)) {
+            )
+            .map_err(|err| {
+                error!("Command Error {}", err);
+                CommandError::from_error(err.as_ref())
+            });
+            match tx_commands.send(Event::UpdateCommandResult(id, outcome)) {
                 Ok(_) => {}
                 Err(err) => {
                     error!("Could not send result {}", err)
                 }
             }
         });
+        id
+    }
+
+    pub fn finish_command(&mut self, id: u64, outcome: CommandOutcome) {
+        if let Some(executed_command) = self.executed_commands.get_mut(&id) {
+            executed_command.finish(outcome, SystemTime::now());
+        }
+    }
+
+    // The latest first, as listed in the view
+    fn latest(&self, ix: usize) -> Option<&ExecutedCommand> {
+        self.executed_commands.values().rev().nth(ix)
     }
 }
 
 #[derive(Default, Debug)]
 pub struct ViewCommand {
     focus: Focus,
-    input: String,
+    editor: LineEditor,
+    // The parameters entered before, per device and command
+    histories: HashMap<String, Vec<String>>,
+    // The selected entry of the command history, counted from the latest
+    selected: usize,
+    show_details: bool,
 }
 
 impl ViewCommand {
     pub fn new() -> ViewCommand {
         ViewCommand {
             focus: Focus::Input,
-            input: String::from("< Enter parameter >"),
+            editor: LineEditor::default(),
+            histories: HashMap::new(),
+            selected: 0,
+            show_details: false,
         }
     }
 
+    // Recall the parameters entered in earlier sessions
+    pub fn load_histories(&mut self, audit_entries: &[AuditEntry]) {
+        for entry in audit_entries
+            .iter()
+            .filter(|entry| entry.action == AuditAction::Command)
+        {
+            push_history(
+                self.histories
+                    .entry(format!("{}/{}", entry.device_name, entry.name))
+                    .or_default(),
+                &entry.parameter,
+            );
+        }
+    }
+
+    fn history_key(shared_view_state: &SharedViewState) -> Option<String> {
+        match (
+            &shared_view_state.selected_device,
+            &shared_view_state.executed_commands.current_command,
+        ) {
+            (Some(device_name), Some(command)) => Some(format!("{}/{}", device_name, command)),
+            _ => None,
+        }
+    }
+
+    // Make the command of a history entry the current one
+    fn select_command(executed_command: &ExecutedCommand, shared_view_state: &mut SharedViewState) {
+        shared_view_state.selected_device = Some(executed_command.device_name.clone());
+        shared_view_state.executed_commands.current_command =
+            Some(executed_command.command.clone());
+        shared_view_state.executed_commands.current_command_in_type = executed_command.in_type;
+    }
+
     fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
-        let paramater_string = String::from("< Enter parameter >");
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Down if ctrl => {
+                if !shared_view_state
+                    .executed_commands
+                    .executed_commands
+                    .is_empty()
+                {
+                    self.focus = Focus::History;
+                }
+                return;
+            }
+            KeyCode::Up if ctrl => {
+                self.focus = Focus::Input;
+                return;
+            }
+            _ => {}
+        }
+        match self.focus {
+            Focus::Input => self.handle_input_event(key_event, shared_view_state),
+            Focus::History => self.handle_history_event(key_event, shared_view_state),
+        }
+    }
 
-        if shared_view_state.selected_device.is_some()
-            && shared_view_state
-                .executed_commands
-                .current_command
-                .is_some()
+    fn handle_input_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let history_key = match ViewCommand::history_key(shared_view_state) {
+            Some(history_key) => history_key,
+            None => return,
+        };
+        // Results are formatted as arguments, so the last one can be edited and sent again
+        if key_event.code == KeyCode::Char('r')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL)
         {
-            match key_event.code {
-                KeyCode::Left | KeyCode::Right => {
-                    if self.input == paramater_string {
-                        self.input.clear();
-                    }
+            if let Some(output) = shared_view_state
+                .executed_commands
+                .executed_commands
+                .values()
+                .rev()
+                .filter(|executed_command| {
+                    history_key
+                        == format!(
+                            "{}/{}",
+                            executed_command.device_name, executed_command.command
+                        )
+                })
+                .find_map(|executed_command| executed_command.output.as_ref())
+            {
+                self.editor.set_text(&format_command_data(output));
+            }
+            return;
+        }
+        match self.editor.handle_key(key_event) {
+            EditorAction::Submit => {
+                self.editor
+                    .commit_history(self.histories.entry(history_key).or_default());
+                shared_view_state.current_view = View::ConfirmCommand;
+                shared_view_state.executed_commands.current_parameter =
+                    Some(self.editor.text().to_string());
+                shared_view_state.executed_commands.current_device =
+                    shared_view_state.selected_device.clone();
+            }
+            EditorAction::HistoryPrevious => {
+                if let Some(history) = self.histories.get(&history_key) {
+                    self.editor.history_previous(history);
+                }
+            }
+            EditorAction::HistoryNext => {
+                if let Some(history) = self.histories.get(&history_key) {
+                    self.editor.history_next(history);
+                }
+            }
+            EditorAction::Edited | EditorAction::Ignored => {}
+        }
+    }
+
+    fn handle_history_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let executed_commands = &shared_view_state.executed_commands.executed_commands;
+        self.selected = self.selected.min(executed_commands.len().saturating_sub(1));
+        match key_event.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(executed_commands.len().saturating_sub(1))
+            }
+            KeyCode::Enter => self.show_details = !self.show_details,
+            // Edit the parameter before running the command again
+            KeyCode::Char('e') => {
+                if let Some(executed_command) = shared_view_state
+                    .executed_commands
+                    .latest(self.selected)
+                    .cloned()
+                {
+                    ViewCommand::select_command(&executed_command, shared_view_state);
+                    self.editor.set_text(&executed_command.parameter);
+                    self.focus = Focus::Input;
+                }
+            }
+            // Run it again as it was, after the confirmation
+            KeyCode::Char('r') => {
+                if let Some(executed_command) = shared_view_state
+                    .executed_commands
+                    .latest(self.selected)
+                    .cloned()
+                {
+                    ViewCommand::select_command(&executed_command, shared_view_state);
+                    shared_view_state.executed_commands.current_parameter =
+                        Some(executed_command.parameter.clone());
+                    shared_view_state.executed_commands.current_device =
+                        Some(executed_command.device_name.clone());
+                    shared_view_state.current_view = View::ConfirmCommand;
                 }
-                KeyCode::Enter => match self.focus {
-                    Focus::Input => {
-                        if self.input == paramater_string {
-                            self.input.clear();
-                        }
-                        shared_view_state.current_view = View::ConfirmCommand;
-                        shared_view_state.executed_commands.current_parameter =
-                            Some(self.input.clone());
-                        shared_view_state.executed_commands.current_device =
-                            shared_view_state.selected_device.clone();
-                    }
-                },
-                KeyCode::Char(c) => match self.focus {
-                    Focus::Input => {
-                        if self.input == paramater_string {
-                            self.input.clear();
-                        }
-                        self.input.push(c);
-                    }
-                },
-                KeyCode::Backspace => match self.focus {
-                    Focus::Input => {
-                        if self.input == paramater_string {
-                            self.input.clear();
-                        }
-                        self.input.pop();
-                    }
-                },
-                _ => {}
             }
+            _ => {}
+        }
+    }
+
+    pub fn handle_paste(&mut self, text: &str, shared_view_state: &SharedViewState) {
+        if self.focus == Focus::Input && ViewCommand::history_key(shared_view_state).is_some() {
+            self.editor.insert_str(text);
         }
     }
 
@@ -171,9 +470,26 @@ impl ViewCommand {
         area: Rect,
         shared_view_state: &mut SharedViewState,
     ) {
+        let selected_command = match self.focus {
+            Focus::History => shared_view_state.executed_commands.latest(self.selected),
+            Focus::Input => None,
+        };
+        // Arrays can be entered over several lines
+        let input_height = self.editor.lines().len().clamp(1, MAX_INPUT_LINES) as u16 + 2;
+        let details_height = match selected_command {
+            Some(_) if self.show_details => 10,
+            _ => 0,
+        };
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+            .constraints(
+                [
+                    Constraint::Length(input_height),
+                    Constraint::Min(1),
+                    Constraint::Length(details_height),
+                ]
+                .as_ref(),
+            )
             .split(area);
 
         let title = match &shared_view_state.selected_device {
@@ -195,39 +511,65 @@ impl ViewCommand {
                 .block(Block::default().borders(Borders::ALL).title(title));
             input
         } else {
-            let input = Paragraph::new(self.input.as_str())
-                .block(Block::default().borders(Borders::ALL).title(title));
+            // Scroll to keep the cursor inside the box
+            let (row, column) = self.editor.cursor_position();
+            let scroll_y = row.saturating_sub(MAX_INPUT_LINES - 1) as u16;
+            let scroll_x = column.saturating_sub(chunks[0].width.saturating_sub(3) as usize) as u16;
+            let input = if self.editor.is_empty() {
+                let hint = match shared_view_state.executed_commands.current_command_in_type {
+                    Some(in_type) => command_input_hint(in_type),
+                    None => "< Enter parameter >",
+                };
+                Paragraph::new(hint).style(Style::default().fg(Color::DarkGray))
+            } else {
+                Paragraph::new(self.editor.text()).scroll((scroll_y, scroll_x))
+            }
+            .block(Block::default().borders(Borders::ALL).title(title));
             match self.focus {
                 Focus::Input => {
-                    f.set_cursor(chunks[0].x + self.input.len() as u16 + 1, chunks[0].y + 1);
+                    f.set_cursor(
+                        chunks[0].x + 1 + column as u16 - scroll_x,
+                        chunks[0].y + 1 + row as u16 - scroll_y,
+                    );
                 }
+                Focus::History => {}
             };
             input
         };
 
         let mut rows: Vec<Row> = Vec::new();
-        for (_, executed_command) in shared_view_state
+        for executed_command in shared_view_state
             .executed_commands
             .executed_commands
-            .iter()
+            .values()
             .rev()
         {
             rows.push(Row::new(vec![
+                Cell::from(executed_command.id.to_string()),
                 Cell::from(executed_command.device_name.clone()),
                 Cell::from(executed_command.command.clone()),
-                Cell::from(executed_command.result.clone()),
+                Cell::from(executed_command.parameter.replace('\n', " ")),
+                Cell::from(executed_command.status.to_string())
+                    .style(Style::default().fg(executed_command.status.color())),
+                Cell::from(display_duration(executed_command.duration())),
+                Cell::from(executed_command.result_text()),
             ]))
         }
 
-        let size_a = area.width / 3;
-        let size_b = area.width / 3;
-        let size_c = area.width / 3 + 1;
         let widths = vec![
-            Constraint::Length(size_a),
-            Constraint::Length(size_b),
-            Constraint::Length(size_c),
+            Constraint::Length(4),
+            Constraint::Percentage(18),
+            Constraint::Percentage(15),
+            Constraint::Percentage(17),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Min(10),
         ];
 
+        let table_title = match self.focus {
+            Focus::Input => " Commands (CTRL+↓ to select) ",
+            Focus::History => " Commands ",
+        };
         let table = Table::new(rows)
             .style(Style::default().fg(Color::White))
             .block(
@@ -235,24 +577,152 @@ impl ViewCommand {
                     .borders(Borders::ALL)
                     .style(Style::default().fg(Color::White))
                     .border_type(BorderType::Plain)
-                    .title(" Commands"),
+                    .title(table_title),
+            )
+            .header(
+                Row::new(vec![
+                    "#",
+                    "Device",
+                    "Command",
+                    "Parameter",
+                    "Status",
+                    "Duration",
+                    "Result",
+                ])
+                .bottom_margin(1),
             )
-            .header(Row::new(vec!["Device", "Command", "Result"]).bottom_margin(1))
             .widths(&widths)
-            .column_spacing(1);
+            .column_spacing(1)
+            .highlight_style(
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            );
 
+        let mut table_state = TableState::default();
+        if selected_command.is_some() {
+            table_state.select(Some(self.selected));
+        }
         f.render_widget(input, chunks[0]);
-        f.render_widget(table, chunks[1]);
+        f.render_stateful_widget(table, chunks[1], &mut table_state);
+        if let (Some(executed_command), true) = (selected_command, self.show_details) {
+            self.draw_details(f, chunks[2], executed_command);
+        }
+    }
+
+    fn draw_details<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        executed_command: &ExecutedCommand,
+    ) {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    executed_command.status.to_string(),
+                    bold.fg(executed_command.status.color()),
+                ),
+                Span::raw(format!(
+                    " after {}, started {}",
+                    display_duration(executed_command.duration()),
+                    display_time(executed_command.started)
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled("Parameter: ", bold),
+                Span::raw(executed_command.parameter.clone()),
+            ]),
+        ];
+        if let Some(output) = &executed_command.output {
+            lines.push(Line::from(vec![
+                Span::styled("Output: ", bold),
+                Span::raw(format_command_data(output)),
+            ]));
+        }
+        if let Some(err) = &executed_command.error {
+            lines.push(Line::from(vec![
+                Span::styled("Error: ", bold),
+                Span::raw(err.message.clone()),
+            ]));
+            for detail in &err.details {
+                lines.push(Line::from(vec![
+                    Span::styled(detail.reason.clone(), bold),
+                    Span::raw(format!(
+                        ": {} ({}) [{}]",
+                        detail.desc, detail.origin, detail.severity
+                    )),
+                ]));
+            }
+        }
+        let title = format!(
+            " #{} {}/{} ",
+            executed_command.id, executed_command.device_name, executed_command.command
+        );
+        let panel = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(panel, area);
     }
 }
 
 impl Draw for ViewCommand {
     fn get_view_menu_items(&self, _shared_view_state: &mut SharedViewState) -> Vec<MenuOption> {
-        let items = vec![MenuOption {
-            key: "Enter".to_string(),
-            description: "Execute command with paramater".to_string(),
-        }];
-        items
+        match self.focus {
+            Focus::Input => vec![
+                MenuOption {
+                    key: "Enter".to_string(),
+                    description: "Execute command with paramater".to_string(),
+                },
+                MenuOption {
+                    key: "ALT+ENTER".to_string(),
+                    description: "New line".to_string(),
+                },
+                MenuOption {
+                    key: "↑,↓".to_string(),
+                    description: "History".to_string(),
+                },
+                MenuOption {
+                    key: "CTRL+←,→".to_string(),
+                    description: "Move by word".to_string(),
+                },
+                MenuOption {
+                    key: "CTRL+W,U,K".to_string(),
+                    description: "Delete word/line".to_string(),
+                },
+                MenuOption {
+                    key: "CTRL+R".to_string(),
+                    description: "Reuse result".to_string(),
+                },
+                MenuOption {
+                    key: "CTRL+↓".to_string(),
+                    description: "Past commands".to_string(),
+                },
+            ],
+            Focus::History => vec![
+                MenuOption {
+                    key: "↑,↓".to_string(),
+                    description: "Select".to_string(),
+                },
+                MenuOption {
+                    key: "ENTER".to_string(),
+                    description: "Details".to_string(),
+                },
+                MenuOption {
+                    key: "e".to_string(),
+                    description: "Edit and re-run".to_string(),
+                },
+                MenuOption {
+                    key: "r".to_string(),
+                    description: "Re-run".to_string(),
+                },
+                MenuOption {
+                    key: "CTRL+↑".to_string(),
+                    description: "Back to input".to_string(),
+                },
+            ],
+        }
     }
 
     fn draw_body<B: Backend>(
@@ -285,3 +755,65 @@ impl From<ViewCommand> for usize {
         2
     }
 }
+
+#[cfg(test)]
+mod command_tests {
+    use super::*;
+    use tango_controls_client_sys::error::{ErrSeverity, TangoFailure};
+
+    fn executed_command() -> ExecutedCommand {
+        ExecutedCommand {
+            id: 1,
+            device_name: "sys/tg_test/1".to_string(),
+            command: "DevDouble".to_string(),
+            in_type: Some(CmdArgType::DevDouble),
+            parameter: "1.5".to_string(),
+            status: CommandStatus::Pending,
+            started: SystemTime::UNIX_EPOCH,
+            finished: None,
+            output: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_finish_command() {
+        let mut succeeded = executed_command();
+        assert_eq!(succeeded.result_text(), "In progress");
+        succeeded.finish(
+            Ok(CommandData::Double(1.5)),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(12),
+        );
+        assert_eq!(succeeded.status, CommandStatus::Succeeded);
+        assert_eq!(succeeded.duration(), Some(Duration::from_millis(12)));
+        assert_eq!(succeeded.result_text(), "1.5");
+
+        let timeout = TangoError {
+            failures: vec![TangoFailure {
+                desc: "Timeout (3000 mS) exceeded on device sys/tg_test/1 ".to_string(),
+                reason: "API_DeviceTimedOut".to_string(),
+                origin: "Connection::command_inout()".to_string(),
+                severity: ErrSeverity::Err,
+            }],
+        };
+        let mut timed_out = executed_command();
+        timed_out.finish(
+            Err(CommandError::from_error(&timeout)),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(3),
+        );
+        assert_eq!(timed_out.status, CommandStatus::TimedOut);
+        assert_eq!(
+            timed_out.result_text(),
+            "Timeout (3000 mS) exceeded on device sys/tg_test/1"
+        );
+
+        let mut failed = executed_command();
+        let parse_error: Box<dyn Error> = "Missing closing ] at column 1".into();
+        failed.finish(
+            Err(CommandError::from_error(parse_error.as_ref())),
+            SystemTime::UNIX_EPOCH,
+        );
+        assert_eq!(failed.status, CommandStatus::Failed);
+        assert!(failed.error.unwrap().details.is_empty());
+    }
+}
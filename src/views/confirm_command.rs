@@ -1,5 +1,5 @@
 use crate::{
-    tango_utils::parse_command_data,
+    value_parser::{format_command_data, parse_command_data},
     views::{Draw, SharedViewState},
 };
 use crossterm::event::{KeyCode, KeyEvent};
@@ -37,7 +37,7 @@ impl ViewConfirmCommand {
                 match parse_command_data(current_param.as_str(), in_type) {
                     Ok(command_data) => {
                         shared_view_state.executed_commands.current_parsed_parameter =
-                            Some(format!("{:?}", command_data))
+                            Some(format_command_data(&command_data))
                     }
                     Err(err) => {
                         shared_view_state.executed_commands.current_parsed_error =
@@ -56,15 +56,24 @@ impl ViewConfirmCommand {
                     .current_parameter
                     .clone()
                 {
+                    let in_type = shared_view_state.executed_commands.current_command_in_type;
                     shared_view_state
                         .executed_commands
-                        .execute_command(device, command, parameter);
+                        .execute_command(device, command, in_type, parameter);
                 }
             }
         }
     }
 
     fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
+        if shared_view_state.confirm_delete_page {
+            if let KeyCode::Enter | KeyCode::Char('Y') | KeyCode::Char('y') = key_event.code {
+                shared_view_state.delete_page();
+            }
+            shared_view_state.confirm_delete_page = false;
+            shared_view_state.current_view = View::WatchList;
+            return;
+        }
         match key_event.code {
             KeyCode::Enter => {
                 if shared_view_state
@@ -130,6 +139,32 @@ impl ViewConfirmCommand {
         f.render_widget(paragraph, area);
     }
 
+    fn draw_delete_page<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &SharedViewState,
+    ) {
+        let page = shared_view_state.page();
+        let text = vec![
+            Line::from(Span::raw("")),
+            Line::from(format!("Delete page: {}", page.name)),
+            Line::from(""),
+            Line::from(format!("With {} attributes", page.entries.len())),
+        ];
+
+        let paragraph = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                " Confirm (Y)es / (N)o ",
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: true });
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
     fn draw_error<B: Backend>(&self, f: &mut Frame<B>, area: Rect, err: String) {
         let create_block = |title| {
             Block::default().borders(Borders::ALL).title(Span::styled(
@@ -157,6 +192,10 @@ impl Draw for ViewConfirmCommand {
         area: Rect,
         shared_view_state: &mut SharedViewState,
     ) {
+        if shared_view_state.confirm_delete_page {
+            self.draw_delete_page(f, area, shared_view_state);
+            return;
+        }
         self.update_param_values(shared_view_state);
         if let Some(err) = shared_view_state
             .executed_commands
@@ -188,6 +227,6 @@ impl Draw for ViewConfirmCommand {
 
 impl From<ViewConfirmCommand> for usize {
     fn from(_item: ViewConfirmCommand) -> usize {
-        3
+        2
     }
 }
@@ -267,6 +267,7 @@ impl<'a> ViewExplorerHome<'a> {
                     self.stateful_table.select(Some(0));
                 }
             }
+            KeyCode::Char('r') => shared_view_state.refresh_devices = true,
             _ => {}
         }
     }
@@ -354,6 +355,12 @@ impl Draw for ViewExplorerHome<'_> {
             key: "←,↑,→,↓".to_string(),
             description: "Navigate tree".to_string(),
         }];
+        if self.focus == Focus::Left {
+            items.push(MenuOption {
+                key: "r".to_string(),
+                description: "Refresh tree".to_string(),
+            });
+        }
 
         if shared_view_state.selected_device.is_some()
             && self.focus == Focus::Right
@@ -0,0 +1,289 @@
+use crate::audit_log::{AuditAction, AuditEntry};
+use crate::line_editor::{EditorAction, LineEditor};
+use crate::views::command::{display_duration, display_time};
+use crate::views::{Draw, MenuOption, SharedViewState};
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+use std::convert::From;
+
+// The commands executed and attributes written, from this and earlier sessions
+#[derive(Default, Debug)]
+pub struct ViewHistory {
+    searching: bool,
+    query: LineEditor,
+    // Counted from the latest matching entry
+    selected: usize,
+    show_details: bool,
+}
+
+impl ViewHistory {
+    pub fn new() -> ViewHistory {
+        ViewHistory::default()
+    }
+
+    // The entries matching the search, latest first
+    fn matching<'a>(&self, audit_entries: &'a [AuditEntry]) -> Vec<&'a AuditEntry> {
+        audit_entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.matches(self.query.text()))
+            .collect()
+    }
+
+    fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
+        if self.searching {
+            match self.query.handle_key(key_event) {
+                EditorAction::Submit => self.searching = false,
+                EditorAction::Edited => self.selected = 0,
+                _ => {}
+            }
+            return;
+        }
+        let matching = self.matching(&shared_view_state.audit_entries).len();
+        match key_event.code {
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(matching.saturating_sub(1)),
+            KeyCode::Enter => self.show_details = !self.show_details,
+            KeyCode::Char('r') => {
+                shared_view_state.load_audit_entries();
+                self.selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_paste(&mut self, text: &str) {
+        if self.searching {
+            self.query.insert_str(&text.replace('\n', " "));
+            self.selected = 0;
+        }
+    }
+
+    fn draw_history<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let matching = self.matching(&shared_view_state.audit_entries);
+        let selected_entry = matching.get(self.selected.min(matching.len().saturating_sub(1)));
+        let details_height = match selected_entry {
+            Some(_) if self.show_details => 9,
+            _ => 0,
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Min(1),
+                    Constraint::Length(details_height),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        let search_title = match &shared_view_state.audit_log {
+            Some(audit_log) => format!(" Search {} ", audit_log.path.display()),
+            None => " Search (no audit log) ".to_string(),
+        };
+        let search = if self.query.is_empty() && !self.searching {
+            Paragraph::new("< / to search by device, command, user, status or result >")
+                .style(Style::default().fg(Color::DarkGray))
+        } else {
+            Paragraph::new(self.query.text())
+        }
+        .block(Block::default().borders(Borders::ALL).title(search_title));
+        f.render_widget(search, chunks[0]);
+        if self.searching {
+            let (_, column) = self.query.cursor_position();
+            f.set_cursor(chunks[0].x + 1 + column as u16, chunks[0].y + 1);
+        }
+
+        let rows: Vec<Row> = matching
+            .iter()
+            .map(|entry| {
+                let started: DateTime<Local> = entry.started.into();
+                let duration = entry
+                    .finished
+                    .and_then(|finished| finished.duration_since(entry.started).ok());
+                Row::new(vec![
+                    Cell::from(started.format("%Y-%m-%d %H:%M:%S").to_string()),
+                    Cell::from(entry.user.clone()),
+                    Cell::from(entry.action.to_string()),
+                    Cell::from(format!("{}/{}", entry.device_name, entry.name)),
+                    Cell::from(entry.parameter.replace('\n', " ")),
+                    Cell::from(entry.status.to_string())
+                        .style(Style::default().fg(entry.status.color())),
+                    Cell::from(display_duration(duration)),
+                    Cell::from(entry.result.clone()),
+                ])
+            })
+            .collect();
+
+        let widths = vec![
+            Constraint::Length(19),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ];
+        let title = match &shared_view_state.audit_error {
+            Some(err) => format!(" History - {} ", err),
+            None => format!(
+                " History - {} of {} ",
+                matching.len(),
+                shared_view_state.audit_entries.len()
+            ),
+        };
+        let table = Table::new(rows)
+            .style(Style::default().fg(Color::White))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Plain)
+                    .title(title),
+            )
+            .header(
+                Row::new(vec![
+                    "Started",
+                    "User",
+                    "Action",
+                    "Device/Name",
+                    "Parameter",
+                    "Status",
+                    "Duration",
+                    "Result",
+                ])
+                .bottom_margin(1),
+            )
+            .widths(&widths)
+            .column_spacing(1)
+            .highlight_style(
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            );
+        let mut table_state = TableState::default();
+        if selected_entry.is_some() {
+            table_state.select(Some(self.selected.min(matching.len() - 1)));
+        }
+        f.render_stateful_widget(table, chunks[1], &mut table_state);
+
+        if let (Some(entry), true) = (selected_entry, self.show_details) {
+            self.draw_details(f, chunks[2], entry);
+        }
+    }
+
+    fn draw_details<B: Backend>(&self, f: &mut Frame<B>, area: Rect, entry: &AuditEntry) {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let name_label = match entry.action {
+            AuditAction::Command => "Command: ",
+            AuditAction::Write => "Attribute: ",
+        };
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(entry.status.to_string(), bold.fg(entry.status.color())),
+                Span::raw(format!(
+                    " by {}@{} on TANGO_HOST {}",
+                    entry.user, entry.hostname, entry.tango_host
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled(name_label, bold),
+                Span::raw(format!("{}/{}", entry.device_name, entry.name)),
+            ]),
+            Line::from(vec![
+                Span::styled("Parameter: ", bold),
+                Span::raw(entry.parameter.clone()),
+            ]),
+            Line::from(vec![
+                Span::styled("Started: ", bold),
+                Span::raw(display_time(entry.started)),
+                Span::styled("  Finished: ", bold),
+                Span::raw(entry.finished.map(display_time).unwrap_or_default()),
+            ]),
+            Line::from(vec![
+                Span::styled("Result: ", bold),
+                Span::raw(entry.result.clone()),
+            ]),
+        ];
+        let panel = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(" Details "));
+        f.render_widget(panel, area);
+    }
+}
+
+impl Draw for ViewHistory {
+    fn get_view_menu_items(&self, _shared_view_state: &mut SharedViewState) -> Vec<MenuOption> {
+        if self.searching {
+            return vec![
+                MenuOption {
+                    key: "ENTER".to_string(),
+                    description: "Done".to_string(),
+                },
+                MenuOption {
+                    key: "CTRL+U".to_string(),
+                    description: "Clear".to_string(),
+                },
+            ];
+        }
+        vec![
+            MenuOption {
+                key: "/".to_string(),
+                description: "Search".to_string(),
+            },
+            MenuOption {
+                key: "↑,↓".to_string(),
+                description: "Select".to_string(),
+            },
+            MenuOption {
+                key: "ENTER".to_string(),
+                description: "Details".to_string(),
+            },
+            MenuOption {
+                key: "r".to_string(),
+                description: "Reload the log".to_string(),
+            },
+        ]
+    }
+
+    fn draw_body<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        self.draw_history(f, area, shared_view_state);
+    }
+
+    fn handle_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) -> usize {
+        self.handle_event(key_event, shared_view_state);
+        3
+    }
+}
+
+impl From<ViewHistory> for usize {
+    fn from(_item: ViewHistory) -> usize {
+        3
+    }
+}
@@ -0,0 +1,227 @@
+use crate::image_data::{ColorMap, HalfBlockImage, ImageData};
+use crate::views::watchlist::AttributeReading;
+use crate::views::{Draw, MenuOption, SharedViewState, View};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::convert::From;
+
+// The percentage of darkest and brightest pixels ignored by auto-contrast
+const AUTO_CONTRAST_CLIP: f64 = 1.0;
+// The fraction of the clip range moved per key press
+const CLIP_STEP: f64 = 0.05;
+
+#[derive(Debug)]
+pub struct ViewImage {
+    color_map: ColorMap,
+    auto_contrast: bool,
+    clip: (f64, f64),
+    cursor: Option<(usize, usize)>,
+}
+
+impl Default for ViewImage {
+    fn default() -> Self {
+        ViewImage::new()
+    }
+}
+
+impl ViewImage {
+    pub fn new() -> ViewImage {
+        ViewImage {
+            color_map: ColorMap::default(),
+            auto_contrast: true,
+            clip: (0.0, 255.0),
+            cursor: None,
+        }
+    }
+
+    // The latest image of the attribute opened from the watchlist
+    fn image(&self, shared_view_state: &SharedViewState) -> Result<ImageData, String> {
+        let (device_name, attr_name) = shared_view_state
+            .detail_attribute
+            .as_ref()
+            .ok_or_else(|| "No attribute selected".to_string())?;
+        let watch_list = shared_view_state.watch_list.lock().unwrap();
+        let attr_reading = watch_list
+            .get(device_name)
+            .and_then(|attr_map| attr_map.get(attr_name))
+            .ok_or_else(|| "The attribute is no longer watched".to_string())?;
+        match attr_reading {
+            AttributeReading::Value(reading) => match &reading.data {
+                Some(data) => {
+                    ImageData::from_attr_value(data, reading.format, reading.dim_x, reading.dim_y)
+                        .map_err(|err| err.to_string())
+                }
+                None => Err("No value read yet".to_string()),
+            },
+            AttributeReading::Error(err) => Err(err.message.clone()),
+        }
+    }
+
+    fn current_clip(&self, image: &ImageData) -> (f64, f64) {
+        if self.auto_contrast {
+            image.auto_contrast(AUTO_CONTRAST_CLIP)
+        } else {
+            self.clip
+        }
+    }
+
+    fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
+        let image = self.image(shared_view_state).ok();
+        let clip = match &image {
+            Some(image) => self.current_clip(image),
+            None => self.clip,
+        };
+        let clip_step = ((clip.1 - clip.0).abs() * CLIP_STEP).max(f64::EPSILON);
+        let (width, height) = image
+            .as_ref()
+            .map(|image| (image.width, image.height))
+            .unwrap_or_default();
+        let (x, y) = self.cursor.unwrap_or((width / 2, height / 2));
+
+        match key_event.code {
+            KeyCode::Char('m') => self.color_map = self.color_map.next(),
+            KeyCode::Char('a') => {
+                // Continue from the auto-contrast range when switching to manual
+                self.clip = clip;
+                self.auto_contrast = !self.auto_contrast;
+            }
+            KeyCode::Char('[') => self.set_clip((clip.0 - clip_step, clip.1)),
+            KeyCode::Char(']') => self.set_clip(((clip.0 + clip_step).min(clip.1), clip.1)),
+            KeyCode::Char('{') => self.set_clip((clip.0, (clip.1 - clip_step).max(clip.0))),
+            KeyCode::Char('}') => self.set_clip((clip.0, clip.1 + clip_step)),
+            KeyCode::Left => self.cursor = Some((x.saturating_sub(1), y)),
+            KeyCode::Right => self.cursor = Some(((x + 1).min(width.saturating_sub(1)), y)),
+            KeyCode::Up => self.cursor = Some((x, y.saturating_sub(1))),
+            KeyCode::Down => self.cursor = Some((x, (y + 1).min(height.saturating_sub(1)))),
+            KeyCode::Char('x') => self.cursor = None,
+            KeyCode::Backspace => {
+                shared_view_state.current_view = View::WatchList;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_clip(&mut self, clip: (f64, f64)) {
+        self.auto_contrast = false;
+        self.clip = clip;
+    }
+
+    fn draw_image<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let title = match &shared_view_state.detail_attribute {
+            Some((device_name, attr_name)) => format!(" {}/{} ", device_name, attr_name),
+            None => " No attribute selected ".to_string(),
+        };
+        let image = match self.image(shared_view_state) {
+            Ok(image) => image,
+            Err(err) => {
+                let message =
+                    Paragraph::new(err).block(Block::default().borders(Borders::ALL).title(title));
+                f.render_widget(message, area);
+                return;
+            }
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+            .split(area);
+
+        let clip = self.current_clip(&image);
+        let image_block = Block::default().borders(Borders::ALL).title(title);
+        let image_area = image_block.inner(chunks[0]);
+        f.render_widget(image_block, chunks[0]);
+        f.render_widget(
+            HalfBlockImage {
+                image: &image,
+                color_map: self.color_map,
+                clip,
+                cursor: self.cursor,
+            },
+            image_area,
+        );
+
+        let pixel = match self.cursor {
+            Some((x, y)) => format!(
+                "Pixel ({}, {}): {}   ",
+                x,
+                y,
+                image.describe_pixel(x, y).unwrap_or_default()
+            ),
+            None => String::new(),
+        };
+        let readout = Paragraph::new(format!(
+            "{}Size: {}x{}   Scale: 1/{:.1}   Map: {}   Range: {} - {} ({})",
+            pixel,
+            image.width,
+            image.height,
+            image.scale(image_area),
+            self.color_map,
+            clip.0,
+            clip.1,
+            if self.auto_contrast { "auto" } else { "manual" }
+        ))
+        .block(Block::default().borders(Borders::ALL));
+        f.render_widget(readout, chunks[1]);
+    }
+}
+
+impl Draw for ViewImage {
+    fn get_view_menu_items(&self, _shared_view_state: &mut SharedViewState) -> Vec<MenuOption> {
+        vec![
+            MenuOption {
+                key: "m".to_string(),
+                description: format!("Map ({})", self.color_map),
+            },
+            MenuOption {
+                key: "a".to_string(),
+                description: "Auto-contrast".to_string(),
+            },
+            MenuOption {
+                key: "[,],{,}".to_string(),
+                description: "Clip min/max".to_string(),
+            },
+            MenuOption {
+                key: "←,↑,→,↓,x".to_string(),
+                description: "Pixel cursor".to_string(),
+            },
+            MenuOption {
+                key: "BACKSPACE".to_string(),
+                description: "Watchlist".to_string(),
+            },
+        ]
+    }
+
+    fn draw_body<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        self.draw_image(f, area, shared_view_state);
+    }
+
+    fn handle_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) -> usize {
+        self.handle_event(key_event, shared_view_state);
+        1
+    }
+}
+
+impl From<ViewImage> for usize {
+    fn from(_item: ViewImage) -> usize {
+        1
+    }
+}
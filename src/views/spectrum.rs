@@ -0,0 +1,321 @@
+use crate::tango_utils::attr_value_as_f64_array;
+use crate::trend::bounds;
+use crate::views::watchlist::AttributeReading;
+use crate::views::{Draw, MenuOption, SharedViewState, View};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::Span,
+    widgets::{
+        canvas::{Canvas, Line as CanvasLine},
+        Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Row, Table,
+    },
+    Frame,
+};
+use std::convert::From;
+
+const PAGE_SIZE: usize = 20;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum SpectrumDisplay {
+    #[default]
+    Line,
+    Bar,
+    Table,
+}
+
+#[derive(Debug, Default)]
+pub struct ViewSpectrum {
+    display: SpectrumDisplay,
+    table_offset: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpectrumStats {
+    pub length: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl SpectrumStats {
+    pub fn from_values(values: &[f64]) -> SpectrumStats {
+        if values.is_empty() {
+            return SpectrumStats::default();
+        }
+        let (min, max) = bounds(values);
+        SpectrumStats {
+            length: values.len(),
+            min,
+            max,
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+        }
+    }
+}
+
+impl ViewSpectrum {
+    pub fn new() -> ViewSpectrum {
+        ViewSpectrum::default()
+    }
+
+    // The values of the attribute opened from the watchlist
+    fn values(&self, shared_view_state: &SharedViewState) -> Option<Vec<f64>> {
+        let (device_name, attr_name) = shared_view_state.detail_attribute.as_ref()?;
+        let watch_list = shared_view_state.watch_list.lock().unwrap();
+        match watch_list.get(device_name)?.get(attr_name)? {
+            AttributeReading::Value(reading) => {
+                reading.data.as_ref().and_then(attr_value_as_f64_array)
+            }
+            AttributeReading::Error(_) => None,
+        }
+    }
+
+    fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
+        let length = self
+            .values(shared_view_state)
+            .map(|values| values.len())
+            .unwrap_or_default();
+        match key_event.code {
+            KeyCode::Char('l') => self.display = SpectrumDisplay::Line,
+            KeyCode::Char('b') => self.display = SpectrumDisplay::Bar,
+            KeyCode::Char('t') => self.display = SpectrumDisplay::Table,
+            KeyCode::Up => self.table_offset = self.table_offset.saturating_sub(1),
+            KeyCode::Down => self.table_offset += 1,
+            KeyCode::PageUp => self.table_offset = self.table_offset.saturating_sub(PAGE_SIZE),
+            KeyCode::PageDown => self.table_offset += PAGE_SIZE,
+            KeyCode::Home => self.table_offset = 0,
+            KeyCode::End => self.table_offset = length,
+            KeyCode::Backspace => {
+                self.table_offset = 0;
+                shared_view_state.current_view = View::WatchList;
+            }
+            _ => {}
+        }
+        self.table_offset = self.table_offset.min(length.saturating_sub(1));
+    }
+
+    fn draw_line<B: Backend>(&self, f: &mut Frame<B>, area: Rect, values: &[f64]) {
+        let points: Vec<(f64, f64)> = values
+            .iter()
+            .enumerate()
+            .map(|(ix, value)| (ix as f64, *value))
+            .collect();
+        let stats = SpectrumStats::from_values(values);
+        let x_max = (values.len().max(2) - 1) as f64;
+        let datasets = vec![Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::LightGreen))
+            .data(&points)];
+        let chart = Chart::new(datasets)
+            .block(Block::default().borders(Borders::ALL))
+            .x_axis(
+                Axis::default()
+                    .title("Index")
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([0.0, x_max])
+                    .labels(vec![
+                        Span::raw("0"),
+                        Span::raw(format!("{}", values.len() / 2)),
+                        Span::raw(format!("{}", x_max)),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds(ViewSpectrum::y_bounds(&stats))
+                    .labels(vec![
+                        Span::raw(format!("{:.3}", stats.min)),
+                        Span::raw(format!("{:.3}", stats.max)),
+                    ]),
+            );
+        f.render_widget(chart, area);
+    }
+
+    fn draw_bar<B: Backend>(&self, f: &mut Frame<B>, area: Rect, values: &[f64]) {
+        let stats = SpectrumStats::from_values(values);
+        let y_bounds = ViewSpectrum::y_bounds(&stats);
+        // Bars grow from zero, or from the bottom of the plot when zero is out of range
+        let base = 0.0_f64.clamp(y_bounds[0], y_bounds[1]);
+        let canvas = Canvas::default()
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " Index 0 - {}, value {:.3} - {:.3} ",
+                values.len().saturating_sub(1),
+                stats.min,
+                stats.max
+            )))
+            .marker(symbols::Marker::Braille)
+            .x_bounds([-0.5, values.len() as f64 - 0.5])
+            .y_bounds(y_bounds)
+            .paint(|ctx| {
+                for (ix, value) in values.iter().enumerate() {
+                    ctx.draw(&CanvasLine {
+                        x1: ix as f64,
+                        y1: base,
+                        x2: ix as f64,
+                        y2: *value,
+                        color: Color::LightGreen,
+                    });
+                }
+            });
+        f.render_widget(canvas, area);
+    }
+
+    fn draw_table<B: Backend>(&self, f: &mut Frame<B>, area: Rect, values: &[f64]) {
+        let visible_rows = area.height.saturating_sub(4) as usize;
+        let rows: Vec<Row> = values
+            .iter()
+            .enumerate()
+            .skip(self.table_offset)
+            .take(visible_rows)
+            .map(|(ix, value)| Row::new(vec![ix.to_string(), value.to_string()]))
+            .collect();
+        let widths = [Constraint::Length(10), Constraint::Min(10)];
+        let table = Table::new(rows)
+            .style(Style::default().fg(Color::White))
+            .header(
+                Row::new(vec!["Index", "Value"])
+                    .style(Style::default().fg(Color::LightCyan))
+                    .bottom_margin(1),
+            )
+            .block(Block::default().borders(Borders::ALL))
+            .widths(&widths)
+            .column_spacing(1);
+        f.render_widget(table, area);
+    }
+
+    fn y_bounds(stats: &SpectrumStats) -> [f64; 2] {
+        // No finite values to fit, e.g. an uninitialised spectrum of NaN
+        if !stats.min.is_finite() || !stats.max.is_finite() {
+            return [0.0, 1.0];
+        }
+        if stats.length == 0 || stats.max - stats.min <= f64::EPSILON {
+            return [stats.min - 1.0, stats.max + 1.0];
+        }
+        let padding = (stats.max - stats.min) * 0.05;
+        [stats.min - padding, stats.max + padding]
+    }
+
+    fn draw_spectrum<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+            .split(area);
+
+        let title = match &shared_view_state.detail_attribute {
+            Some((device_name, attr_name)) => format!(" {}/{} ", device_name, attr_name),
+            None => " No attribute selected ".to_string(),
+        };
+        let values = match self.values(shared_view_state) {
+            Some(values) => values,
+            None => {
+                let message = Paragraph::new("The attribute has no numeric spectrum value")
+                    .block(Block::default().borders(Borders::ALL).title(title));
+                f.render_widget(message, area);
+                return;
+            }
+        };
+
+        let stats = SpectrumStats::from_values(&values);
+        let summary = Paragraph::new(format!(
+            "Length: {}   Min: {}   Max: {}   Mean: {:.6}",
+            stats.length, stats.min, stats.max, stats.mean
+        ))
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(summary, chunks[0]);
+
+        match self.display {
+            SpectrumDisplay::Line => self.draw_line(f, chunks[1], &values),
+            SpectrumDisplay::Bar => self.draw_bar(f, chunks[1], &values),
+            SpectrumDisplay::Table => self.draw_table(f, chunks[1], &values),
+        }
+    }
+}
+
+impl Draw for ViewSpectrum {
+    fn get_view_menu_items(&self, _shared_view_state: &mut SharedViewState) -> Vec<MenuOption> {
+        let mut items = vec![
+            MenuOption {
+                key: "l,b".to_string(),
+                description: "Line/Bar chart".to_string(),
+            },
+            MenuOption {
+                key: "t".to_string(),
+                description: "Table".to_string(),
+            },
+        ];
+        if self.display == SpectrumDisplay::Table {
+            items.push(MenuOption {
+                key: "PgUp,PgDn".to_string(),
+                description: "Scroll".to_string(),
+            });
+        }
+        items.push(MenuOption {
+            key: "BACKSPACE".to_string(),
+            description: "Watchlist".to_string(),
+        });
+        items
+    }
+
+    fn draw_body<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        self.draw_spectrum(f, area, shared_view_state);
+    }
+
+    fn handle_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) -> usize {
+        self.handle_event(key_event, shared_view_state);
+        1
+    }
+}
+
+impl From<ViewSpectrum> for usize {
+    fn from(_item: ViewSpectrum) -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+mod spectrum_tests {
+    use super::*;
+
+    #[test]
+    fn test_y_bounds() {
+        let empty = SpectrumStats::from_values(&[]);
+        assert_eq!(empty.length, 0);
+        assert_eq!(ViewSpectrum::y_bounds(&empty), [-1.0, 1.0]);
+
+        let constant = SpectrumStats::from_values(&[2.0, 2.0, 2.0]);
+        assert_eq!((constant.min, constant.max, constant.mean), (2.0, 2.0, 2.0));
+        assert_eq!(ViewSpectrum::y_bounds(&constant), [1.0, 3.0]);
+
+        let stats = SpectrumStats::from_values(&[0.0, f64::INFINITY, 10.0]);
+        assert_eq!((stats.min, stats.max), (0.0, 10.0));
+        assert_eq!(ViewSpectrum::y_bounds(&stats), [-0.5, 10.5]);
+
+        for values in [vec![f64::NAN; 3], vec![f64::INFINITY, f64::NEG_INFINITY]] {
+            let stats = SpectrumStats::from_values(&values);
+            assert_eq!(stats.length, values.len());
+            let y_bounds = ViewSpectrum::y_bounds(&stats);
+            assert_eq!(y_bounds, [0.0, 1.0]);
+            // The bar base is clamped into the bounds
+            assert_eq!(0.0_f64.clamp(y_bounds[0], y_bounds[1]), 0.0);
+        }
+    }
+}
@@ -1,63 +1,828 @@
-use crate::tango_utils;
-use crate::views::{Draw, SharedViewState};
-use log::error;
+use crate::derived::{quality_rank, DERIVED_DEVICE};
+use crate::limits::LimitLevel;
+use crate::statistics::AttributeStatistics;
+use crate::tango_utils::{
+    self, attr_value_as_f64, attr_value_as_f64_array, display_attribute_quality,
+    UNREACHABLE_REASONS,
+};
+use crate::value_format::{format_value, value_style, DisplayMode};
+use crate::views::{AttributeName, DeviceName, Draw, MenuOption, SharedViewState, View};
+use chrono::{DateTime, Local, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use log::{error, info};
 use ratatui::{
     backend::Backend,
-    layout::Constraint,
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Row, Table, TableState},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::convert::From;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use tango_controls_client_sys::error::TangoError;
+use tango_controls_client_sys::types::{AttrDataFormat, AttrQuality, AttrValue, AttributeData};
+
+#[derive(Debug, Default, Clone)]
+pub struct Reading {
+    pub value: String,
+    pub data: Option<AttrValue>,
+    pub format: Option<AttrDataFormat>,
+    pub dim_x: usize,
+    pub dim_y: usize,
+    pub quality: Option<AttrQuality>,
+    pub time_stamp: Option<SystemTime>,
+    pub received_time_stamp: Option<SystemTime>,
+}
+
+impl Reading {
+    pub fn is_stale(&self, now: SystemTime, stale_after: Duration) -> bool {
+        match self.time_stamp {
+            Some(time_stamp) => match now.duration_since(time_stamp) {
+                Ok(age) => age > stale_after,
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+}
+
+// Failed reads are retried after a delay that doubles on every failure
+const RETRY_DELAY_MIN: Duration = Duration::from_secs(1);
+const RETRY_DELAY_MAX: Duration = Duration::from_secs(60);
+
+// Tango error reasons of an attribute value that cannot be converted
+const UNSUPPORTED_REASONS: [&str; 3] = [
+    "API_IncompatibleAttrDataType",
+    "API_NotSupported",
+    "API_NotSupportedFeature",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadErrorKind {
+    // Retried with backoff
+    Failed,
+    // The device could not be contacted, retried with backoff
+    Unreachable,
+    // The attribute type cannot be displayed, never retried
+    Unsupported,
+}
+
+impl fmt::Display for ReadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadErrorKind::Failed => write!(f, "ERROR"),
+            ReadErrorKind::Unreachable => write!(f, "UNREACHABLE"),
+            ReadErrorKind::Unsupported => write!(f, "UNSUPPORTED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDetail {
+    pub reason: String,
+    pub desc: String,
+    pub origin: String,
+    pub severity: String,
+}
+
+impl ErrorDetail {
+    // The failures of a Tango error, the first one is the original cause
+    pub fn from_tango_error(err: &TangoError) -> Vec<ErrorDetail> {
+        err.failures
+            .iter()
+            .map(|failure| ErrorDetail {
+                reason: failure.reason.clone(),
+                desc: failure.desc.trim().to_string(),
+                origin: failure.origin.clone(),
+                severity: format!("{:?}", failure.severity),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadError {
+    pub kind: ReadErrorKind,
+    pub message: String,
+    pub details: Vec<ErrorDetail>,
+    // Consecutive failed reads
+    pub attempts: u32,
+    pub retry_at: Option<SystemTime>,
+}
+
+impl ReadError {
+    pub fn new(kind: ReadErrorKind, message: &str) -> ReadError {
+        ReadError {
+            kind,
+            message: message.to_string(),
+            details: Vec::new(),
+            attempts: 1,
+            retry_at: None,
+        }
+    }
+
+    fn from_tango_error(err: &TangoError) -> ReadError {
+        let details = ErrorDetail::from_tango_error(err);
+        let has_reason = |reasons: &[&str]| {
+            details
+                .iter()
+                .any(|detail| reasons.contains(&detail.reason.as_str()))
+        };
+        let kind = if has_reason(&UNREACHABLE_REASONS) {
+            ReadErrorKind::Unreachable
+        } else if has_reason(&UNSUPPORTED_REASONS) {
+            ReadErrorKind::Unsupported
+        } else {
+            ReadErrorKind::Failed
+        };
+        // The first failure is the original cause
+        let message = match details.first() {
+            Some(detail) => detail.desc.clone(),
+            None => err.to_string(),
+        };
+        ReadError {
+            kind,
+            message,
+            details,
+            attempts: 1,
+            retry_at: None,
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub fn retry_delay(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    RETRY_DELAY_MIN.saturating_mul(factor).min(RETRY_DELAY_MAX)
+}
 
 #[derive(Debug, Clone)]
 pub enum AttributeReading {
-    Value(String),
-    Error(String),
+    Value(Reading),
+    Error(ReadError),
 }
 
 impl Default for AttributeReading {
     fn default() -> AttributeReading {
-        AttributeReading::Value(String::from(""))
+        AttributeReading::Value(Reading::default())
     }
 }
 
 impl AttributeReading {
-    pub fn update(&mut self, device_name: &str, attr_name: &str) -> &mut AttributeReading {
-        if let AttributeReading::Value(_) = self {
-            match tango_utils::read_attribute(device_name, attr_name) {
-                Ok(attr_data_option) => match attr_data_option {
-                    // Some(attr_data) => *self = AttributeReading::Value(attr_data.data.to_string()),
-                    Some(attr_data) => {
-                        *self = AttributeReading::Value(format!("{}", attr_data.data))
-                    }
-                    None => {
-                        *self = AttributeReading::Value("Error reading attribute".to_string());
-                    }
-                },
-                Err(err) => {
-                    *self = AttributeReading::Error("Reading of type unsupported".to_string());
+    // Whether the attribute should be read in this poll
+    pub fn is_due(&self, now: SystemTime) -> bool {
+        match self {
+            AttributeReading::Value(_) => true,
+            AttributeReading::Error(err) => match err.retry_at {
+                Some(retry_at) => retry_at <= now,
+                None => false,
+            },
+        }
+    }
+
+    fn set_attribute_data(&mut self, attr_data: AttributeData, now: SystemTime) {
+        *self = AttributeReading::Value(Reading {
+            value: format!("{}", attr_data.data),
+            data: Some(attr_data.data.clone()),
+            format: Some(attr_data.format),
+            dim_x: attr_data.dim_x,
+            dim_y: attr_data.dim_y,
+            quality: Some(attr_data.quality),
+            time_stamp: Some(tango_utils::attribute_time_stamp(&attr_data)),
+            received_time_stamp: Some(now),
+        })
+    }
+
+    // Record a failed read and schedule the next attempt
+    pub fn set_error(&mut self, mut err: ReadError, now: SystemTime) {
+        if let AttributeReading::Error(previous) = self {
+            err.attempts = previous.attempts + 1;
+        }
+        if err.kind != ReadErrorKind::Unsupported {
+            err.retry_at = Some(now + retry_delay(err.attempts));
+        }
+        *self = AttributeReading::Error(err);
+    }
+}
+
+// Read the watched attributes of a device with one request. Attributes in error
+// are only read again once their retry time has passed.
+pub fn update_device_readings(
+    device_name: &str,
+    attr_map: &mut BTreeMap<AttributeName, AttributeReading>,
+) {
+    let now = SystemTime::now();
+    let attr_names: Vec<AttributeName> = attr_map
+        .iter()
+        .filter(|(_, attr_reading)| attr_reading.is_due(now))
+        .map(|(attr_name, _)| attr_name.clone())
+        .collect();
+    if attr_names.is_empty() {
+        return;
+    }
+    let results: Vec<Result<AttributeData, ReadError>> =
+        match tango_utils::read_attributes(device_name, &attr_names) {
+            Ok(results) => results
+                .into_iter()
+                .map(|result| result.map_err(|err| ReadError::from_tango_error(&err)))
+                .collect(),
+            Err(err) => {
+                let mut read_error = ReadError::from_tango_error(&err);
+                read_error.kind = ReadErrorKind::Unreachable;
+                vec![Err(read_error); attr_names.len()]
+            }
+        };
+    for (attr_name, result) in attr_names.iter().zip(results) {
+        if let Some(attr_reading) = attr_map.get_mut(attr_name) {
+            match (result, &attr_reading) {
+                (Ok(attr_data), AttributeReading::Error(previous)) => {
+                    info!(
+                        "{}/{} recovered after {} failed reads",
+                        device_name, attr_name, previous.attempts
+                    );
+                    attr_reading.set_attribute_data(attr_data, now);
+                }
+                (Ok(attr_data), _) => attr_reading.set_attribute_data(attr_data, now),
+                (Err(err), AttributeReading::Error(_)) => attr_reading.set_error(err, now),
+                (Err(err), _) => {
                     error!(
-                        "Reading conversion error for {}/{}: {}",
-                        device_name, attr_name, err
+                        "Reading {}/{} failed ({}): {}",
+                        device_name, attr_name, err.kind, err.message
                     );
+                    attr_reading.set_error(err, now);
                 }
-            };
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampDisplay {
+    #[default]
+    Local,
+    Utc,
+    Relative,
+}
+
+impl TimestampDisplay {
+    pub fn next(&self) -> TimestampDisplay {
+        match self {
+            TimestampDisplay::Local => TimestampDisplay::Utc,
+            TimestampDisplay::Utc => TimestampDisplay::Relative,
+            TimestampDisplay::Relative => TimestampDisplay::Local,
+        }
+    }
+
+    pub fn format(&self, time_stamp: SystemTime, now: SystemTime) -> String {
+        match self {
+            TimestampDisplay::Local => DateTime::<Local>::from(time_stamp)
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            TimestampDisplay::Utc => DateTime::<Utc>::from(time_stamp)
+                .format("%Y-%m-%d %H:%M:%S%.3fZ")
+                .to_string(),
+            TimestampDisplay::Relative => match now.duration_since(time_stamp) {
+                Ok(age) => format!("{} ago", display_age(age)),
+                // The device clock is ahead of ours
+                Err(_) => "0.0s ago".to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for TimestampDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimestampDisplay::Local => write!(f, "Local"),
+            TimestampDisplay::Utc => write!(f, "UTC"),
+            TimestampDisplay::Relative => write!(f, "Age"),
+        }
+    }
+}
+
+pub fn display_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", age.as_secs_f64())
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+fn retry_text(err: &ReadError, now: SystemTime) -> String {
+    match err.retry_at {
+        Some(retry_at) => match retry_at.duration_since(now) {
+            Ok(wait) => format!("retry in {}", display_age(wait)),
+            Err(_) => "retrying".to_string(),
+        },
+        None => "not retried".to_string(),
+    }
+}
+
+pub fn quality_style(quality: Option<AttrQuality>) -> Style {
+    match quality {
+        Some(AttrQuality::ATTR_VALID) | None => Style::default().fg(Color::White),
+        Some(AttrQuality::ATTR_INVALID) => Style::default().fg(Color::DarkGray),
+        Some(AttrQuality::ATTR_ALARM) => Style::default().fg(Color::Red),
+        Some(AttrQuality::ATTR_WARNING) => Style::default().fg(Color::Yellow),
+        Some(AttrQuality::ATTR_CHANGING) => Style::default().fg(Color::LightBlue),
+    }
+}
+
+pub fn limit_style(level: LimitLevel) -> Style {
+    match level {
+        LimitLevel::Normal => Style::default().fg(Color::LightGreen),
+        LimitLevel::Warning => Style::default().fg(Color::Yellow),
+        LimitLevel::Alarm => Style::default().fg(Color::Red),
+    }
+}
+
+const SPARKLINE_WIDTH: u16 = 20;
+const GAUGE_WIDTH: u16 = 12;
+const STATISTIC_WIDTH: u16 = 10;
+
+// Min, max, mean, standard deviation, updates, update rate, changes and errors
+pub fn display_statistics(statistics: &AttributeStatistics, now: SystemTime) -> Vec<String> {
+    let display_number = |number: Option<f64>| match number {
+        Some(number) => format!("{:.4}", number),
+        None => String::new(),
+    };
+    let has_numbers = statistics.count > 0;
+    vec![
+        display_number(has_numbers.then_some(statistics.min)),
+        display_number(has_numbers.then_some(statistics.max)),
+        display_number(statistics.mean()),
+        display_number(statistics.std_dev()),
+        statistics.updates.to_string(),
+        match statistics.update_rate(now) {
+            Some(rate) => format!("{:.2}", rate),
+            None => String::new(),
+        },
+        statistics.changes.to_string(),
+        statistics.errors.to_string(),
+    ]
+}
+
+fn freeze_status(shared_view_state: &SharedViewState) -> Span<'static> {
+    match &shared_view_state.frozen {
+        Some(frozen) => Span::styled(
+            format!(" FROZEN ({} updates buffered)", frozen.buffered_updates),
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+        ),
+        None => Span::raw(""),
+    }
+}
+
+fn snapshot_status(shared_view_state: &SharedViewState) -> Span<'static> {
+    match &shared_view_state.snapshot_status {
+        Some(Ok(path)) => Span::styled(
+            format!(" Snapshot saved to {}", path),
+            Style::default().fg(Color::LightCyan),
+        ),
+        Some(Err(err)) => Span::styled(
+            format!(" Snapshot failed: {}", err),
+            Style::default().fg(Color::Red),
+        ),
+        None => Span::raw(""),
+    }
+}
+
+fn recording_status(shared_view_state: &SharedViewState) -> Span<'static> {
+    match (
+        &shared_view_state.recorder,
+        &shared_view_state.recorder_error,
+    ) {
+        (Some(recorder), _) => Span::styled(
+            format!(
+                "● REC {} ({} lines)",
+                recorder.path().display(),
+                recorder.lines_written
+            ),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        (None, Some(err)) => Span::styled(
+            format!("Recording failed: {}", err),
+            Style::default().fg(Color::Red),
+        ),
+        (None, None) => Span::raw(""),
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Device,
+    Attribute,
+    Value,
+    // Most recently changed first
+    LastChange,
+    // Worst quality first
+    Quality,
+    // Failing reads first
+    Error,
+}
+
+impl SortKey {
+    pub fn next(&self) -> SortKey {
+        match self {
+            SortKey::Device => SortKey::Attribute,
+            SortKey::Attribute => SortKey::Value,
+            SortKey::Value => SortKey::LastChange,
+            SortKey::LastChange => SortKey::Quality,
+            SortKey::Quality => SortKey::Error,
+            SortKey::Error => SortKey::Device,
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortKey::Device => write!(f, "device"),
+            SortKey::Attribute => write!(f, "attribute"),
+            SortKey::Value => write!(f, "value"),
+            SortKey::LastChange => write!(f, "last change"),
+            SortKey::Quality => write!(f, "quality"),
+            SortKey::Error => write!(f, "error"),
+        }
+    }
+}
+
+// The text a row filter is matched against
+fn filter_text(device_name: &str, attr_name: &str, attr_reading: &AttributeReading) -> String {
+    let (value, state) = match attr_reading {
+        AttributeReading::Value(reading) => (
+            reading.value.as_str(),
+            reading
+                .quality
+                .map(display_attribute_quality)
+                .unwrap_or_default(),
+        ),
+        AttributeReading::Error(err) => (err.message.as_str(), err.kind.to_string()),
+    };
+    format!("{}/{} {} {}", device_name, attr_name, value, state).to_lowercase()
+}
+
+// Every word of the filter has to appear in the row, ignoring case
+pub fn matches_filter(
+    filter: &str,
+    device_name: &str,
+    attr_name: &str,
+    attr_reading: &AttributeReading,
+) -> bool {
+    let text = filter_text(device_name, attr_name, attr_reading);
+    filter
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| text.contains(word))
+}
+
+fn compare_values(a: &AttributeReading, b: &AttributeReading) -> Ordering {
+    match (a, b) {
+        (AttributeReading::Value(a), AttributeReading::Value(b)) => {
+            let number = |reading: &Reading| reading.data.as_ref().and_then(attr_value_as_f64);
+            match (number(a), number(b)) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => a.value.cmp(&b.value),
+            }
+        }
+        // Errors go last
+        (AttributeReading::Value(_), AttributeReading::Error(_)) => Ordering::Less,
+        (AttributeReading::Error(_), AttributeReading::Value(_)) => Ordering::Greater,
+        (AttributeReading::Error(a), AttributeReading::Error(b)) => a.message.cmp(&b.message),
+    }
+}
+
+// Higher is worse, errors are worse than any quality
+fn severity(attr_reading: &AttributeReading) -> u8 {
+    match attr_reading {
+        AttributeReading::Value(reading) => reading.quality.map(quality_rank).unwrap_or_default(),
+        AttributeReading::Error(_) => u8::MAX,
+    }
+}
+
+// The layout of a watchlist page
+#[derive(Debug, Default, Clone)]
+pub struct PageLayout {
+    pub selected: Option<usize>,
+    pub sort: SortKey,
+    pub sort_reversed: bool,
+    // Only rows matching every word are shown
+    pub filter: String,
+    pub timestamp_display: TimestampDisplay,
+    pub show_statistics: bool,
+    pub show_statistics_panel: bool,
+    // Rows not shown with the attribute's own format
+    pub display_modes: BTreeMap<(DeviceName, AttributeName), DisplayMode>,
+}
+
+impl PageLayout {
+    // Keep the selection on a row when rows disappear
+    fn clamp_selected(&mut self, entries_count: usize) {
+        self.selected = match self.selected {
+            Some(_) if entries_count == 0 => None,
+            Some(selected) => Some(selected.min(entries_count - 1)),
+            None => None,
         };
-        self
     }
 }
 
+// A page name being typed, for a new page or to rename the current one
+#[derive(Debug, Clone)]
+struct PageNameInput {
+    rename: bool,
+    name: String,
+}
+
 #[derive(Default, Debug)]
 pub struct ViewWatchList {
-    stateful_table: TableState,
+    page_name_input: Option<PageNameInput>,
+    // Typing the filter of the current page
+    editing_filter: bool,
+    // Show the Tango error of the selected attribute when it failed to read
+    show_error_details: bool,
 }
 
 impl ViewWatchList {
     pub fn new() -> ViewWatchList {
         ViewWatchList {
-            stateful_table: TableState::default(),
+            page_name_input: None,
+            editing_filter: false,
+            show_error_details: false,
+        }
+    }
+
+    // The watched attributes of the current page in the order that they are displayed
+    fn visible_entries(
+        &self,
+        shared_view_state: &SharedViewState,
+    ) -> Vec<(DeviceName, AttributeName, AttributeReading)> {
+        let watch_list = shared_view_state.watch_list.lock().unwrap();
+        let frozen = shared_view_state
+            .frozen
+            .as_ref()
+            .map(|frozen| &frozen.readings);
+        let layout = &shared_view_state.page().layout;
+        let mut entries: Vec<(DeviceName, AttributeName, AttributeReading)> = shared_view_state
+            .page()
+            .entries
+            .iter()
+            .map(|(device_name, attr_name)| {
+                // Entries added since the freeze show their latest reading
+                let attr_reading = [frozen, Some(&*watch_list)]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|readings| {
+                        readings
+                            .get(device_name)
+                            .and_then(|attr_map| attr_map.get(attr_name))
+                    })
+                    .cloned()
+                    .unwrap_or_default();
+                (device_name.clone(), attr_name.clone(), attr_reading)
+            })
+            .filter(|(device_name, attr_name, attr_reading)| {
+                matches_filter(&layout.filter, device_name, attr_name, attr_reading)
+            })
+            .collect();
+        let last_changed = |device_name: &str, attr_name: &str| {
+            shared_view_state
+                .statistics
+                .get(device_name)
+                .and_then(|statistics_map| statistics_map.get(attr_name))
+                .and_then(|statistics| statistics.last_changed)
+        };
+        // Ties keep the device and attribute order
+        entries.sort_by(|a, b| {
+            let ordering = match layout.sort {
+                SortKey::Device => Ordering::Equal,
+                SortKey::Attribute => a.1.cmp(&b.1),
+                SortKey::Value => compare_values(&a.2, &b.2),
+                SortKey::LastChange => last_changed(&b.0, &b.1).cmp(&last_changed(&a.0, &a.1)),
+                SortKey::Quality => severity(&b.2).cmp(&severity(&a.2)),
+                SortKey::Error => matches!(b.2, AttributeReading::Error(_))
+                    .cmp(&matches!(a.2, AttributeReading::Error(_))),
+            };
+            if layout.sort_reversed {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        entries
+    }
+
+    fn selected_entry(
+        &self,
+        shared_view_state: &SharedViewState,
+    ) -> Option<(DeviceName, AttributeName)> {
+        self.selected_reading(shared_view_state)
+            .map(|(device_name, attr_name, _)| (device_name, attr_name))
+    }
+
+    fn selected_reading(
+        &self,
+        shared_view_state: &SharedViewState,
+    ) -> Option<(DeviceName, AttributeName, AttributeReading)> {
+        let current_selected = shared_view_state.page().layout.selected?;
+        self.visible_entries(shared_view_state)
+            .into_iter()
+            .nth(current_selected)
+    }
+
+    // Typing a page name, ENTER applies it and an empty name cancels
+    fn handle_page_name_input(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        if let Some(input) = self.page_name_input.as_mut() {
+            match key_event.code {
+                KeyCode::Char(c) => input.name.push(c),
+                KeyCode::Backspace => {
+                    input.name.pop();
+                }
+                KeyCode::Enter => {
+                    let name = input.name.trim().to_string();
+                    let applied = if input.rename {
+                        shared_view_state.rename_page(&name)
+                    } else {
+                        shared_view_state.add_page(&name)
+                    };
+                    // Keep typing when the name is already taken
+                    if applied || name.is_empty() {
+                        self.page_name_input = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Typing the filter narrows the rows straight away, ENTER stops typing
+    fn handle_filter_input(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let layout = &mut shared_view_state.page_mut().layout;
+        match key_event.code {
+            KeyCode::Char(c) => layout.filter.push(c),
+            KeyCode::Backspace => {
+                layout.filter.pop();
+            }
+            KeyCode::Enter => self.editing_filter = false,
+            _ => {}
+        }
+        let entries_count = self.visible_entries(shared_view_state).len();
+        shared_view_state.page_mut().layout.selected = (entries_count > 0).then_some(0);
+    }
+
+    fn handle_event(&mut self, key_event: &KeyEvent, shared_view_state: &mut SharedViewState) {
+        if self.page_name_input.is_some() {
+            self.handle_page_name_input(key_event, shared_view_state);
+            return;
+        }
+        if self.editing_filter {
+            self.handle_filter_input(key_event, shared_view_state);
+            return;
+        }
+        let entries_count = self.visible_entries(shared_view_state).len();
+        let several_pages = shared_view_state.pages.len() > 1;
+        let layout = &mut shared_view_state.page_mut().layout;
+        layout.clamp_selected(entries_count);
+        match key_event.code {
+            KeyCode::Char('t') => {
+                layout.timestamp_display = layout.timestamp_display.next();
+            }
+            KeyCode::Up if entries_count > 0 => match layout.selected {
+                Some(current_selected) if current_selected > 0 => {
+                    layout.selected = Some(current_selected - 1)
+                }
+                _ => layout.selected = Some(entries_count - 1),
+            },
+            KeyCode::Down if entries_count > 0 => match layout.selected {
+                Some(current_selected) if current_selected + 1 < entries_count => {
+                    layout.selected = Some(current_selected + 1)
+                }
+                _ => layout.selected = Some(0),
+            },
+            KeyCode::Char(']') => shared_view_state.next_page(),
+            KeyCode::Char('[') => shared_view_state.previous_page(),
+            KeyCode::Char('n') => {
+                self.page_name_input = Some(PageNameInput {
+                    rename: false,
+                    name: String::new(),
+                })
+            }
+            KeyCode::Char('e') => {
+                self.page_name_input = Some(PageNameInput {
+                    rename: true,
+                    name: shared_view_state.page().name.clone(),
+                })
+            }
+            // Ask before deleting the page, the last one is never deleted
+            KeyCode::Char('x') if several_pages => {
+                shared_view_state.confirm_delete_page = true;
+                shared_view_state.current_view = View::ConfirmCommand;
+            }
+            KeyCode::Char(' ') => {
+                if let Some(entry) = self.selected_entry(shared_view_state) {
+                    let chart_attributes = &mut shared_view_state.chart_attributes;
+                    if let Some(ix) = chart_attributes.iter().position(|e| *e == entry) {
+                        chart_attributes.remove(ix);
+                    } else {
+                        chart_attributes.push(entry);
+                    }
+                }
+            }
+            KeyCode::Enter => match self.selected_reading(shared_view_state) {
+                Some((_, _, AttributeReading::Error(_))) => {
+                    self.show_error_details = !self.show_error_details;
+                }
+                Some((device_name, attr_name, AttributeReading::Value(reading))) => {
+                    let is_image = reading.format == Some(AttrDataFormat::IMAGE)
+                        || matches!(reading.data, Some(AttrValue::DevEncoded(_)));
+                    let is_spectrum = reading
+                        .data
+                        .as_ref()
+                        .and_then(attr_value_as_f64_array)
+                        .is_some();
+                    if is_image {
+                        shared_view_state.detail_attribute = Some((device_name, attr_name));
+                        shared_view_state.current_view = View::Image;
+                    } else if is_spectrum {
+                        shared_view_state.detail_attribute = Some((device_name, attr_name));
+                        shared_view_state.current_view = View::Spectrum;
+                    }
+                }
+                None => {}
+            },
+            KeyCode::Char('r') => shared_view_state.toggle_recording(),
+            KeyCode::Char('p') => shared_view_state.save_snapshot(),
+            KeyCode::Char('h') => shared_view_state.toggle_freeze(),
+            KeyCode::Char('o') => layout.sort = layout.sort.next(),
+            KeyCode::Char('O') => layout.sort_reversed = !layout.sort_reversed,
+            KeyCode::Char('/') => self.editing_filter = true,
+            KeyCode::Char('P') => {
+                if let Some((device_name, attr_name)) = self.selected_entry(shared_view_state) {
+                    if device_name != DERIVED_DEVICE {
+                        shared_view_state.toggle_paused(device_name, attr_name);
+                    }
+                }
+            }
+            // Cycle the number format of the selected row
+            KeyCode::Char('f') => {
+                if let Some(entry) = self.selected_entry(shared_view_state) {
+                    let display_modes = &mut shared_view_state.page_mut().layout.display_modes;
+                    let mode = display_modes
+                        .get(&entry)
+                        .copied()
+                        .unwrap_or_default()
+                        .next();
+                    if mode == DisplayMode::default() {
+                        display_modes.remove(&entry);
+                    } else {
+                        display_modes.insert(entry, mode);
+                    }
+                }
+            }
+            KeyCode::Char('s') => layout.show_statistics = !layout.show_statistics,
+            KeyCode::Char('i') => layout.show_statistics_panel = !layout.show_statistics_panel,
+            // Reset the statistics of the selected attribute, or of all of them
+            KeyCode::Char('z') => match self.selected_entry(shared_view_state) {
+                Some((device_name, attr_name)) => {
+                    if let Some(statistics_map) = shared_view_state.statistics.get_mut(&device_name)
+                    {
+                        statistics_map.remove(&attr_name);
+                    }
+                }
+                None => shared_view_state.statistics.clear(),
+            },
+            KeyCode::Char('c') => {
+                if shared_view_state.chart_attributes.is_empty() {
+                    if let Some(entry) = self.selected_entry(shared_view_state) {
+                        shared_view_state.chart_attributes.push(entry);
+                    }
+                }
+                if !shared_view_state.chart_attributes.is_empty() {
+                    shared_view_state.current_view = View::Chart;
+                }
+            }
+            _ => {}
         }
     }
 
@@ -67,32 +832,167 @@ impl ViewWatchList {
         area: Rect,
         shared_view_state: &mut SharedViewState,
     ) {
-        let header = vec!["Device", "Attribute", "Value"];
+        let entries = self.visible_entries(shared_view_state);
+        shared_view_state
+            .page_mut()
+            .layout
+            .clamp_selected(entries.len());
+        let layout = shared_view_state.page().layout.clone();
+        let header = if layout.show_statistics {
+            vec![
+                "Device",
+                "Attribute",
+                "Value",
+                "Min",
+                "Max",
+                "Mean",
+                "Std dev",
+                "Updates",
+                "Rate/s",
+                "Changes",
+                "Errors",
+            ]
+        } else {
+            vec![
+                "Device",
+                "Attribute",
+                "Value",
+                "Trend",
+                "Range",
+                "Quality",
+                "Timestamp",
+            ]
+        };
         let widths = {
             let size_a = area.width / 6;
             let size_b = area.width / 6;
-            let size_c = area.width - size_a - size_b;
-            vec![
+            let detail_sizes = if layout.show_statistics {
+                vec![STATISTIC_WIDTH; 8]
+            } else {
+                vec![SPARKLINE_WIDTH, GAUGE_WIDTH, 11, 32]
+            };
+            let size_c = area.width.saturating_sub(
+                size_a + size_b + detail_sizes.iter().sum::<u16>() + detail_sizes.len() as u16 + 2,
+            );
+            let mut widths = vec![
                 Constraint::Length(size_a),
                 Constraint::Length(size_b),
                 Constraint::Length(size_c),
-            ]
+            ];
+            widths.extend(detail_sizes.into_iter().map(Constraint::Length));
+            widths
         };
 
+        let now = SystemTime::now();
+        let stale_after = shared_view_state.stale_after;
         let mut table_items: Vec<Row> = Vec::new();
-        let watch_l = &shared_view_state.watch_list.lock().unwrap();
-        for (device_name, attr_map) in watch_l.iter() {
-            for (attr_name, attr_value) in attr_map {
-                let attr_reading = match attr_value {
-                    AttributeReading::Value(val) => val,
-                    AttributeReading::Error(val) => val,
-                };
-                table_items.push(Row::new(vec![
-                    device_name.clone(),
-                    attr_name.clone(),
-                    attr_reading.clone(),
-                ]));
+        for (device_name, attr_name, attr_value) in entries {
+            let charted = shared_view_state
+                .chart_attributes
+                .iter()
+                .any(|(dev, attr)| *dev == device_name && *attr == attr_name);
+            let device_cell = if charted {
+                Cell::from(format!("* {}", device_name))
+            } else {
+                Cell::from(device_name.clone())
+            };
+            let sparkline = shared_view_state
+                .trends
+                .get(&device_name)
+                .and_then(|trend_map| trend_map.get(&attr_name))
+                .map(|trend| trend.sparkline(SPARKLINE_WIDTH as usize))
+                .unwrap_or_default();
+            let statistics_cells = match shared_view_state
+                .statistics
+                .get(&device_name)
+                .and_then(|statistics_map| statistics_map.get(&attr_name))
+            {
+                Some(statistics) => display_statistics(statistics, now)
+                    .into_iter()
+                    .map(Cell::from)
+                    .collect(),
+                None => vec![Cell::from(""); 8],
+            };
+            let paused = shared_view_state.is_paused(&device_name, &attr_name);
+            let (value_cell, detail_cells, row_style) = match attr_value {
+                AttributeReading::Value(reading) => {
+                    let quality = reading
+                        .quality
+                        .map(display_attribute_quality)
+                        .unwrap_or_default();
+                    let time_stamp_cell = match reading.time_stamp {
+                        Some(time_stamp) => {
+                            let time_stamp = layout.timestamp_display.format(time_stamp, now);
+                            if paused {
+                                Cell::from(format!("{} PAUSED", time_stamp))
+                                    .style(Style::default().fg(Color::DarkGray))
+                            } else if reading.is_stale(now, stale_after) {
+                                Cell::from(format!("{} STALE", time_stamp))
+                                    .style(Style::default().fg(Color::Magenta))
+                            } else {
+                                Cell::from(time_stamp)
+                            }
+                        }
+                        None => Cell::from(""),
+                    };
+                    // Colour the value against the alarm and warning limits
+                    let limits = shared_view_state.attribute_limits(&device_name, &attr_name);
+                    let number = reading.data.as_ref().and_then(attr_value_as_f64);
+                    let value = match &reading.data {
+                        Some(data) => format_value(
+                            data,
+                            &shared_view_state.value_format(&device_name, &attr_name),
+                            layout
+                                .display_modes
+                                .get(&(device_name.clone(), attr_name.clone()))
+                                .copied()
+                                .unwrap_or_default(),
+                        ),
+                        None => reading.value.clone(),
+                    };
+                    let value_style = reading.data.as_ref().and_then(value_style);
+                    let value_cell =
+                        match (number.and_then(|number| limits.level(number)), value_style) {
+                            (Some(level), _) => Cell::from(value).style(limit_style(level)),
+                            (None, Some(style)) => Cell::from(value).style(style),
+                            (None, None) => Cell::from(value),
+                        };
+                    let gauge = number
+                        .and_then(|number| limits.gauge(number, GAUGE_WIDTH as usize))
+                        .unwrap_or_default();
+                    (
+                        value_cell,
+                        vec![
+                            Cell::from(sparkline),
+                            Cell::from(gauge),
+                            Cell::from(quality),
+                            time_stamp_cell,
+                        ],
+                        quality_style(reading.quality),
+                    )
+                }
+                AttributeReading::Error(err) => (
+                    Cell::from(err.message.clone()),
+                    vec![
+                        Cell::from(sparkline),
+                        Cell::from(""),
+                        Cell::from(err.kind.to_string()),
+                        Cell::from(if paused {
+                            "PAUSED".to_string()
+                        } else {
+                            retry_text(&err, now)
+                        }),
+                    ],
+                    Style::default().fg(Color::Red),
+                ),
+            };
+            let mut cells = vec![device_cell, Cell::from(attr_name), value_cell];
+            if layout.show_statistics {
+                cells.extend(statistics_cells);
+            } else {
+                cells.extend(detail_cells);
             }
+            table_items.push(Row::new(cells).style(row_style));
         }
 
         let table = Table::new(table_items)
@@ -102,7 +1002,11 @@ impl ViewWatchList {
                     .style(Style::default().fg(Color::LightCyan))
                     .bottom_margin(1),
             )
-            .block(Block::default().title(""))
+            .block(Block::default().title(Line::from(vec![
+                recording_status(shared_view_state),
+                freeze_status(shared_view_state),
+                snapshot_status(shared_view_state),
+            ])))
             .widths(&widths)
             .column_spacing(1)
             .highlight_style(
@@ -113,18 +1017,270 @@ impl ViewWatchList {
             )
             .highlight_symbol(">>");
 
-        f.render_stateful_widget(table, area, &mut self.stateful_table.clone());
+        let mut table_state = TableState::default();
+        table_state.select(layout.selected);
+        f.render_stateful_widget(table, area, &mut table_state);
+    }
+
+    // The page names, or the prompt while a page name is typed
+    fn draw_pages<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &SharedViewState,
+    ) {
+        let line = match &self.page_name_input {
+            Some(input) => Line::from(vec![
+                Span::styled(
+                    if input.rename {
+                        "Rename page: "
+                    } else {
+                        "New page: "
+                    },
+                    Style::default().fg(Color::LightCyan),
+                ),
+                Span::raw(input.name.clone()),
+                Span::styled("█", Style::default().fg(Color::White)),
+            ]),
+            None => {
+                let mut spans = Vec::new();
+                for (page_ix, page) in shared_view_state.pages.iter().enumerate() {
+                    let style = if page_ix == shared_view_state.current_page {
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(Color::White)
+                    };
+                    spans.push(Span::styled(format!(" {} ", page.name), style));
+                    spans.push(Span::styled("│", Style::default().fg(Color::LightCyan)));
+                }
+                let layout = &shared_view_state.page().layout;
+                if layout.sort != SortKey::Device || layout.sort_reversed {
+                    spans.push(Span::raw(format!(
+                        " Sort: {} {}",
+                        layout.sort,
+                        if layout.sort_reversed { "▲" } else { "▼" }
+                    )));
+                }
+                if self.editing_filter || !layout.filter.is_empty() {
+                    spans.push(Span::styled(
+                        format!(" Filter: {}", layout.filter),
+                        Style::default().fg(Color::LightCyan),
+                    ));
+                    if self.editing_filter {
+                        spans.push(Span::styled("█", Style::default().fg(Color::White)));
+                    } else {
+                        spans.push(Span::raw(format!(
+                            " ({} of {})",
+                            self.visible_entries(shared_view_state).len(),
+                            shared_view_state.page().entries.len()
+                        )));
+                    }
+                }
+                Line::from(spans)
+            }
+        };
+        f.render_widget(Paragraph::new(line), area);
+    }
+
+    fn draw_statistics_panel<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+    ) {
+        let (title, text) = match self.selected_entry(shared_view_state) {
+            Some((device_name, attr_name)) => {
+                let title = format!(" Statistics {}/{} ", device_name, attr_name);
+                let statistics = shared_view_state
+                    .statistics
+                    .get(&device_name)
+                    .and_then(|statistics_map| statistics_map.get(&attr_name));
+                let text = match statistics {
+                    Some(statistics) => {
+                        let values = display_statistics(statistics, SystemTime::now());
+                        let since: DateTime<Local> = statistics.since.into();
+                        format!(
+                            "Min: {}   Max: {}   Mean: {}   Std dev: {}\n\
+                             Updates: {}   Rate: {}/s   Changes: {}   Errors: {}   Since: {}",
+                            values[0],
+                            values[1],
+                            values[2],
+                            values[3],
+                            values[4],
+                            values[5],
+                            values[6],
+                            values[7],
+                            since.format("%Y-%m-%d %H:%M:%S")
+                        )
+                    }
+                    None => "No readings yet".to_string(),
+                };
+                (title, text)
+            }
+            None => (
+                " Statistics ".to_string(),
+                "Select an attribute to show its statistics".to_string(),
+            ),
+        };
+        let panel = Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(panel, area);
+    }
+
+    // The error of the selected attribute when the details are shown
+    fn selected_error(&self, shared_view_state: &SharedViewState) -> Option<ReadError> {
+        if !self.show_error_details {
+            return None;
+        }
+        match self.selected_reading(shared_view_state) {
+            Some((_, _, AttributeReading::Error(err))) => Some(err),
+            _ => None,
+        }
+    }
+
+    fn draw_error_panel<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        shared_view_state: &mut SharedViewState,
+        err: &ReadError,
+    ) {
+        let title = match self.selected_entry(shared_view_state) {
+            Some((device_name, attr_name)) => format!(" Error {}/{} ", device_name, attr_name),
+            None => " Error ".to_string(),
+        };
+        let mut lines = vec![Line::from(format!(
+            "{} after {} attempts, {}",
+            err.kind,
+            err.attempts,
+            retry_text(err, SystemTime::now())
+        ))];
+        if err.details.is_empty() {
+            lines.push(Line::from(err.message.clone()));
+        }
+        for detail in &err.details {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    detail.reason.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    ": {} ({}) [{}]",
+                    detail.desc, detail.origin, detail.severity
+                )),
+            ]));
+        }
+        let panel = Paragraph::new(lines)
+            .style(Style::default().fg(Color::Red))
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(panel, area);
     }
 }
 
 impl Draw for ViewWatchList {
+    fn get_view_menu_items(&self, shared_view_state: &mut SharedViewState) -> Vec<MenuOption> {
+        vec![
+            MenuOption {
+                key: "↑,↓".to_string(),
+                description: "Select".to_string(),
+            },
+            MenuOption {
+                key: "t".to_string(),
+                description: format!(
+                    "Time ({})",
+                    shared_view_state.page().layout.timestamp_display
+                ),
+            },
+            MenuOption {
+                key: "[,],n,e,x".to_string(),
+                description: "Pages".to_string(),
+            },
+            MenuOption {
+                key: "SPACE,c".to_string(),
+                description: "Mark/chart".to_string(),
+            },
+            MenuOption {
+                key: "ENTER".to_string(),
+                description: "Open/Error info".to_string(),
+            },
+            MenuOption {
+                key: "f".to_string(),
+                description: "Number format".to_string(),
+            },
+            MenuOption {
+                key: "s,i,z".to_string(),
+                description: "Stats/reset".to_string(),
+            },
+            MenuOption {
+                key: "r,p".to_string(),
+                description: if shared_view_state.recorder.is_some() {
+                    "Stop rec/snap".to_string()
+                } else {
+                    "Record/snapshot".to_string()
+                },
+            },
+            MenuOption {
+                key: "h,P".to_string(),
+                description: if shared_view_state.frozen.is_some() {
+                    "Unfreeze/pause".to_string()
+                } else {
+                    "Freeze/pause".to_string()
+                },
+            },
+            MenuOption {
+                key: "o,O,/".to_string(),
+                description: "Sort/filter".to_string(),
+            },
+        ]
+    }
+
     fn draw_body<B: Backend>(
         &self,
         f: &mut Frame<B>,
         area: Rect,
         shared_view_state: &mut SharedViewState,
     ) {
-        self.draw_table(f, area, shared_view_state);
+        let panel_height = if shared_view_state.page().layout.show_statistics_panel {
+            4
+        } else {
+            0
+        };
+        let selected_error = self.selected_error(shared_view_state);
+        let error_height = match &selected_error {
+            Some(err) => 3 + err.details.len().clamp(1, 6) as u16,
+            None => 0,
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Min(3),
+                    Constraint::Length(error_height),
+                    Constraint::Length(panel_height),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        self.draw_pages(f, chunks[0], shared_view_state);
+        self.draw_table(f, chunks[1], shared_view_state);
+        if let Some(err) = &selected_error {
+            self.draw_error_panel(f, chunks[2], shared_view_state, err);
+        }
+        if panel_height > 0 {
+            self.draw_statistics_panel(f, chunks[3], shared_view_state);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        key_event: &KeyEvent,
+        shared_view_state: &mut SharedViewState,
+    ) -> usize {
+        self.handle_event(key_event, shared_view_state);
+        1
     }
 }
 
@@ -139,3 +1295,96 @@ impl From<ViewWatchList> for usize {
         1
     }
 }
+
+#[cfg(test)]
+mod watchlist_tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(4), Duration::from_secs(8));
+        assert_eq!(retry_delay(7), RETRY_DELAY_MAX);
+        assert_eq!(retry_delay(100), RETRY_DELAY_MAX);
+
+        let now = SystemTime::UNIX_EPOCH;
+        let mut attr_reading = AttributeReading::default();
+        assert!(attr_reading.is_due(now));
+        for attempts in 1..=3 {
+            attr_reading.set_error(ReadError::new(ReadErrorKind::Failed, "timeout"), now);
+            match &attr_reading {
+                AttributeReading::Error(err) => assert_eq!(err.attempts, attempts),
+                _ => panic!("expected an error"),
+            }
+        }
+        assert!(!attr_reading.is_due(now + Duration::from_secs(3)));
+        assert!(attr_reading.is_due(now + Duration::from_secs(4)));
+
+        // Unsupported attributes are not read again
+        attr_reading.set_error(ReadError::new(ReadErrorKind::Unsupported, "DevPipe"), now);
+        assert!(!attr_reading.is_due(now + RETRY_DELAY_MAX));
+    }
+
+    #[test]
+    fn test_clamp_selected() {
+        let mut layout = PageLayout {
+            selected: Some(5),
+            ..PageLayout::default()
+        };
+        layout.clamp_selected(3);
+        assert_eq!(layout.selected, Some(2));
+        layout.clamp_selected(3);
+        assert_eq!(layout.selected, Some(2));
+        layout.clamp_selected(0);
+        assert_eq!(layout.selected, None);
+        layout.clamp_selected(3);
+        assert_eq!(layout.selected, None);
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let alarm = AttributeReading::Value(Reading {
+            value: "12.5".to_string(),
+            data: Some(AttrValue::Double(12.5)),
+            quality: Some(AttrQuality::ATTR_ALARM),
+            ..Reading::default()
+        });
+        let valid = AttributeReading::Value(Reading {
+            value: "3".to_string(),
+            data: Some(AttrValue::Long(3)),
+            quality: Some(AttrQuality::ATTR_VALID),
+            ..Reading::default()
+        });
+        let failed = AttributeReading::Error(ReadError::new(ReadErrorKind::Unreachable, "timeout"));
+
+        assert!(matches_filter(
+            "alarm",
+            "sys/tg_test/1",
+            "double_scalar",
+            &alarm
+        ));
+        assert!(matches_filter(
+            "TG_TEST double",
+            "sys/tg_test/1",
+            "double_scalar",
+            &alarm
+        ));
+        assert!(!matches_filter(
+            "alarm",
+            "sys/tg_test/1",
+            "long_scalar",
+            &valid
+        ));
+        assert!(matches_filter(
+            "unreachable",
+            "sys/tg_test/1",
+            "short_scalar",
+            &failed
+        ));
+        assert!(matches_filter("", "sys/tg_test/1", "long_scalar", &valid));
+
+        assert_eq!(compare_values(&valid, &alarm), Ordering::Less);
+        assert_eq!(compare_values(&failed, &valid), Ordering::Greater);
+        assert!(severity(&failed) > severity(&alarm) && severity(&alarm) > severity(&valid));
+    }
+}
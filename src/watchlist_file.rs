@@ -0,0 +1,258 @@
+use crate::expression::Expression;
+use crate::limits::AttributeLimits;
+use anyhow::anyhow;
+use std::error::Error;
+use std::fs;
+
+// A watchlist file lists one attribute per line as `domain/family/member/attribute`,
+// optionally followed by limits that override the ones configured on the device:
+//
+// # Comments start with a hash
+// sys/tg_test/1/double_scalar min_alarm=-100 max_alarm=100 min_warning=-50 max_warning=50
+// sys/tg_test/1/long_scalar min_value=0 max_value=255
+//
+// Derived attributes are defined as `name = expression`, limits follow a `;`:
+//
+// doubled = sys/tg_test/1/double_scalar * 2
+// gap = abs(sys/motor/1/position - sys/motor/2/position) ; max_warning=0.5 max_alarm=1
+//
+// A `[name]` line starts a named watchlist page, the entries before the first one
+// go on the current page:
+//
+// [Pointing]
+// sys/dish/1/azimuth
+//
+// `*` wildcards expand to every matching exported device and attribute:
+//
+// mid-dish/*/*/State
+// */subarray/*/obsState
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WatchEntry {
+    pub device_name: String,
+    pub attr_name: String,
+    pub limits: AttributeLimits,
+    pub page: Option<String>,
+}
+
+impl WatchEntry {
+    pub fn is_wildcard(&self) -> bool {
+        self.device_name.contains('*') || self.attr_name.contains('*')
+    }
+
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.device_name, self.attr_name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedEntry {
+    pub name: String,
+    pub expression: Expression,
+    pub limits: AttributeLimits,
+    pub page: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WatchlistFile {
+    pub entries: Vec<WatchEntry>,
+    pub derived: Vec<DerivedEntry>,
+}
+
+fn parse_limits<'a>(
+    options: impl Iterator<Item = &'a str>,
+    line_no: usize,
+) -> Result<AttributeLimits, Box<dyn Error>> {
+    let mut limits = AttributeLimits::default();
+    for option in options {
+        let (name, value) = option
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Line {}: expected name=value, got [{}]", line_no, option))?;
+        let value: f64 = value
+            .parse()
+            .map_err(|_| anyhow!("Line {}: [{}] is not a number", line_no, value))?;
+        if !limits.set(name, value) {
+            return Err(anyhow!("Line {}: unknown limit [{}]", line_no, name).into());
+        }
+    }
+    Ok(limits)
+}
+
+fn is_derived_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+// Split `domain/family/member/attribute` into the device and attribute names
+pub fn split_attribute_name(full_name: &str) -> Option<(String, String)> {
+    let (device_name, attr_name) = full_name.rsplit_once('/')?;
+    if device_name.split('/').count() != 3 || attr_name.is_empty() {
+        return None;
+    }
+    Some((device_name.to_string(), attr_name.to_string()))
+}
+
+impl WatchlistFile {
+    pub fn load(path: &str) -> Result<WatchlistFile, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Could not read watchlist file {}: {}", path, err))?;
+        WatchlistFile::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<WatchlistFile, Box<dyn Error>> {
+        let mut watchlist_file = WatchlistFile::default();
+        let mut page: Option<String> = None;
+        for (line_ix, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_no = line_ix + 1;
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                if name.trim().is_empty() {
+                    return Err(anyhow!("Line {}: empty page name", line_no).into());
+                }
+                page = Some(name.trim().to_string());
+                continue;
+            }
+            if let Some((name, definition)) = line
+                .split_once('=')
+                .filter(|(name, _)| is_derived_name(name.trim()))
+            {
+                let (source, options) = definition.split_once(';').unwrap_or((definition, ""));
+                let expression = Expression::parse(source.trim())
+                    .map_err(|err| anyhow!("Line {}: {}", line_no, err))?;
+                watchlist_file.derived.push(DerivedEntry {
+                    name: name.trim().to_string(),
+                    expression,
+                    limits: parse_limits(options.split_whitespace(), line_no)?,
+                    page: page.clone(),
+                });
+                continue;
+            }
+            let mut tokens = line.split_whitespace();
+            let full_name = tokens.next().unwrap_or_default();
+            let (device_name, attr_name) = split_attribute_name(full_name).ok_or_else(|| {
+                anyhow!(
+                    "Line {}: [{}] is not of the form domain/family/member/attribute",
+                    line_no,
+                    full_name
+                )
+            })?;
+            watchlist_file.entries.push(WatchEntry {
+                device_name,
+                attr_name,
+                limits: parse_limits(tokens, line_no)?,
+                page: page.clone(),
+            });
+        }
+        Ok(watchlist_file)
+    }
+}
+
+#[cfg(test)]
+mod watchlist_file_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_watchlist_file() {
+        let content = "
+            # A comment
+            sys/tg_test/1/double_scalar
+            sys/tg_test/1/long_scalar min_alarm=-10 max_warning=5.5
+        ";
+        let watchlist_file = WatchlistFile::parse(content).unwrap();
+        assert_eq!(watchlist_file.entries.len(), 2);
+        assert_eq!(watchlist_file.entries[0].device_name, "sys/tg_test/1");
+        assert_eq!(watchlist_file.entries[0].attr_name, "double_scalar");
+        assert!(watchlist_file.entries[0].limits.is_empty());
+        assert_eq!(watchlist_file.entries[1].limits.min_alarm, Some(-10.0));
+        assert_eq!(watchlist_file.entries[1].limits.max_warning, Some(5.5));
+    }
+
+    #[test]
+    fn test_parse_derived_entries() {
+        let content = "
+            sys/tg_test/1/double_scalar
+            doubled = sys/tg_test/1/double_scalar * 2
+            gap = abs(sys/motor/1/position - sys/motor/2/position) ; max_alarm=1
+        ";
+        let watchlist_file = WatchlistFile::parse(content).unwrap();
+        assert_eq!(watchlist_file.entries.len(), 1);
+        assert_eq!(watchlist_file.derived.len(), 2);
+        assert_eq!(watchlist_file.derived[0].name, "doubled");
+        assert_eq!(
+            watchlist_file.derived[0].expression.source,
+            "sys/tg_test/1/double_scalar * 2"
+        );
+        assert!(watchlist_file.derived[0].limits.is_empty());
+        assert_eq!(
+            watchlist_file.derived[1].expression.inputs(),
+            vec!["sys/motor/1/position", "sys/motor/2/position"]
+        );
+        assert_eq!(watchlist_file.derived[1].limits.max_alarm, Some(1.0));
+
+        let err = WatchlistFile::parse("bad = 1 +").unwrap_err();
+        assert!(err.to_string().starts_with("Line 1: Unexpected end"));
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        let content = "
+            sys/tg_test/1/double_scalar
+            [Pointing]
+            sys/dish/1/azimuth
+            offset = sys/dish/1/azimuth - 180
+            [ CBF health ]
+            sys/cbf/1/state
+        ";
+        let watchlist_file = WatchlistFile::parse(content).unwrap();
+        let pages: Vec<Option<&str>> = watchlist_file
+            .entries
+            .iter()
+            .map(|entry| entry.page.as_deref())
+            .collect();
+        assert_eq!(pages, vec![None, Some("Pointing"), Some("CBF health")]);
+        assert_eq!(watchlist_file.derived[0].page.as_deref(), Some("Pointing"));
+        assert!(!watchlist_file.entries[0].is_wildcard());
+
+        let watchlist_file = WatchlistFile::parse("*/subarray/*/obsState").unwrap();
+        assert!(watchlist_file.entries[0].is_wildcard());
+        assert_eq!(
+            watchlist_file.entries[0].full_name(),
+            "*/subarray/*/obsState"
+        );
+
+        let err = WatchlistFile::parse("[ ]").unwrap_err();
+        assert!(err.to_string().starts_with("Line 1: empty page name"));
+    }
+
+    #[test]
+    fn test_parse_watchlist_file_errors() {
+        let tests = vec![
+            (
+                "sys/tg_test/double_scalar",
+                "Line 1: [sys/tg_test/double_scalar]",
+            ),
+            ("sys/tg_test/1/a min_alarm", "Line 1: expected name=value"),
+            (
+                "\nsys/tg_test/1/a min_alarm=x",
+                "Line 2: [x] is not a number",
+            ),
+            ("sys/tg_test/1/a low=1", "Line 1: unknown limit [low]"),
+        ];
+        for (content, expected_error) in tests {
+            let err = WatchlistFile::parse(content).unwrap_err();
+            assert!(
+                err.to_string().starts_with(expected_error),
+                "{} does not start with {}",
+                err,
+                expected_error
+            );
+        }
+    }
+}